@@ -0,0 +1,289 @@
+//! Analysis passes that flag suspicious playlist contents.
+use std::collections::HashMap;
+
+use crate::{Segment, M3U8};
+
+/// A suspicious pattern in segment URIs
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum UriAnomaly {
+    /// The same URI is used by segments with different sequence numbers
+    DuplicateUri {
+        uri: String,
+        first_sequence: u64,
+        sequence: u64,
+    },
+    /// The number in a segment filename stopped advancing with the media sequence
+    SequenceMismatch {
+        uri: String,
+        sequence: u64,
+        expected: u64,
+        found: u64,
+    },
+    /// A segment is served from a different host than the previous segment
+    HostChange {
+        uri: String,
+        sequence: u64,
+        from: String,
+        to: String,
+    },
+}
+
+/// Duration-weighted distribution of segment bitrates in kbit/s
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitrateReport {
+    pub p50: u64,
+    pub p95: u64,
+    pub max: u64,
+    /// Duration-weighted mean bitrate
+    pub mean: u64,
+    /// Seconds of media that had a known bitrate
+    pub measured_duration: f64,
+    pub segments: usize,
+}
+
+/// Implementation for BitrateReport
+impl BitrateReport {
+    /// Builds a report from (bitrate, duration) samples
+    fn from_samples(mut samples: Vec<(u64, f64)>) -> Option<BitrateReport> {
+        samples.retain(|&(_, duration)| duration > 0.0);
+        let total: f64 = samples.iter().map(|&(_, duration)| duration).sum();
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_by_key(|&(bitrate, _)| bitrate);
+        let percentile = |fraction: f64| {
+            let mut elapsed = 0.0;
+            for &(bitrate, duration) in &samples {
+                elapsed += duration;
+                if elapsed >= fraction * total {
+                    return bitrate;
+                }
+            }
+            samples[samples.len() - 1].0
+        };
+        let weighted: f64 = samples
+            .iter()
+            .map(|&(bitrate, duration)| bitrate as f64 * duration)
+            .sum();
+        Some(BitrateReport {
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: samples[samples.len() - 1].0,
+            mean: (weighted / total).round() as u64,
+            measured_duration: total,
+            segments: samples.len(),
+        })
+    }
+}
+
+/// Returns the host of an absolute URI
+fn host(uri: &str) -> Option<&str> {
+    let (_, rest) = uri.split_once("://")?;
+    rest.split(['/', '?', '#']).next()
+}
+
+/// Returns the last run of digits in the filename of a URI
+fn filename_number(uri: &str) -> Option<u64> {
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    let stem = filename.split('.').next().unwrap_or(filename);
+    let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = stem[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |index| index + 1);
+    stem[start..end].parse().ok()
+}
+
+/// Implementation of analysis passes for M3U8
+impl M3U8 {
+    /// Flags duplicate segment URIs, filename numbers that drift from the media sequence
+    /// and segments that jump between hosts
+    ///
+    /// Filename numbers may be offset from the media sequence; only a change in that
+    /// offset after the first numbered segment is reported.
+    pub fn uri_anomalies(&self) -> Vec<UriAnomaly> {
+        let mut anomalies = Vec::new();
+        let mut seen: HashMap<&str, &Segment> = HashMap::new();
+        let mut offset: Option<i128> = None;
+        let mut previous_host: Option<&str> = None;
+        for segment in &self.segments {
+            // Byte range segments legitimately share a URI
+            if segment.byte_range.is_none() {
+                if let Some(first) = seen.insert(&segment.uri, segment) {
+                    anomalies.push(UriAnomaly::DuplicateUri {
+                        uri: segment.uri.to_string(),
+                        first_sequence: first.sequence,
+                        sequence: segment.sequence,
+                    });
+                }
+            }
+
+            if let Some(found) = filename_number(&segment.uri) {
+                let current = found as i128 - segment.sequence as i128;
+                match offset {
+                    None => offset = Some(current),
+                    Some(offset) if offset != current => {
+                        anomalies.push(UriAnomaly::SequenceMismatch {
+                            uri: segment.uri.to_string(),
+                            sequence: segment.sequence,
+                            expected: (segment.sequence as i128 + offset).max(0) as u64,
+                            found,
+                        });
+                    }
+                    _ => (),
+                }
+            }
+
+            if let Some(host) = host(&segment.uri) {
+                if let Some(from) = previous_host.filter(|&from| from != host) {
+                    anomalies.push(UriAnomaly::HostChange {
+                        uri: segment.uri.to_string(),
+                        sequence: segment.sequence,
+                        from: from.to_string(),
+                        to: host.to_string(),
+                    });
+                }
+                previous_host = Some(host);
+            }
+        }
+        anomalies
+    }
+}
+
+/// Implementation of bitrate reporting for M3U8
+impl M3U8 {
+    /// Reports the bitrate distribution from EXT-X-BITRATE hints, or byte range sizes
+    /// for segments without one
+    pub fn bitrate_report(&self) -> Option<BitrateReport> {
+        self.bitrate_report_with(|segment| segment.byte_range.map(|range| range.length))
+    }
+
+    /// Reports the bitrate distribution using measured segment sizes in bytes where
+    /// no EXT-X-BITRATE hint applies
+    pub fn bitrate_report_with<F>(&self, size_of: F) -> Option<BitrateReport>
+    where
+        F: Fn(&Segment) -> Option<u64>,
+    {
+        let samples = self
+            .segments
+            .iter()
+            .filter(|segment| segment.duration > 0.0)
+            .filter_map(|segment| {
+                let bitrate = segment.bitrate.or_else(|| {
+                    let bits = size_of(segment)? as f64 * 8.0;
+                    Some((bits / segment.duration / 1000.0).round() as u64)
+                })?;
+                Some((bitrate, segment.duration))
+            })
+            .collect();
+        BitrateReport::from_samples(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{BitrateReport, UriAnomaly, M3U8};
+
+    #[test]
+    /// Tests duplicate URIs, drifting filename numbers and host jumps are reported
+    fn it_flags_uri_anomalies() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA-SEQUENCE:100\n\
+            #EXTINF:6,\n\
+            http://cdn-a.example.com/live/seg_1100.ts\n\
+            #EXTINF:6,\n\
+            http://cdn-a.example.com/live/seg_1101.ts\n\
+            #EXTINF:6,\n\
+            http://cdn-b.example.com/live/seg_1102.ts\n\
+            #EXTINF:6,\n\
+            http://cdn-b.example.com/live/seg_1107.ts\n\
+            #EXTINF:6,\n\
+            http://cdn-b.example.com/live/seg_1101.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let anomalies = parsed.uri_anomalies();
+        assert_eq!(
+            anomalies,
+            vec![
+                UriAnomaly::HostChange {
+                    uri: "http://cdn-b.example.com/live/seg_1102.ts".to_string(),
+                    sequence: 102,
+                    from: "cdn-a.example.com".to_string(),
+                    to: "cdn-b.example.com".to_string(),
+                },
+                UriAnomaly::SequenceMismatch {
+                    uri: "http://cdn-b.example.com/live/seg_1107.ts".to_string(),
+                    sequence: 103,
+                    expected: 1103,
+                    found: 1107,
+                },
+                UriAnomaly::SequenceMismatch {
+                    uri: "http://cdn-b.example.com/live/seg_1101.ts".to_string(),
+                    sequence: 104,
+                    expected: 1104,
+                    found: 1101,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    /// Tests reused URIs are flagged unless they are byte ranges of one resource
+    fn it_flags_duplicate_uris() {
+        let body = "#EXTM3U\n\
+            #EXTINF:6,\nintro.ts\n\
+            #EXTINF:6,\n#EXT-X-BYTERANGE:100@0\nmain.mp4\n\
+            #EXTINF:6,\n#EXT-X-BYTERANGE:100\nmain.mp4\n\
+            #EXTINF:6,\nintro.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(
+            parsed.uri_anomalies(),
+            vec![UriAnomaly::DuplicateUri {
+                uri: "intro.ts".to_string(),
+                first_sequence: 0,
+                sequence: 3,
+            }]
+        );
+    }
+
+    #[test]
+    /// Tests bitrate percentiles are weighted by segment duration
+    fn it_reports_duration_weighted_bitrates() {
+        let body = "#EXTM3U\n\
+            #EXTINF:2,\nunknown.ts\n\
+            #EXT-X-BITRATE:1000\n\
+            #EXTINF:8,\na.ts\n\
+            #EXT-X-BITRATE:3000\n\
+            #EXTINF:1,\nb.ts\n\
+            #EXTINF:1,\n#EXT-X-BYTERANGE:500000@0\nc.mp4\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(
+            parsed.bitrate_report(),
+            Some(BitrateReport {
+                p50: 1000,
+                p95: 4000,
+                max: 4000,
+                mean: 1500,
+                measured_duration: 10.0,
+                segments: 3,
+            })
+        );
+        let measured = parsed.bitrate_report_with(|segment| match segment.byte_range {
+            Some(range) => Some(range.length),
+            None => (segment.uri == "unknown.ts").then_some(250_000),
+        });
+        assert_eq!(measured.unwrap().segments, 4);
+        assert_eq!(measured.unwrap().mean, 1417);
+        assert!(M3U8::from_body("#EXTM3U\n")
+            .unwrap()
+            .bitrate_report()
+            .is_none());
+    }
+}
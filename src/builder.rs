@@ -0,0 +1,96 @@
+//! Parsing with application-registered handlers for vendor tags.
+use std::collections::HashMap;
+
+use crate::{ParseError, SpecVersion, UnknownLine, M3U8};
+
+/// Handler called with each occurrence of a registered tag
+pub(crate) type TagHandler<'a> = Box<dyn FnMut(&UnknownLine) -> Result<(), ParseError> + 'a>;
+
+/// Parses playlists, handing registered vendor tags to application handlers
+///
+/// Handled lines are still kept in `unknown_lines` so the playlist writes back
+/// out unchanged.
+#[derive(Default)]
+pub struct ParserBuilder<'a> {
+    handlers: HashMap<String, TagHandler<'a>>,
+    spec: Option<SpecVersion>,
+}
+
+/// Implementation for ParserBuilder
+impl<'a> ParserBuilder<'a> {
+    /// Parser without custom tag handlers
+    pub fn new() -> Self {
+        ParserBuilder::default()
+    }
+
+    /// Calls `handler` for every `tag` line, in playlist order, during the parse
+    ///
+    /// An error returned by the handler aborts the parse.
+    pub fn register_tag<F>(mut self, tag: &str, handler: F) -> Self
+    where
+        F: FnMut(&UnknownLine) -> Result<(), ParseError> + 'a,
+    {
+        self.handlers.insert(tag.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Rejects playlists using tags or attributes that `spec` does not define
+    pub fn spec_version(mut self, spec: SpecVersion) -> Self {
+        self.spec = Some(spec);
+        self
+    }
+
+    /// Parses a playlist body
+    pub fn parse(mut self, body: &str) -> Result<M3U8, ParseError> {
+        let m3u8 = M3U8::from_body_with_handlers(body, &mut self.handlers)?;
+        if let Some(violation) = self
+            .spec
+            .and_then(|spec| m3u8.spec_violations(spec).into_iter().next())
+        {
+            return Err(ParseError::InvalidM3U8(violation.to_string()));
+        }
+        Ok(m3u8)
+    }
+
+    /// Fetches and parses a playlist
+    pub fn parse_uri(self, uri: &str) -> Result<M3U8, ParseError> {
+        let body = M3U8::fetch_body(uri)?;
+        self.parse(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ParseError, ParserBuilder};
+
+    #[test]
+    /// Tests registered tags reach their handler with their segment position
+    fn it_calls_registered_tag_handlers() {
+        let body = "#EXTM3U\n\
+            #EXTINF:6,\na.ts\n\
+            #EXT-X-AD-MARKER:30\n\
+            #EXTINF:6,\nb.ts\n\
+            #EXT-X-OTHER:1\n";
+        let mut cues = Vec::new();
+
+        let parsed = ParserBuilder::new()
+            .register_tag("#EXT-X-AD-MARKER", |unknown| {
+                let (_, duration) = unknown.line.split_once(':').unwrap();
+                cues.push((unknown.segment_index, duration.parse::<f64>().unwrap()));
+                Ok(())
+            })
+            .parse(body)
+            .unwrap();
+
+        assert_eq!(cues, vec![(1, 30.0)]);
+        assert_eq!(parsed.unknown_lines().len(), 2);
+
+        let failed = ParserBuilder::new()
+            .register_tag("#EXT-X-OTHER", |_| {
+                Err(ParseError::InvalidM3U8("rejected".to_string()))
+            })
+            .parse(body);
+        assert!(matches!(failed, Err(ParseError::InvalidM3U8(_))));
+    }
+}
@@ -0,0 +1,172 @@
+//! Fetching many playlists concurrently.
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{FetchOptions, ParseError, M3U8};
+
+type BoxedFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Runs futures with at most `limit` in flight, returning their outputs in order
+struct Bounded<'a, T> {
+    queued: VecDeque<BoxedFuture<'a, T>>,
+    running: Vec<(usize, BoxedFuture<'a, T>)>,
+    started: usize,
+    outputs: Vec<Option<T>>,
+    limit: usize,
+}
+
+/// Implementation for Bounded
+impl<'a, T> Bounded<'a, T> {
+    fn new(futures: Vec<BoxedFuture<'a, T>>, limit: usize) -> Self {
+        Bounded {
+            outputs: futures.iter().map(|_| None).collect(),
+            queued: futures.into(),
+            running: vec![],
+            started: 0,
+            limit: limit.max(1),
+        }
+    }
+}
+
+/// Bounded can move while pinned, since its futures are boxed and outputs are never pinned
+impl<T> Unpin for Bounded<'_, T> {}
+
+/// Bounded as a Future, polling every running future when woken
+impl<T> Future for Bounded<'_, T> {
+    type Output = Vec<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Vec<T>> {
+        let Bounded {
+            queued,
+            running,
+            started,
+            outputs,
+            limit,
+        } = self.get_mut();
+        loop {
+            while running.len() < *limit {
+                let Some(future) = queued.pop_front() else {
+                    break;
+                };
+                running.push((*started, future));
+                *started += 1;
+            }
+            let before = running.len();
+            running.retain_mut(|(index, future)| match future.as_mut().poll(cx) {
+                Poll::Ready(output) => {
+                    outputs[*index] = Some(output);
+                    false
+                }
+                Poll::Pending => true,
+            });
+            if running.is_empty() && queued.is_empty() {
+                return Poll::Ready(outputs.iter_mut().filter_map(Option::take).collect());
+            }
+            // Only a finished future frees a slot for a queued one
+            if running.len() == before {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+/// Implementation of bulk fetching for M3U8
+impl M3U8 {
+    /// Fetches and parses many playlists, at most `concurrency` at a time
+    ///
+    /// Results are returned per URI, in the order given, so one failing stream does
+    /// not hide the others. A concurrency of 0 is treated as 1.
+    pub async fn from_uris(
+        uris: &[&str],
+        concurrency: usize,
+    ) -> Vec<(String, Result<M3U8, ParseError>)> {
+        M3U8::from_uris_with_options(uris, concurrency, &FetchOptions::default()).await
+    }
+
+    /// Like from_uris, but fetches every playlist with the given options
+    ///
+    /// Every request goes through the one client built from the options, so
+    /// connections to an origin are kept alive and reused.
+    pub async fn from_uris_with_options(
+        uris: &[&str],
+        concurrency: usize,
+        options: &FetchOptions,
+    ) -> Vec<(String, Result<M3U8, ParseError>)> {
+        let futures = uris
+            .iter()
+            .map(|uri| -> BoxedFuture<'_, Result<M3U8, ParseError>> {
+                Box::pin(M3U8::from_uri_with_options_async(uri, options))
+            })
+            .collect();
+        let results = Bounded::new(futures, concurrency).await;
+        uris.iter()
+            .map(|uri| uri.to_string())
+            .zip(results)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::Cell;
+
+    use crate::bulk::{Bounded, BoxedFuture};
+    use crate::M3U8;
+
+    #[test]
+    /// Tests playlists are fetched with bounded concurrency and reported per URI
+    fn it_fetches_many_playlists() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (running, most) = (Cell::new(0), Cell::new(0));
+        let futures: Vec<BoxedFuture<'_, usize>> = (0..5)
+            .map(|index| -> BoxedFuture<'_, usize> {
+                let (running, most) = (&running, &most);
+                Box::pin(async move {
+                    running.set(running.get() + 1);
+                    most.set(most.get().max(running.get()));
+                    tokio::task::yield_now().await;
+                    running.set(running.get() - 1);
+                    index
+                })
+            })
+            .collect();
+        assert_eq!(
+            runtime.block_on(Bounded::new(futures, 2)),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert_eq!(most.get(), 2);
+
+        let dir = crate::testing::FixtureDir::new("bulk").unwrap();
+        for name in ["a", "b", "c"] {
+            std::fs::write(
+                dir.join(format!("{}.m3u8", name)),
+                format!("#EXTM3U\n#EXTINF:4,\n{}.ts\n", name),
+            )
+            .unwrap();
+        }
+        let (url, server) = crate::testing::serve_fixture(&dir).unwrap();
+        let uris: Vec<String> = ["a", "missing", "b", "c"]
+            .iter()
+            .map(|name| format!("{}/{}.m3u8", url, name))
+            .collect();
+        let uris: Vec<&str> = uris.iter().map(String::as_str).collect();
+
+        let results = runtime.block_on(M3U8::from_uris(&uris, 0));
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[2].0, uris[2]);
+        assert_eq!(results[2].1.as_ref().unwrap().segments()[0].uri, "b.ts");
+        assert!(results[1].1.is_err());
+        assert_eq!(
+            results.iter().filter(|(_, result)| result.is_ok()).count(),
+            3
+        );
+        assert_eq!(server.requests(), 4);
+    }
+}
@@ -0,0 +1,207 @@
+//! Capturing one playlist with several request profiles to detect conditional manifests.
+use std::collections::HashMap;
+
+use crate::{FetchOptions, ParseError, M3U8};
+
+/// Named request options, such as a player User-Agent and its headers
+#[derive(Debug, Clone)]
+pub struct FetchProfile {
+    pub name: String,
+    pub options: FetchOptions,
+}
+
+/// Implementation for FetchProfile
+impl FetchProfile {
+    /// Profile with a name used when reporting differences
+    pub fn new(name: &str, options: FetchOptions) -> Self {
+        FetchProfile {
+            name: name.to_string(),
+            options,
+        }
+    }
+}
+
+/// A playlist as served to one profile
+#[derive(Debug)]
+pub struct ProfileCapture {
+    pub profile: String,
+    pub playlist: M3U8,
+}
+
+/// A way a profile was served different content than the first profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ManifestDifference {
+    /// A variant served to the first profile was not served to this one
+    MissingVariant { profile: String, uri: String },
+    /// A variant was only served to this profile
+    ExtraVariant { profile: String, uri: String },
+    /// A variant was served to both profiles with different attributes
+    ChangedVariant { profile: String, uri: String },
+    /// A segment served to the first profile was not served to this one
+    MissingSegment { profile: String, uri: String },
+    /// A segment was only served to this profile, such as an inserted ad
+    ExtraSegment { profile: String, uri: String },
+}
+
+/// Drops the query string, where CDNs put per-request tokens
+fn without_query(uri: &str) -> &str {
+    uri.split('?').next().unwrap_or(uri)
+}
+
+/// Implementation of conditional manifest capture for M3U8
+impl M3U8 {
+    /// Fetches the same playlist once per profile
+    pub fn capture_with_profiles(
+        uri: &str,
+        profiles: &[FetchProfile],
+    ) -> Result<Vec<ProfileCapture>, ParseError> {
+        profiles
+            .iter()
+            .map(|profile| {
+                let fetched = M3U8::fetch_playlist(uri, &profile.options)?;
+                Ok(ProfileCapture {
+                    profile: profile.name.to_string(),
+                    playlist: M3U8::from_body_at(&fetched.body, &fetched.url)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Compares every capture against the first, ignoring URI query strings
+    pub fn conditional_differences(captures: &[ProfileCapture]) -> Vec<ManifestDifference> {
+        let Some((baseline, others)) = captures.split_first() else {
+            return Vec::new();
+        };
+        let variants = |playlist: &M3U8| -> HashMap<String, HashMap<String, String>> {
+            playlist
+                .variant_streams
+                .iter()
+                .map(|variant| {
+                    let uri = variant.get("uri").map_or("", |uri| uri);
+                    let mut attributes = variant.clone();
+                    attributes.remove("uri");
+                    (without_query(uri).to_string(), attributes)
+                })
+                .collect()
+        };
+        let segments = |playlist: &M3U8| -> Vec<String> {
+            playlist
+                .segments
+                .iter()
+                .map(|segment| without_query(&segment.uri).to_string())
+                .collect()
+        };
+        let expected_variants = variants(&baseline.playlist);
+        let expected_segments = segments(&baseline.playlist);
+        let mut differences = Vec::new();
+        for capture in others {
+            let profile = &capture.profile;
+            let served_variants = variants(&capture.playlist);
+            let served_segments = segments(&capture.playlist);
+            let mut uris: Vec<&String> = expected_variants.keys().collect();
+            uris.sort();
+            for uri in uris {
+                let (profile, uri) = (profile.to_string(), uri.to_string());
+                match served_variants.get(&uri) {
+                    None => differences.push(ManifestDifference::MissingVariant { profile, uri }),
+                    Some(attributes) if attributes != &expected_variants[&uri] => {
+                        differences.push(ManifestDifference::ChangedVariant { profile, uri })
+                    }
+                    _ => (),
+                }
+            }
+            let mut uris: Vec<&String> = served_variants
+                .keys()
+                .filter(|uri| !expected_variants.contains_key(*uri))
+                .collect();
+            uris.sort();
+            for uri in uris {
+                differences.push(ManifestDifference::ExtraVariant {
+                    profile: profile.to_string(),
+                    uri: uri.to_string(),
+                });
+            }
+            for uri in expected_segments
+                .iter()
+                .filter(|uri| !served_segments.contains(uri))
+            {
+                differences.push(ManifestDifference::MissingSegment {
+                    profile: profile.to_string(),
+                    uri: uri.to_string(),
+                });
+            }
+            for uri in served_segments
+                .iter()
+                .filter(|uri| !expected_segments.contains(uri))
+            {
+                differences.push(ManifestDifference::ExtraSegment {
+                    profile: profile.to_string(),
+                    uri: uri.to_string(),
+                });
+            }
+        }
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ManifestDifference, ProfileCapture, M3U8};
+
+    /// Captures a body as served to a profile
+    fn capture(profile: &str, body: &str) -> ProfileCapture {
+        ProfileCapture {
+            profile: profile.to_string(),
+            playlist: M3U8::from_body(body).unwrap(),
+        }
+    }
+
+    #[test]
+    /// Tests variants and segments served to only some profiles are reported
+    fn it_diffs_captures_across_profiles() {
+        let master = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000\nlow.m3u8?token=a\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000\nhigh.m3u8?token=a\n";
+        let mobile = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=900000\nlow.m3u8?token=b\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=600000\nmobile.m3u8?token=b\n";
+        let media = "#EXTM3U\n#EXTINF:6,\na.ts\n#EXTINF:6,\nb.ts\n";
+        let with_ad = "#EXTM3U\n#EXTINF:6,\na.ts\n#EXTINF:6,\nad.ts\n#EXTINF:6,\nb.ts\n";
+
+        let differences = M3U8::conditional_differences(&[
+            capture("chrome", master),
+            capture("chrome", master),
+            capture("ios", mobile),
+        ]);
+        assert_eq!(
+            differences,
+            vec![
+                ManifestDifference::MissingVariant {
+                    profile: "ios".to_string(),
+                    uri: "high.m3u8".to_string(),
+                },
+                ManifestDifference::ChangedVariant {
+                    profile: "ios".to_string(),
+                    uri: "low.m3u8".to_string(),
+                },
+                ManifestDifference::ExtraVariant {
+                    profile: "ios".to_string(),
+                    uri: "mobile.m3u8".to_string(),
+                },
+            ]
+        );
+
+        let differences =
+            M3U8::conditional_differences(&[capture("chrome", media), capture("exo", with_ad)]);
+        assert_eq!(
+            differences,
+            vec![ManifestDifference::ExtraSegment {
+                profile: "exo".to_string(),
+                uri: "ad.ts".to_string(),
+            }]
+        );
+        assert!(M3U8::conditional_differences(&[]).is_empty());
+    }
+}
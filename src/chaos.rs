@@ -0,0 +1,197 @@
+//! Fault-injecting fetcher for exercising retry and monitoring code.
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::{FetchOptions, ParseError, PlaylistFetcher, SchemeHandler};
+
+/// Fault injected into a fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The fetch fails with a timed out IO error after the timeout delay
+    Timeout,
+    /// The body of a 503 response is returned, as an origin error page would be
+    ServerError,
+    /// The body is cut off at a random point
+    Truncated,
+    /// The body is returned after a delay per kilobyte
+    Trickle,
+}
+
+/// Fetches through HTTP with the given options
+struct HttpFetcher(FetchOptions);
+
+impl SchemeHandler for HttpFetcher {
+    fn fetch(&self, uri: &str) -> Result<String, ParseError> {
+        Ok(self.0.fetch(uri)?.body)
+    }
+}
+
+/// Wraps a fetcher and injects faults at configured rates from a seeded generator
+///
+/// The same seed and sequence of fetches injects the same faults, so failing runs
+/// can be replayed. Register it with `FetchOptions::with_scheme_handler` for the
+/// schemes under test.
+pub struct ChaosFetcher {
+    inner: Arc<dyn SchemeHandler>,
+    rates: [(Fault, f64); 4],
+    timeout: Duration,
+    trickle_per_kb: Duration,
+    state: Mutex<(u64, Vec<Fault>)>,
+}
+
+/// Implementation for ChaosFetcher
+impl ChaosFetcher {
+    /// Wraps a fetcher, injecting no faults until rates are set
+    pub fn new(inner: Arc<dyn SchemeHandler>, seed: u64) -> Self {
+        ChaosFetcher {
+            inner,
+            rates: [
+                (Fault::Timeout, 0.0),
+                (Fault::ServerError, 0.0),
+                (Fault::Truncated, 0.0),
+                (Fault::Trickle, 0.0),
+            ],
+            timeout: Duration::ZERO,
+            trickle_per_kb: Duration::ZERO,
+            // xorshift needs a non-zero state
+            state: Mutex::new((seed.max(1), vec![])),
+        }
+    }
+
+    /// Wraps plain HTTP fetches made with `options`
+    pub fn http(options: FetchOptions, seed: u64) -> Self {
+        ChaosFetcher::new(Arc::new(HttpFetcher(options)), seed)
+    }
+
+    /// Sets the probability, from 0 to 1, of injecting a fault on each fetch
+    pub fn with_rate(mut self, fault: Fault, rate: f64) -> Self {
+        for (kind, probability) in &mut self.rates {
+            if *kind == fault {
+                *probability = rate.clamp(0.0, 1.0);
+            }
+        }
+        self
+    }
+
+    /// How long an injected timeout waits before failing
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How long a trickled body takes per kilobyte
+    pub fn with_trickle(mut self, per_kb: Duration) -> Self {
+        self.trickle_per_kb = per_kb;
+        self
+    }
+
+    /// Returns the faults injected so far, in order
+    pub fn injected(&self) -> Vec<Fault> {
+        self.state.lock().unwrap().1.clone()
+    }
+
+    /// Advances the xorshift64* generator, returning a value in `0..1`
+    fn next_random(state: &mut u64) -> f64 {
+        *state ^= *state >> 12;
+        *state ^= *state << 25;
+        *state ^= *state >> 27;
+        (state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Fetches through the wrapped fetcher unless a fault is drawn
+impl SchemeHandler for ChaosFetcher {
+    fn fetch(&self, uri: &str) -> Result<String, ParseError> {
+        let (fault, cut) = {
+            let mut state = self.state.lock().unwrap();
+            let draw = ChaosFetcher::next_random(&mut state.0);
+            let cut = ChaosFetcher::next_random(&mut state.0);
+            let mut threshold = 0.0;
+            let fault = self.rates.iter().find_map(|(fault, rate)| {
+                threshold += rate;
+                (draw < threshold).then_some(*fault)
+            });
+            state.1.extend(fault);
+            (fault, cut)
+        };
+        match fault {
+            Some(Fault::Timeout) => {
+                thread::sleep(self.timeout);
+                Err(ParseError::IoError(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("injected timeout fetching {}", uri),
+                )))
+            }
+            Some(Fault::ServerError) => Ok("503 Service Unavailable\n".to_string()),
+            Some(Fault::Truncated) => {
+                let body = self.inner.fetch(uri)?;
+                let mut end = (body.len() as f64 * cut) as usize;
+                while !body.is_char_boundary(end) {
+                    end -= 1;
+                }
+                Ok(body[..end].to_string())
+            }
+            Some(Fault::Trickle) => {
+                let body = self.inner.fetch(uri)?;
+                thread::sleep(self.trickle_per_kb * (body.len() / 1024 + 1) as u32);
+                Ok(body)
+            }
+            None => self.inner.fetch(uri),
+        }
+    }
+}
+
+/// Fetches through the wrapped fetcher unless a fault is drawn
+impl PlaylistFetcher for ChaosFetcher {
+    fn fetch(&self, uri: &str) -> Result<String, ParseError> {
+        SchemeHandler::fetch(self, uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use crate::{ChaosFetcher, Fault, FetchOptions, ParseError, SchemeHandler, M3U8};
+
+    /// Serves the same playlist for every URI
+    struct Fixed;
+
+    impl SchemeHandler for Fixed {
+        fn fetch(&self, _uri: &str) -> Result<String, ParseError> {
+            Ok("#EXTM3U\n#EXTINF:4,\na.ts\n#EXTINF:4,\nb.ts\n#EXT-X-ENDLIST\n".to_string())
+        }
+    }
+
+    #[test]
+    /// Tests faults are injected at their rates and replay the same for a seed
+    fn it_injects_seeded_faults() {
+        let chaos = || {
+            ChaosFetcher::new(Arc::new(Fixed), 7)
+                .with_rate(Fault::ServerError, 0.3)
+                .with_rate(Fault::Timeout, 0.2)
+        };
+        let first = Arc::new(chaos());
+        let options = FetchOptions::new().with_scheme_handler("test", first.clone());
+        let results: Vec<bool> = (0..50)
+            .map(|_| M3U8::from_uri_with_options("test://live.m3u8", &options).is_ok())
+            .collect();
+
+        let injected = first.injected();
+        assert_eq!(results.iter().filter(|ok| !**ok).count(), injected.len());
+        assert!(injected.contains(&Fault::ServerError) && injected.contains(&Fault::Timeout));
+        assert!(injected.len() > 10 && injected.len() < 40);
+
+        let second = chaos();
+        for _ in 0..50 {
+            let _ = second.fetch("test://live.m3u8");
+        }
+        assert_eq!(second.injected(), injected);
+
+        let truncating = ChaosFetcher::new(Arc::new(Fixed), 3).with_rate(Fault::Truncated, 1.0);
+        assert!(truncating.fetch("test://live.m3u8").unwrap().len() < 47);
+    }
+}
@@ -0,0 +1,434 @@
+//! Codec strings from CODECS attributes.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{ParseError, VariantStream};
+
+/// H.264 profile_idc values defined by ITU-T H.264
+const AVC_PROFILES: &[u8] = &[
+    44, 66, 77, 83, 86, 88, 100, 110, 118, 122, 128, 134, 135, 138, 139, 244,
+];
+
+/// H.264 level_idc values, ten times the level number
+const AVC_LEVELS: &[u8] = &[
+    9, 10, 11, 12, 13, 20, 21, 22, 30, 31, 32, 40, 41, 42, 50, 51, 52, 60, 61, 62,
+];
+
+/// H.265 general_level_idc values, thirty times the level number
+const HEVC_LEVELS: &[u8] = &[30, 60, 63, 90, 93, 120, 123, 150, 153, 156, 180, 183, 186];
+
+/// A codec string that could not be understood
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CodecError {
+    /// Not a video codec this crate parses
+    Unsupported(String),
+    Invalid {
+        codec: String,
+        reason: &'static str,
+    },
+}
+
+/// CodecError as a Display type, naming the codec string
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecError::Unsupported(codec) => write!(f, "unsupported codec: {}", codec),
+            CodecError::Invalid { codec, reason } => {
+                write!(f, "invalid codec {}: {}", codec, reason)
+            }
+        }
+    }
+}
+
+/// Implementation of the standard Error trait for CodecError
+impl std::error::Error for CodecError {}
+
+/// Map a CodecError to our Error Wrapper
+impl From<CodecError> for ParseError {
+    fn from(err: CodecError) -> ParseError {
+        ParseError::InvalidM3U8(err.to_string())
+    }
+}
+
+/// Tier of an HEVC or AV1 level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CodecTier {
+    Main,
+    High,
+}
+
+/// Profile and level of an `avc1`/`avc3` codec string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AvcProfile {
+    pub profile_idc: u8,
+    pub constraint_flags: u8,
+    /// Ten times the level number, such as 31 for level 3.1
+    pub level_idc: u8,
+}
+
+/// Profile, tier and level of an `hvc1`/`hev1` codec string
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HevcProfile {
+    /// 0 when absent, 1 to 3 for the A to C prefixes
+    pub profile_space: u8,
+    pub profile_idc: u8,
+    pub compatibility_flags: u32,
+    pub tier: CodecTier,
+    /// Thirty times the level number, such as 123 for level 4.1
+    pub level_idc: u8,
+    pub constraint_flags: Vec<u8>,
+}
+
+/// Profile, level, tier and bit depth of an `av01` codec string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Av1Profile {
+    pub profile: u8,
+    /// seq_level_idx, where level X.Y is `(X - 2) * 4 + Y`
+    pub level: u8,
+    pub tier: CodecTier,
+    pub bit_depth: u8,
+}
+
+/// A video codec string parsed into its profile and level
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VideoCodec {
+    Avc(AvcProfile),
+    Hevc(HevcProfile),
+    Av1(Av1Profile),
+}
+
+/// Parses a number in the given radix, naming the field on failure
+fn number<T>(
+    codec: &str,
+    value: Option<&str>,
+    radix: u32,
+    field: &'static str,
+) -> Result<T, CodecError>
+where
+    T: TryFrom<u32>,
+{
+    value
+        .and_then(|value| u32::from_str_radix(value, radix).ok())
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or(CodecError::Invalid {
+            codec: codec.to_string(),
+            reason: field,
+        })
+}
+
+/// Implementation for VideoCodec
+impl VideoCodec {
+    /// Returns the level as a decimal number, such as 4.1
+    pub fn level(&self) -> f64 {
+        match self {
+            VideoCodec::Avc(avc) => f64::from(avc.level_idc) / 10.0,
+            VideoCodec::Hevc(hevc) => f64::from(hevc.level_idc) / 30.0,
+            VideoCodec::Av1(av1) => f64::from(2 + av1.level / 4) + f64::from(av1.level % 4) / 10.0,
+        }
+    }
+
+    /// Whether a decoder for `limit` can also decode this codec
+    ///
+    /// Both must be the same codec and profile, and this tier and level may not exceed
+    /// those of the limit. For AV1 the bit depth may not exceed it either.
+    pub fn fits_within(&self, limit: &VideoCodec) -> bool {
+        match (self, limit) {
+            (VideoCodec::Avc(avc), VideoCodec::Avc(limit)) => {
+                avc.profile_idc == limit.profile_idc && avc.level_idc <= limit.level_idc
+            }
+            (VideoCodec::Hevc(hevc), VideoCodec::Hevc(limit)) => {
+                hevc.profile_idc == limit.profile_idc
+                    && hevc.tier <= limit.tier
+                    && hevc.level_idc <= limit.level_idc
+            }
+            (VideoCodec::Av1(av1), VideoCodec::Av1(limit)) => {
+                av1.profile == limit.profile
+                    && av1.tier <= limit.tier
+                    && av1.level <= limit.level
+                    && av1.bit_depth <= limit.bit_depth
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses `avc1.PPCCLL`, or the legacy `avc1.<profile>.<level>` decimal form
+    fn parse_avc(codec: &str, fields: &[&str]) -> Result<VideoCodec, CodecError> {
+        let avc = match fields {
+            [hex] if hex.len() == 6 => AvcProfile {
+                profile_idc: number(codec, hex.get(0..2), 16, "profile")?,
+                constraint_flags: number(codec, hex.get(2..4), 16, "constraint flags")?,
+                level_idc: number(codec, hex.get(4..6), 16, "level")?,
+            },
+            [profile, level] => AvcProfile {
+                profile_idc: number(codec, Some(profile), 10, "profile")?,
+                constraint_flags: 0,
+                level_idc: number(codec, Some(level), 10, "level")?,
+            },
+            _ => {
+                return Err(CodecError::Invalid {
+                    codec: codec.to_string(),
+                    reason: "expected profile, constraints and level",
+                })
+            }
+        };
+        let invalid = |reason| CodecError::Invalid {
+            codec: codec.to_string(),
+            reason,
+        };
+        if !AVC_PROFILES.contains(&avc.profile_idc) {
+            return Err(invalid("unknown profile"));
+        }
+        if !AVC_LEVELS.contains(&avc.level_idc) {
+            return Err(invalid("unknown level"));
+        }
+        Ok(VideoCodec::Avc(avc))
+    }
+
+    /// Parses `hvc1.[A-C]<profile>.<compatibility>.<tier><level>[.<constraints>]`
+    fn parse_hevc(codec: &str, fields: &[&str]) -> Result<VideoCodec, CodecError> {
+        let invalid = |reason| CodecError::Invalid {
+            codec: codec.to_string(),
+            reason,
+        };
+        let [profile, compatibility, level, constraints @ ..] = fields else {
+            return Err(invalid("expected profile, compatibility and level"));
+        };
+        if constraints.len() > 6 {
+            return Err(invalid("too many constraint bytes"));
+        }
+        let (profile_space, profile) = match profile.chars().next() {
+            Some(space @ 'A'..='C') => (space as u8 - b'A' + 1, &profile[1..]),
+            _ => (0, *profile),
+        };
+        let profile_idc: u8 = number(codec, Some(profile), 10, "profile")?;
+        if !(1..=11).contains(&profile_idc) {
+            return Err(invalid("unknown profile"));
+        }
+        let tier = match level.chars().next() {
+            Some('L') => CodecTier::Main,
+            Some('H') => CodecTier::High,
+            _ => return Err(invalid("tier")),
+        };
+        let level_idc = number(codec, level.get(1..), 10, "level")?;
+        if !HEVC_LEVELS.contains(&level_idc) {
+            return Err(invalid("unknown level"));
+        }
+        Ok(VideoCodec::Hevc(HevcProfile {
+            profile_space,
+            profile_idc,
+            compatibility_flags: number(codec, Some(compatibility), 16, "compatibility flags")?,
+            tier,
+            level_idc,
+            constraint_flags: constraints
+                .iter()
+                .map(|byte| number(codec, Some(byte), 16, "constraint flags"))
+                .collect::<Result<_, _>>()?,
+        }))
+    }
+
+    /// Parses `av01.<profile>.<level><tier>.<bit depth>` with optional trailing fields
+    fn parse_av1(codec: &str, fields: &[&str]) -> Result<VideoCodec, CodecError> {
+        let invalid = |reason| CodecError::Invalid {
+            codec: codec.to_string(),
+            reason,
+        };
+        let [profile, level, bit_depth, rest @ ..] = fields else {
+            return Err(invalid("expected profile, level and bit depth"));
+        };
+        if rest.len() > 6 {
+            return Err(invalid("too many fields"));
+        }
+        let profile = number(codec, Some(profile), 10, "profile")?;
+        if profile > 2 {
+            return Err(invalid("unknown profile"));
+        }
+        let tier = match level.get(2..) {
+            Some("M") => CodecTier::Main,
+            Some("H") => CodecTier::High,
+            _ => return Err(invalid("tier")),
+        };
+        let level = number(codec, level.get(0..2), 10, "level")?;
+        if level > 31 {
+            return Err(invalid("unknown level"));
+        }
+        let bit_depth = number(codec, Some(bit_depth), 10, "bit depth")?;
+        if ![8, 10, 12].contains(&bit_depth) {
+            return Err(invalid("unknown bit depth"));
+        }
+        Ok(VideoCodec::Av1(Av1Profile {
+            profile,
+            level,
+            tier,
+            bit_depth,
+        }))
+    }
+}
+
+/// Implementation for VideoCodec
+impl FromStr for VideoCodec {
+    type Err = CodecError;
+
+    fn from_str(input: &str) -> Result<VideoCodec, Self::Err> {
+        let codec = input.trim();
+        let fields: Vec<&str> = codec.split('.').collect();
+        match fields[0] {
+            "avc1" | "avc3" => VideoCodec::parse_avc(codec, &fields[1..]),
+            "hvc1" | "hev1" => VideoCodec::parse_hevc(codec, &fields[1..]),
+            "av01" => VideoCodec::parse_av1(codec, &fields[1..]),
+            _ => Err(CodecError::Unsupported(codec.to_string())),
+        }
+    }
+}
+
+/// One entry of a CODECS attribute
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Codec {
+    Video(VideoCodec),
+    /// `mp4a.40.<object type>`, such as 2 for AAC-LC and 5 for HE-AAC
+    Aac {
+        object_type: u8,
+    },
+    /// `mp4a.40.34` or `mp4a.6B`
+    Mp3,
+    /// `ac-3` Dolby Digital
+    Ac3,
+    /// `ec-3` Dolby Digital Plus
+    Ec3,
+    /// `ac-4` Dolby AC-4
+    Ac4,
+    Opus,
+    Flac,
+    /// `wvtt` WebVTT subtitles in fragmented MP4
+    WebVtt,
+    /// `stpp` TTML subtitles in fragmented MP4
+    Ttml,
+    /// A codec this crate does not classify, such as `vp09` or `mha1`
+    Other(String),
+}
+
+/// Implementation for Codec
+impl Codec {
+    /// Whether the codec carries video
+    pub fn is_video(&self) -> bool {
+        matches!(self, Codec::Video(_))
+    }
+
+    /// Whether the codec carries audio
+    pub fn is_audio(&self) -> bool {
+        matches!(
+            self,
+            Codec::Aac { .. }
+                | Codec::Mp3
+                | Codec::Ac3
+                | Codec::Ec3
+                | Codec::Ac4
+                | Codec::Opus
+                | Codec::Flac
+        )
+    }
+}
+
+/// Implementation for Codec
+impl FromStr for Codec {
+    type Err = CodecError;
+
+    fn from_str(input: &str) -> Result<Codec, Self::Err> {
+        let codec = input.trim();
+        let fields: Vec<&str> = codec.split('.').collect();
+        match fields.as_slice() {
+            ["avc1" | "avc3" | "hvc1" | "hev1" | "av01", ..] => {
+                Ok(Codec::Video(VideoCodec::from_str(codec)?))
+            }
+            ["mp4a", "40", "34"] | ["mp4a", "6B" | "6b" | "69"] => Ok(Codec::Mp3),
+            ["mp4a", "40", object_type] => Ok(Codec::Aac {
+                object_type: number(codec, Some(object_type), 10, "object type")?,
+            }),
+            ["mp4a", ..] => Err(CodecError::Invalid {
+                codec: codec.to_string(),
+                reason: "expected object type",
+            }),
+            ["ac-3"] => Ok(Codec::Ac3),
+            ["ec-3"] => Ok(Codec::Ec3),
+            ["ac-4", ..] => Ok(Codec::Ac4),
+            ["Opus" | "opus"] => Ok(Codec::Opus),
+            ["fLaC" | "flac"] => Ok(Codec::Flac),
+            ["wvtt"] => Ok(Codec::WebVtt),
+            ["stpp", ..] => Ok(Codec::Ttml),
+            _ => Ok(Codec::Other(codec.to_string())),
+        }
+    }
+}
+
+/// Implementation of codec parsing for VariantStream
+impl VariantStream {
+    /// Returns the CODECS entries classified, failing on a malformed entry
+    pub fn parsed_codecs(&self) -> Result<Vec<Codec>, CodecError> {
+        self.codecs.iter().map(|codec| codec.parse()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{Codec, CodecError, CodecTier, VideoCodec, M3U8};
+
+    #[test]
+    /// Tests AVC, HEVC and AV1 codec strings parse into profiles and compare by level
+    fn it_parses_video_codecs() {
+        let avc: VideoCodec = "avc1.64001f".parse().unwrap();
+        assert_eq!(avc.level(), 3.1);
+        let VideoCodec::Avc(profile) = avc else {
+            panic!("expected AVC")
+        };
+        assert_eq!(profile.profile_idc, 100);
+        assert_eq!("avc1.66.30".parse::<VideoCodec>().unwrap().level(), 3.0);
+
+        let hevc: VideoCodec = "hvc1.2.4.L123.B0".parse().unwrap();
+        let VideoCodec::Hevc(profile) = &hevc else {
+            panic!("expected HEVC")
+        };
+        assert_eq!(profile.profile_idc, 2);
+        assert_eq!(profile.tier, CodecTier::Main);
+        assert_eq!(profile.constraint_flags, vec![0xB0]);
+        assert!((hevc.level() - 4.1).abs() < 1e-9);
+
+        let av1: VideoCodec = "av01.0.08M.10.0.110.09.16.09.0".parse().unwrap();
+        assert_eq!(av1.level(), 4.0);
+        let limit: VideoCodec = "av01.0.09M.10".parse().unwrap();
+        assert!(av1.fits_within(&limit));
+        assert!(!limit.fits_within(&av1));
+        assert!(!av1.fits_within(&hevc));
+
+        assert_eq!(
+            "mp4a.40.2".parse::<VideoCodec>(),
+            Err(CodecError::Unsupported("mp4a.40.2".to_string()))
+        );
+        assert!("avc1.64001".parse::<VideoCodec>().is_err());
+        assert!("hvc1.2.4.X123".parse::<VideoCodec>().is_err());
+        assert!("av01.3.08M.10".parse::<VideoCodec>().is_err());
+        assert!("av01.0.08M.09".parse::<VideoCodec>().is_err());
+    }
+
+    #[test]
+    /// Tests CODECS entries of a variant are classified into audio, video and text
+    fn it_classifies_variant_codecs() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1,CODECS=\"hvc1.2.4.L123.B0,mp4a.40.2,ec-3,wvtt,vp09.00.10.08\"\n\
+            a.m3u8\n";
+        let variant = &M3U8::from_body(body).unwrap().variants().unwrap()[0];
+
+        let codecs = variant.parsed_codecs().unwrap();
+
+        assert!(codecs[0].is_video());
+        assert_eq!(codecs[1], Codec::Aac { object_type: 2 });
+        assert!(codecs[1].is_audio() && codecs[2].is_audio());
+        assert_eq!(codecs[2], Codec::Ec3);
+        assert_eq!(codecs[3], Codec::WebVtt);
+        assert_eq!(codecs[4], Codec::Other("vp09.00.10.08".to_string()));
+        assert_eq!("mp4a.6B".parse::<Codec>(), Ok(Codec::Mp3));
+        assert!("mp4a.40".parse::<Codec>().is_err());
+        assert!("avc1.bad".parse::<Codec>().is_err());
+    }
+}
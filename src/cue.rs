@@ -0,0 +1,226 @@
+//! SCTE-35 ad cue tags used by ad insertion platforms.
+use std::fmt;
+
+use crate::{ParseError, EXT_X_CUE_IN, EXT_X_CUE_OUT, EXT_X_CUE_OUT_CONT, M3U8};
+
+/// An EXT-X-CUE-OUT, EXT-X-CUE-OUT-CONT or EXT-X-CUE-IN tag before a segment
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdCue {
+    /// Start of an ad break of `duration` seconds
+    Out { duration: Option<f64> },
+    /// Reminder that a break is in progress, for clients joining part way through
+    OutCont {
+        elapsed: Option<f64>,
+        duration: Option<f64>,
+        /// Base64 SCTE-35 splice information
+        scte35: Option<String>,
+    },
+    /// End of the ad break, back to content
+    In,
+}
+
+/// Parses a number of seconds, naming the tag on failure
+fn seconds(value: &str, tag: &str) -> Result<f64, ParseError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ParseError::InvalidM3U8(format!("Invalid {}: {}", tag, value)))
+}
+
+/// Implementation for AdCue
+impl AdCue {
+    /// Parses `#EXT-X-CUE-OUT[:<duration>|:DURATION=<duration>]`
+    pub(crate) fn parse_out(data: &str) -> Result<AdCue, ParseError> {
+        let value = data
+            .split_once('=')
+            .filter(|(key, _)| key.trim().eq_ignore_ascii_case("DURATION"))
+            .map_or(data, |(_, value)| value);
+        let duration = match value.trim() {
+            "" => None,
+            value => Some(seconds(value, EXT_X_CUE_OUT)?),
+        };
+        Ok(AdCue::Out { duration })
+    }
+
+    /// Parses `#EXT-X-CUE-OUT-CONT` as `<elapsed>/<duration>` or an attribute list
+    pub(crate) fn parse_out_cont(data: &str) -> Result<AdCue, ParseError> {
+        // Base64 SCTE35 values may contain '/', so only bare values are split on it
+        if let Some((elapsed, duration)) = data.split_once('/').filter(|_| !data.contains('=')) {
+            return Ok(AdCue::OutCont {
+                elapsed: Some(seconds(elapsed, EXT_X_CUE_OUT_CONT)?),
+                duration: Some(seconds(duration, EXT_X_CUE_OUT_CONT)?),
+                scte35: None,
+            });
+        }
+        let (mut elapsed, mut duration, mut scte35) = (None, None, None);
+        for item in M3U8::split_attributes(data) {
+            let Some((key, value)) = M3U8::get_key_value_pair(item) else {
+                continue;
+            };
+            match key.to_ascii_uppercase().as_str() {
+                "ELAPSEDTIME" => elapsed = Some(seconds(&value, EXT_X_CUE_OUT_CONT)?),
+                "DURATION" => duration = Some(seconds(&value, EXT_X_CUE_OUT_CONT)?),
+                "SCTE35" => scte35 = Some(value),
+                _ => (),
+            }
+        }
+        Ok(AdCue::OutCont {
+            elapsed,
+            duration,
+            scte35,
+        })
+    }
+}
+
+/// AdCue as a Display type, writing the whole tag line
+impl fmt::Display for AdCue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AdCue::Out { duration: None } => write!(f, "{}", EXT_X_CUE_OUT),
+            AdCue::Out {
+                duration: Some(duration),
+            } => write!(f, "{}:{}", EXT_X_CUE_OUT, duration),
+            AdCue::OutCont {
+                elapsed,
+                duration,
+                scte35,
+            } => {
+                let mut attributes = Vec::new();
+                if let Some(elapsed) = elapsed {
+                    attributes.push(format!("ElapsedTime={}", elapsed));
+                }
+                if let Some(duration) = duration {
+                    attributes.push(format!("Duration={}", duration));
+                }
+                if let Some(scte35) = scte35 {
+                    attributes.push(format!("SCTE35={}", scte35));
+                }
+                write!(f, "{}:{}", EXT_X_CUE_OUT_CONT, attributes.join(","))
+            }
+            AdCue::In => write!(f, "{}", EXT_X_CUE_IN),
+        }
+    }
+}
+
+/// A run of segments between EXT-X-CUE-OUT and EXT-X-CUE-IN
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdBreak {
+    /// Media sequence number of the first segment in the break
+    pub start_sequence: u64,
+    /// Announced break duration in seconds
+    pub duration: Option<f64>,
+    pub segments: usize,
+    /// Seconds of segments in the break present in the playlist
+    pub elapsed: f64,
+    /// Whether the break ended with EXT-X-CUE-IN in this playlist
+    pub ended: bool,
+}
+
+/// Implementation of ad break grouping for M3U8
+impl M3U8 {
+    /// Groups segments flagged as inside an ad break into breaks
+    pub fn ad_breaks(&self) -> Vec<AdBreak> {
+        let mut breaks: Vec<AdBreak> = Vec::new();
+        let mut open = false;
+        for segment in &self.segments {
+            if !segment.in_ad_break {
+                if open {
+                    if let Some(last) = breaks.last_mut() {
+                        last.ended = segment.ad_cue == Some(AdCue::In);
+                    }
+                    open = false;
+                }
+                continue;
+            }
+            // A CUE-OUT inside a break starts the next one back to back
+            if !open || matches!(segment.ad_cue, Some(AdCue::Out { .. })) {
+                if let (true, Some(last)) = (open, breaks.last_mut()) {
+                    last.ended = true;
+                }
+                let duration = match &segment.ad_cue {
+                    Some(AdCue::Out { duration }) | Some(AdCue::OutCont { duration, .. }) => {
+                        *duration
+                    }
+                    _ => None,
+                };
+                breaks.push(AdBreak {
+                    start_sequence: segment.sequence,
+                    duration,
+                    segments: 0,
+                    elapsed: 0.0,
+                    ended: false,
+                });
+                open = true;
+            }
+            if let Some(last) = breaks.last_mut() {
+                last.segments += 1;
+                last.elapsed += segment.duration;
+            }
+        }
+        if let (true, Some(last)) = (open, breaks.last_mut()) {
+            last.ended = self.pending_ad_cue == Some(AdCue::In);
+        }
+        breaks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{AdBreak, AdCue, M3U8};
+
+    #[test]
+    /// Tests cue tags flag the segments of a break and are grouped into breaks
+    fn it_parses_ad_cues() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA-SEQUENCE:5\n\
+            #EXTINF:6,\ncontent0.ts\n\
+            #EXT-X-CUE-OUT:DURATION=12\n#EXTINF:6,\nad0.ts\n\
+            #EXT-X-CUE-OUT-CONT:ElapsedTime=6,Duration=12,SCTE35=/DAlAAA=\n#EXTINF:6,\nad1.ts\n\
+            #EXT-X-CUE-IN\n#EXTINF:6,\ncontent1.ts\n\
+            #EXT-X-CUE-OUT-CONT:6/30\n#EXTINF:6,\nad2.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let segments = parsed.segments();
+        assert_eq!(
+            segments[1].ad_cue,
+            Some(AdCue::Out {
+                duration: Some(12.0)
+            })
+        );
+        assert_eq!(
+            segments[2].ad_cue,
+            Some(AdCue::OutCont {
+                elapsed: Some(6.0),
+                duration: Some(12.0),
+                scte35: Some("/DAlAAA=".to_string()),
+            })
+        );
+        let flags: Vec<bool> = segments.iter().map(|segment| segment.in_ad_break).collect();
+        assert_eq!(flags, vec![false, true, true, false, true]);
+        assert_eq!(
+            parsed.ad_breaks(),
+            vec![
+                AdBreak {
+                    start_sequence: 6,
+                    duration: Some(12.0),
+                    segments: 2,
+                    elapsed: 12.0,
+                    ended: true,
+                },
+                AdBreak {
+                    start_sequence: 9,
+                    duration: Some(30.0),
+                    segments: 1,
+                    elapsed: 6.0,
+                    ended: false,
+                },
+            ]
+        );
+
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.segments(), parsed.segments());
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-CUE-OUT:soon\n").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! EXT-X-DATERANGE tags, including SCTE-35 payloads.
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+
+use crate::segment::seconds_to_duration;
+use crate::{ParseError, M3U8};
+
+/// A date range with typed timing fields and decoded SCTE-35 payloads
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateRange {
+    pub id: String,
+    pub class: Option<String>,
+    pub start_date: DateTime<FixedOffset>,
+    pub end_date: Option<DateTime<FixedOffset>>,
+    pub duration: Option<f64>,
+    pub planned_duration: Option<f64>,
+    pub end_on_next: bool,
+    pub scte35_cmd: Option<Vec<u8>>,
+    pub scte35_out: Option<Vec<u8>>,
+    pub scte35_in: Option<Vec<u8>>,
+    /// X-<client-attribute> values, keyed by full attribute name
+    pub client_attributes: HashMap<String, String>,
+}
+
+/// Decodes an optionally 0x prefixed hexadecimal sequence into bytes
+pub(crate) fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    if !digits.len().is_multiple_of(2) || !digits.is_ascii() {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+        .collect()
+}
+
+/// Encodes bytes as a 0x prefixed hexadecimal sequence
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    format!("0x{}", digits)
+}
+
+/// Implementation for DateRange
+impl DateRange {
+    /// Builds a date range from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<DateRange, ParseError> {
+        let invalid = |key: &str| {
+            ParseError::InvalidM3U8(format!("Invalid #EXT-X-DATERANGE {} attribute", key))
+        };
+        let date = |key: &str| -> Result<Option<DateTime<FixedOffset>>, ParseError> {
+            attributes
+                .get(key)
+                .map(|value| DateTime::parse_from_rfc3339(value).map_err(|_| invalid(key)))
+                .transpose()
+        };
+        let seconds = |key: &str| -> Result<Option<f64>, ParseError> {
+            attributes
+                .get(key)
+                .map(|value| value.parse().map_err(|_| invalid(key)))
+                .transpose()
+        };
+        let hex = |key: &str| -> Result<Option<Vec<u8>>, ParseError> {
+            attributes
+                .get(key)
+                .map(|value| decode_hex(value).ok_or_else(|| invalid(key)))
+                .transpose()
+        };
+        Ok(DateRange {
+            id: attributes
+                .get("ID")
+                .ok_or_else(|| invalid("ID"))?
+                .to_string(),
+            class: attributes.get("CLASS").cloned(),
+            start_date: date("START-DATE")?.ok_or_else(|| invalid("START-DATE"))?,
+            end_date: date("END-DATE")?,
+            duration: seconds("DURATION")?,
+            planned_duration: seconds("PLANNED-DURATION")?,
+            end_on_next: attributes
+                .get("END-ON-NEXT")
+                .is_some_and(|value| value == "YES"),
+            scte35_cmd: hex("SCTE35-CMD")?,
+            scte35_out: hex("SCTE35-OUT")?,
+            scte35_in: hex("SCTE35-IN")?,
+            client_attributes: attributes
+                .iter()
+                .filter(|(key, _)| key.starts_with("X-"))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        })
+    }
+
+    /// Wall-clock end of the range from END-DATE, or START-DATE plus DURATION
+    pub fn end(&self) -> Option<DateTime<FixedOffset>> {
+        self.end_date.or_else(|| {
+            self.start_date
+                .checked_add_signed(seconds_to_duration(self.duration?)?)
+        })
+    }
+}
+
+/// DateRange as a Display type, writing its attribute list
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let date = |date: DateTime<FixedOffset>| date.to_rfc3339_opts(SecondsFormat::Millis, true);
+        write!(f, "ID={}", M3U8::quote(&self.id))?;
+        if let Some(class) = &self.class {
+            write!(f, ",CLASS={}", M3U8::quote(class))?;
+        }
+        write!(f, ",START-DATE={}", M3U8::quote(&date(self.start_date)))?;
+        if let Some(end_date) = self.end_date {
+            write!(f, ",END-DATE={}", M3U8::quote(&date(end_date)))?;
+        }
+        if let Some(duration) = self.duration {
+            write!(f, ",DURATION={}", duration)?;
+        }
+        if let Some(planned_duration) = self.planned_duration {
+            write!(f, ",PLANNED-DURATION={}", planned_duration)?;
+        }
+        let mut client_attributes: Vec<_> = self.client_attributes.iter().collect();
+        client_attributes.sort();
+        for (key, value) in client_attributes {
+            // Client attributes are quoted strings unless hexadecimal or numeric
+            if (value.starts_with("0x") && decode_hex(value).is_some())
+                || value.parse::<f64>().is_ok()
+            {
+                write!(f, ",{}={}", key, value)?;
+            } else {
+                write!(f, ",{}={}", key, M3U8::quote(value))?;
+            }
+        }
+        for (key, payload) in [
+            ("SCTE35-CMD", &self.scte35_cmd),
+            ("SCTE35-OUT", &self.scte35_out),
+            ("SCTE35-IN", &self.scte35_in),
+        ] {
+            if let Some(payload) = payload {
+                write!(f, ",{}={}", key, encode_hex(payload))?;
+            }
+        }
+        if self.end_on_next {
+            write!(f, ",END-ON-NEXT=YES")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Playlist delta updates for Low-Latency HLS.
+use crate::{ParseError, M3U8};
+
+/// Implementation of delta updates for M3U8
+impl M3U8 {
+    /// Merges a delta playlist, fetched with `_HLS_skip`, onto this full playlist
+    ///
+    /// The segments replaced by EXT-X-SKIP are taken from this playlist, and date
+    /// ranges not repeated in the delta are kept unless it reports them removed. A
+    /// playlist without EXT-X-SKIP simply replaces this one.
+    pub fn apply_delta(&mut self, delta: &M3U8) -> Result<(), ParseError> {
+        let Some(skip) = &delta.skip else {
+            *self = delta.clone();
+            return Ok(());
+        };
+        let first = delta.media_sequence;
+        let skipped_end = first + skip.skipped_segments;
+        let skipped: Vec<_> = self
+            .segments
+            .iter()
+            .filter(|segment| segment.sequence >= first && segment.sequence < skipped_end)
+            .cloned()
+            .collect();
+        if skipped.len() as u64 != skip.skipped_segments {
+            return Err(ParseError::InvalidM3U8(format!(
+                "Delta update skips segments {} to {} missing from the playlist",
+                first,
+                skipped_end.saturating_sub(1)
+            )));
+        }
+
+        let mut date_ranges: Vec<_> = self
+            .date_ranges
+            .drain(..)
+            .filter(|date_range| {
+                !skip.recently_removed_dateranges.contains(&date_range.id)
+                    && !delta
+                        .date_ranges
+                        .iter()
+                        .any(|updated| updated.id == date_range.id)
+            })
+            .collect();
+        date_ranges.extend(delta.date_ranges.iter().cloned());
+
+        let mut merged = delta.clone();
+        merged.skip = None;
+        merged.date_ranges = date_ranges;
+        merged.segments = skipped;
+        for mut segment in delta.segments.iter().cloned() {
+            // The first segment after the skip can now continue the wall-clock time
+            if segment.date_time.is_none() && !segment.discontinuity {
+                segment.date_time = merged
+                    .segments
+                    .last()
+                    .and_then(|previous| previous.end_date_time());
+            }
+            merged.segments.push(segment);
+        }
+        *self = merged;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::M3U8;
+
+    #[test]
+    /// Tests a delta update is merged onto the full playlist it skips into
+    fn it_applies_delta_updates() {
+        let full = "#EXTM3U\n\
+            #EXT-X-MEDIA-SEQUENCE:10\n\
+            #EXT-X-DATERANGE:ID=\"ad-1\",START-DATE=\"2024-01-01T00:00:00Z\"\n\
+            #EXT-X-DATERANGE:ID=\"ad-2\",START-DATE=\"2024-01-01T00:00:10Z\"\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-01-01T00:00:00Z\n\
+            #EXTINF:4,\ns10.ts\n#EXTINF:4,\ns11.ts\n#EXTINF:4,\ns12.ts\n#EXTINF:4,\ns13.ts\n";
+        let delta = "#EXTM3U\n\
+            #EXT-X-MEDIA-SEQUENCE:11\n\
+            #EXT-X-SKIP:SKIPPED-SEGMENTS=2,RECENTLY-REMOVED-DATERANGES=\"ad-1\"\n\
+            #EXTINF:4,\ns13.ts\n#EXTINF:4,\ns14.ts\n";
+        let mut playlist = M3U8::from_body(full).unwrap();
+        let delta = M3U8::from_body(delta).unwrap();
+        assert_eq!(delta.skip().unwrap().skipped_segments, 2);
+        assert_eq!(delta.segments()[0].sequence, 13);
+        assert_eq!(
+            M3U8::from_body(&delta.to_string()).unwrap().skip(),
+            delta.skip()
+        );
+
+        playlist.apply_delta(&delta).unwrap();
+
+        let uris: Vec<&str> = playlist.segments().iter().map(|s| s.uri.as_str()).collect();
+        assert_eq!(uris, vec!["s11.ts", "s12.ts", "s13.ts", "s14.ts"]);
+        let sequences: Vec<u64> = playlist.segments().iter().map(|s| s.sequence).collect();
+        assert_eq!(sequences, vec![11, 12, 13, 14]);
+        assert_eq!(playlist.media_sequence(), 11);
+        assert!(playlist.skip().is_none());
+        assert_eq!(playlist.date_ranges().len(), 1);
+        assert_eq!(playlist.date_ranges()[0].id, "ad-2");
+        assert_eq!(
+            playlist.segments()[3].date_time.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:16+00:00"
+        );
+
+        let stale = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-MEDIA-SEQUENCE:20\n\
+            #EXT-X-SKIP:SKIPPED-SEGMENTS=2\n#EXTINF:4,\ns22.ts\n",
+        )
+        .unwrap();
+        assert!(playlist.apply_delta(&stale).is_err());
+    }
+}
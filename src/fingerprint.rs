@@ -0,0 +1,218 @@
+//! Best-guess identification of the packager that produced a playlist.
+use crate::M3U8;
+
+/// Packagers and encoders that can be recognized from their playlists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Packager {
+    MediaConvert,
+    ShakaPackager,
+    Ffmpeg,
+    Wowza,
+    UnifiedStreaming,
+}
+
+/// The most likely packager with the signals that pointed to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginFingerprint {
+    pub packager: Packager,
+    /// Sum of the weights of the matching signals
+    pub score: u32,
+    pub evidence: Vec<&'static str>,
+}
+
+/// A weighted signal pointing to a packager
+struct Signal {
+    packager: Packager,
+    weight: u32,
+    description: &'static str,
+    matches: fn(&M3U8) -> bool,
+}
+
+const SIGNALS: &[Signal] = &[
+    Signal {
+        packager: Packager::ShakaPackager,
+        weight: 5,
+        description: "shaka-packager generator comment",
+        matches: |m3u8| m3u8.has_unknown_line("shaka-packager"),
+    },
+    Signal {
+        packager: Packager::UnifiedStreaming,
+        weight: 5,
+        description: "Unified Streaming generator comment",
+        matches: |m3u8| m3u8.has_unknown_line("Unified Streaming"),
+    },
+    Signal {
+        packager: Packager::UnifiedStreaming,
+        weight: 3,
+        description: ".ism server manifest in URIs",
+        matches: |m3u8| m3u8.any_uri(|uri| uri.contains(".ism/")),
+    },
+    Signal {
+        packager: Packager::Wowza,
+        weight: 4,
+        description: "chunklist or media_w session URIs",
+        matches: |m3u8| m3u8.any_uri(|uri| uri.contains("chunklist") || uri.contains("media_w")),
+    },
+    Signal {
+        packager: Packager::Wowza,
+        weight: 1,
+        description: "legacy EXT-X-ALLOW-CACHE tag",
+        matches: |m3u8| m3u8.allow_cache.is_some(),
+    },
+    Signal {
+        packager: Packager::MediaConvert,
+        weight: 3,
+        description: "five digit zero padded segment numbers",
+        matches: |m3u8| m3u8.any_uri(|uri| padded_number(uri) == Some(5)),
+    },
+    Signal {
+        packager: Packager::MediaConvert,
+        weight: 1,
+        description: "AVERAGE-BANDWIDTH and FRAME-RATE on every variant",
+        matches: |m3u8| {
+            !m3u8.variant_streams.is_empty()
+                && m3u8.variant_streams.iter().all(|variant| {
+                    variant.contains_key("AVERAGE-BANDWIDTH") && variant.contains_key("FRAME-RATE")
+                })
+        },
+    },
+    Signal {
+        packager: Packager::Ffmpeg,
+        weight: 2,
+        description: "unpadded sequential .ts filenames",
+        matches: |m3u8| {
+            !m3u8.segments.is_empty()
+                && m3u8.segments.iter().all(|segment| {
+                    segment.uri.ends_with(".ts") && padded_number(&segment.uri) == Some(0)
+                })
+        },
+    },
+    Signal {
+        packager: Packager::Ffmpeg,
+        weight: 1,
+        description: "variants without CODECS",
+        matches: |m3u8| {
+            !m3u8.variant_streams.is_empty()
+                && m3u8
+                    .variant_streams
+                    .iter()
+                    .all(|variant| !variant.contains_key("CODECS"))
+        },
+    },
+];
+
+/// Returns the zero padding of the number before the extension of a URI's filename
+///
+/// `Some(0)` means an unpadded number such as `segment12.ts`.
+fn padded_number(uri: &str) -> Option<usize> {
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    let stem = filename.split('.').next().unwrap_or(filename);
+    let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    match digits {
+        0 => None,
+        _ if stem[stem.len() - digits..].starts_with('0') && digits > 1 => Some(digits),
+        _ => Some(0),
+    }
+}
+
+/// Implementation of packager fingerprinting for M3U8
+impl M3U8 {
+    /// Guesses the packager or encoder from vendor tags, comments and URI and
+    /// attribute conventions
+    pub fn origin_fingerprint(&self) -> Option<OriginFingerprint> {
+        let matched: Vec<&Signal> = SIGNALS
+            .iter()
+            .filter(|signal| (signal.matches)(self))
+            .collect();
+        let mut best: Option<OriginFingerprint> = None;
+        for signal in &matched {
+            let signals = matched
+                .iter()
+                .filter(|other| other.packager == signal.packager);
+            let score = signals.clone().map(|other| other.weight).sum();
+            if best.as_ref().is_none_or(|best| score > best.score) {
+                best = Some(OriginFingerprint {
+                    packager: signal.packager,
+                    score,
+                    evidence: signals.map(|other| other.description).collect(),
+                });
+            }
+        }
+        best
+    }
+
+    /// Whether a comment or unrecognized tag contains `needle`
+    fn has_unknown_line(&self, needle: &str) -> bool {
+        self.unknown_lines
+            .iter()
+            .any(|unknown| unknown.line.contains(needle))
+    }
+
+    /// Whether any segment or variant URI satisfies `predicate`
+    fn any_uri(&self, predicate: impl Fn(&str) -> bool) -> bool {
+        self.segments
+            .iter()
+            .map(|segment| segment.uri.as_str())
+            .chain(
+                self.variant_streams
+                    .iter()
+                    .filter_map(|variant| variant.get("uri").map(|uri| uri.as_str())),
+            )
+            .any(predicate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{Packager, M3U8};
+
+    #[test]
+    /// Tests packagers are recognized from comments and naming conventions
+    fn it_fingerprints_packagers() {
+        let shaka = "#EXTM3U\n\
+            ## Generated with https://github.com/google/shaka-packager version v2.6.1\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,CODECS=\"avc1.64001f\"\nvideo.m3u8\n";
+        let fingerprint = M3U8::from_body(shaka)
+            .unwrap()
+            .origin_fingerprint()
+            .unwrap();
+        assert_eq!(fingerprint.packager, Packager::ShakaPackager);
+        assert_eq!(fingerprint.score, 5);
+
+        let media_convert = "#EXTM3U\n\
+            #EXTINF:6,\nmain_00001.ts\n#EXTINF:6,\nmain_00002.ts\n";
+        let fingerprint = M3U8::from_body(media_convert)
+            .unwrap()
+            .origin_fingerprint()
+            .unwrap();
+        assert_eq!(fingerprint.packager, Packager::MediaConvert);
+
+        let ffmpeg = "#EXTM3U\n#EXTINF:6,\nout0.ts\n#EXTINF:6,\nout1.ts\n";
+        let fingerprint = M3U8::from_body(ffmpeg)
+            .unwrap()
+            .origin_fingerprint()
+            .unwrap();
+        assert_eq!(fingerprint.packager, Packager::Ffmpeg);
+        assert_eq!(
+            fingerprint.evidence,
+            vec!["unpadded sequential .ts filenames"]
+        );
+
+        let wowza = "#EXTM3U\n#EXT-X-ALLOW-CACHE:NO\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,CODECS=\"avc1.64001f\"\nchunklist_w1234.m3u8\n";
+        let fingerprint = M3U8::from_body(wowza)
+            .unwrap()
+            .origin_fingerprint()
+            .unwrap();
+        assert_eq!(fingerprint.packager, Packager::Wowza);
+        assert_eq!(fingerprint.score, 5);
+
+        assert!(M3U8::from_body("#EXTM3U\n#EXTINF:6,\na.mp4\n")
+            .unwrap()
+            .origin_fingerprint()
+            .is_none());
+    }
+}
@@ -0,0 +1,188 @@
+//! Playlists reloaded with conditional requests.
+use crate::fetch::Validators;
+use crate::{FetchOptions, ParseError, Refresh, M3U8};
+
+/// Fetched playlist that can be reloaded politely
+///
+/// The ETag and Last-Modified headers of the last response are sent back as
+/// If-None-Match and If-Modified-Since, so an origin that supports them answers
+/// 304 Not Modified instead of the whole body. Bodies that hash the same as the
+/// last one are also reported as unchanged, for origins that ignore the headers.
+///
+/// With blocking reloads enabled, and EXT-X-SERVER-CONTROL advertising
+/// CAN-BLOCK-RELOAD, reloads ask for the next segment or part with the `_HLS_msn`
+/// and `_HLS_part` query parameters and the origin holds the request until it is
+/// published. Origins may hold a request for three target durations, so any read
+/// timeout in the options should allow for that.
+#[derive(Debug, Clone)]
+pub struct PlaylistHandle {
+    uri: String,
+    options: FetchOptions,
+    blocking_reload: bool,
+    validators: Validators,
+    hash: u64,
+    playlist: M3U8,
+}
+
+/// Implementation for PlaylistHandle
+impl PlaylistHandle {
+    /// Fetches and parses a playlist with default options
+    pub fn open(uri: &str) -> Result<Self, ParseError> {
+        PlaylistHandle::open_with_options(uri, FetchOptions::default())
+    }
+
+    /// Fetches and parses a playlist, keeping the options for every reload
+    pub fn open_with_options(uri: &str, options: FetchOptions) -> Result<Self, ParseError> {
+        let fetched = options
+            .fetch_if_modified(uri, &Validators::default())?
+            .ok_or_else(|| {
+                ParseError::InvalidM3U8(format!("Invalid unconditional response: {}", uri))
+            })?;
+        Ok(PlaylistHandle {
+            uri: uri.to_string(),
+            blocking_reload: false,
+            hash: M3U8::content_hash(&fetched.body),
+            playlist: M3U8::from_body_at(&fetched.body, &fetched.url)?,
+            options,
+            validators: fetched.validators,
+        })
+    }
+
+    /// Long-polls for the next update when the server can block reloads
+    pub fn with_blocking_reload(mut self, blocking_reload: bool) -> Self {
+        self.blocking_reload = blocking_reload;
+        self
+    }
+
+    /// Returns the URI the playlist is fetched from
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Returns the URI the next reload requests, with any blocking reload parameters
+    pub fn reload_uri(&self) -> String {
+        let can_block = self
+            .playlist
+            .server_control
+            .is_some_and(|server_control| server_control.can_block_reload);
+        if !self.blocking_reload || !can_block {
+            return self.uri.clone();
+        }
+        let next_sequence = self
+            .playlist
+            .segments
+            .last()
+            .map_or(self.playlist.media_sequence, |segment| segment.sequence + 1);
+        let separator = if self.uri.contains('?') { '&' } else { '?' };
+        match self.playlist.part_target {
+            Some(_) => format!(
+                "{}{}_HLS_msn={}&_HLS_part={}",
+                self.uri,
+                separator,
+                next_sequence,
+                self.playlist.pending_parts.len()
+            ),
+            None => format!("{}{}_HLS_msn={}", self.uri, separator, next_sequence),
+        }
+    }
+
+    /// Returns the playlist as of the last fetch that changed it
+    pub fn playlist(&self) -> &M3U8 {
+        &self.playlist
+    }
+
+    /// Returns the ETag of the last response, if the origin sent one
+    pub fn etag(&self) -> Option<&str> {
+        self.validators.etag.as_deref()
+    }
+
+    /// Returns the Last-Modified date of the last response, if the origin sent one
+    pub fn last_modified(&self) -> Option<&str> {
+        self.validators.last_modified.as_deref()
+    }
+
+    /// Fetches the playlist again if it was modified since the last fetch
+    pub fn reload(&mut self) -> Result<Refresh, ParseError> {
+        let Some(fetched) = self
+            .options
+            .fetch_if_modified(&self.reload_uri(), &self.validators)?
+        else {
+            return Ok(Refresh::Unchanged);
+        };
+        if fetched.validators != Validators::default() {
+            self.validators = fetched.validators;
+        }
+        let hash = M3U8::content_hash(&fetched.body);
+        if hash == self.hash {
+            return Ok(Refresh::Unchanged);
+        }
+        self.playlist = M3U8::from_body_at(&fetched.body, &fetched.url)?;
+        self.hash = hash;
+        Ok(Refresh::Changed(Box::new(self.playlist.clone())))
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+
+    use crate::testing::{serve_fixture, FixtureDir};
+    use crate::{PlaylistHandle, Refresh};
+
+    #[test]
+    /// Tests reloads send the validators back and only parse modified playlists
+    fn it_reloads_conditionally() {
+        let dir = FixtureDir::new("handle").unwrap();
+        std::fs::write(dir.join("live.m3u8"), "#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+        let (url, guard) = serve_fixture(&dir).unwrap();
+
+        let mut handle = PlaylistHandle::open(&format!("{}/live.m3u8", url)).unwrap();
+        assert!(handle.etag().is_some());
+        assert!(handle.last_modified().is_some());
+        assert!(matches!(handle.reload(), Ok(Refresh::Unchanged)));
+        assert_eq!(guard.not_modified(), 1);
+
+        std::fs::write(
+            dir.join("live.m3u8"),
+            "#EXTM3U\n#EXTINF:4,\na.ts\n#EXTINF:4,\nb.ts\n",
+        )
+        .unwrap();
+        match handle.reload().unwrap() {
+            Refresh::Changed(playlist) => assert_eq!(playlist.segments().len(), 2),
+            Refresh::Unchanged => panic!("expected a change"),
+        }
+        assert_eq!(handle.playlist().segments().len(), 2);
+        assert_eq!(guard.requests(), 3);
+    }
+
+    #[test]
+    /// Tests blocking reloads ask for the next part once the server allows it
+    fn it_requests_blocking_reloads() {
+        let dir = FixtureDir::new("block").unwrap();
+        std::fs::write(
+            dir.join("ll.m3u8"),
+            "#EXTM3U\n#EXT-X-TARGETDURATION:4\n#EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES\n\
+             #EXT-X-PART-INF:PART-TARGET=1\n#EXT-X-MEDIA-SEQUENCE:7\n\
+             #EXTINF:4,\na.mp4\n#EXTINF:4,\nb.mp4\n\
+             #EXT-X-PART:DURATION=1,URI=\"c.0.mp4\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("plain.m3u8"), "#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+        let (url, _guard) = serve_fixture(&dir).unwrap();
+
+        let uri = format!("{}/ll.m3u8", url);
+        let handle = PlaylistHandle::open(&uri).unwrap();
+        assert_eq!(handle.reload_uri(), uri);
+        let mut handle = handle.with_blocking_reload(true);
+        assert_eq!(
+            handle.reload_uri(),
+            format!("{}?_HLS_msn=9&_HLS_part=1", uri)
+        );
+        assert!(matches!(handle.reload(), Ok(Refresh::Unchanged)));
+
+        let plain = format!("{}/plain.m3u8", url);
+        let handle = PlaylistHandle::open(&plain)
+            .unwrap()
+            .with_blocking_reload(true);
+        assert_eq!(handle.reload_uri(), plain);
+    }
+}
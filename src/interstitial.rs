@@ -0,0 +1,110 @@
+//! Apple HLS interstitials carried in EXT-X-DATERANGE tags.
+use chrono::{DateTime, FixedOffset};
+
+use crate::{DateRange, M3U8};
+
+/// CLASS of date ranges that schedule interstitials
+const INTERSTITIAL_CLASS: &str = "com.apple.hls.interstitial";
+
+/// Interstitial content scheduled by a date range
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interstitial {
+    pub id: String,
+    pub start_date: DateTime<FixedOffset>,
+    pub duration: Option<f64>,
+    /// URI of a single interstitial asset, exclusive with `asset_list`
+    pub asset_uri: Option<String>,
+    /// URI of a JSON asset list, exclusive with `asset_uri`
+    pub asset_list: Option<String>,
+    /// Seconds into the primary content to resume at, after the start date
+    pub resume_offset: Option<f64>,
+    /// Maximum seconds of interstitial playback
+    pub playout_limit: Option<f64>,
+    /// Seeking within the interstitial is not allowed
+    pub restrict_skip: bool,
+    /// Seeking past the interstitial without playing it is not allowed
+    pub restrict_jump: bool,
+}
+
+/// Implementation for Interstitial
+impl Interstitial {
+    /// Reads an interstitial from a date range, if it is one and names an asset
+    fn from_date_range(date_range: &DateRange) -> Option<Interstitial> {
+        if date_range.class.as_deref() != Some(INTERSTITIAL_CLASS) {
+            return None;
+        }
+        let attribute = |key: &str| date_range.client_attributes.get(key).cloned();
+        let seconds = |key: &str| attribute(key)?.trim().parse().ok();
+        let asset_uri = attribute("X-ASSET-URI");
+        let asset_list = attribute("X-ASSET-LIST");
+        if asset_uri.is_none() == asset_list.is_none() {
+            return None;
+        }
+        let restrict = attribute("X-RESTRICT").unwrap_or_default();
+        let restricts = |value: &str| restrict.split(',').any(|item| item.trim() == value);
+        Some(Interstitial {
+            id: date_range.id.to_string(),
+            start_date: date_range.start_date,
+            duration: date_range.duration.or(date_range.planned_duration),
+            asset_uri,
+            asset_list,
+            resume_offset: seconds("X-RESUME-OFFSET"),
+            playout_limit: seconds("X-PLAYOUT-LIMIT"),
+            restrict_skip: restricts("SKIP"),
+            restrict_jump: restricts("JUMP"),
+        })
+    }
+}
+
+/// Implementation of interstitial lookup for M3U8
+impl M3U8 {
+    /// Returns the interstitials scheduled by `com.apple.hls.interstitial` date ranges
+    ///
+    /// Date ranges of that class naming neither or both of X-ASSET-URI and
+    /// X-ASSET-LIST are invalid and skipped.
+    pub fn interstitials(&self) -> Vec<Interstitial> {
+        self.date_ranges
+            .iter()
+            .filter_map(Interstitial::from_date_range)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::M3U8;
+
+    #[test]
+    /// Tests interstitial date ranges are typed and other classes ignored
+    fn it_extracts_interstitials() {
+        let body = "#EXTM3U\n\
+            #EXT-X-DATERANGE:ID=\"ad1\",CLASS=\"com.apple.hls.interstitial\",\
+            START-DATE=\"2024-01-01T00:00:10Z\",DURATION=15,\
+            X-ASSET-URI=\"https://ads.example.com/ad1.m3u8\",X-RESUME-OFFSET=0,\
+            X-RESTRICT=\"SKIP,JUMP\"\n\
+            #EXT-X-DATERANGE:ID=\"ad2\",CLASS=\"com.apple.hls.interstitial\",\
+            START-DATE=\"2024-01-01T00:01:00Z\",X-ASSET-LIST=\"https://ads.example.com/list.json\",\
+            X-PLAYOUT-LIMIT=30\n\
+            #EXT-X-DATERANGE:ID=\"bad\",CLASS=\"com.apple.hls.interstitial\",\
+            START-DATE=\"2024-01-01T00:02:00Z\"\n\
+            #EXT-X-DATERANGE:ID=\"chapter\",CLASS=\"com.example.chapter\",\
+            START-DATE=\"2024-01-01T00:00:00Z\"\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let interstitials = parsed.interstitials();
+        assert_eq!(interstitials.len(), 2);
+        assert_eq!(
+            interstitials[0].asset_uri.as_deref(),
+            Some("https://ads.example.com/ad1.m3u8")
+        );
+        assert_eq!(interstitials[0].duration, Some(15.0));
+        assert_eq!(interstitials[0].resume_offset, Some(0.0));
+        assert!(interstitials[0].restrict_skip && interstitials[0].restrict_jump);
+        assert_eq!(interstitials[1].id, "ad2");
+        assert!(interstitials[1].asset_uri.is_none());
+        assert_eq!(interstitials[1].playout_limit, Some(30.0));
+        assert!(!interstitials[1].restrict_skip);
+    }
+}
@@ -0,0 +1,493 @@
+//! EXT-X-KEY encryption keys and a cache for fetched key material.
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+
+use crate::daterange::{decode_hex, encode_hex};
+use crate::{FetchOptions, ParseError, M3U8};
+
+/// Encryption method of an EXT-X-KEY
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyMethod {
+    None,
+    Aes128,
+    SampleAes,
+    SampleAesCtr,
+}
+
+/// Implementation for KeyMethod
+impl FromStr for KeyMethod {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<KeyMethod, Self::Err> {
+        match input {
+            "NONE" => Ok(KeyMethod::None),
+            "AES-128" => Ok(KeyMethod::Aes128),
+            "SAMPLE-AES" => Ok(KeyMethod::SampleAes),
+            "SAMPLE-AES-CTR" => Ok(KeyMethod::SampleAesCtr),
+            _ => Err(ParseError::InvalidM3U8(format!(
+                "Invalid #EXT-X-KEY METHOD: {}",
+                input
+            ))),
+        }
+    }
+}
+
+/// KeyMethod as a Display type
+impl fmt::Display for KeyMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyMethod::None => write!(f, "NONE"),
+            KeyMethod::Aes128 => write!(f, "AES-128"),
+            KeyMethod::SampleAes => write!(f, "SAMPLE-AES"),
+            KeyMethod::SampleAesCtr => write!(f, "SAMPLE-AES-CTR"),
+        }
+    }
+}
+
+/// How segments are encrypted, from EXT-X-KEY
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptionKey {
+    pub method: KeyMethod,
+    pub uri: Option<String>,
+    /// Explicit initialization vector
+    pub iv: Option<[u8; 16]>,
+    /// Key format, where absent means the `identity` format
+    pub keyformat: Option<String>,
+    pub keyformat_versions: Option<String>,
+}
+
+/// Implementation for EncryptionKey
+impl EncryptionKey {
+    /// Builds an encryption key from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<EncryptionKey, ParseError> {
+        let method: KeyMethod = attributes
+            .get("METHOD")
+            .ok_or_else(|| {
+                ParseError::InvalidM3U8("Missing #EXT-X-KEY METHOD attribute".to_string())
+            })?
+            .parse()?;
+        let uri = attributes.get("URI").cloned();
+        if method != KeyMethod::None && uri.is_none() {
+            return Err(ParseError::InvalidM3U8(
+                "Missing #EXT-X-KEY URI attribute".to_string(),
+            ));
+        }
+        let iv = attributes
+            .get("IV")
+            .map(|value| {
+                decode_hex(value)
+                    .and_then(|bytes| <[u8; 16]>::try_from(bytes).ok())
+                    .ok_or_else(|| {
+                        ParseError::InvalidM3U8("Invalid #EXT-X-KEY IV attribute".to_string())
+                    })
+            })
+            .transpose()?;
+        Ok(EncryptionKey {
+            method,
+            uri,
+            iv,
+            keyformat: attributes.get("KEYFORMAT").cloned(),
+            keyformat_versions: attributes.get("KEYFORMATVERSIONS").cloned(),
+        })
+    }
+}
+
+/// Implementation of IV derivation for EncryptionKey
+impl EncryptionKey {
+    /// IV used to decrypt the segment with `media_sequence`
+    ///
+    /// Without an explicit IV, the media sequence number is used as a big-endian
+    /// 128-bit integer.
+    pub fn effective_iv(&self, media_sequence: u64) -> [u8; 16] {
+        self.iv
+            .unwrap_or_else(|| u128::from(media_sequence).to_be_bytes())
+    }
+}
+
+/// An explicit IV shared by different keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IvReuse {
+    pub iv: [u8; 16],
+    /// URIs of the keys using the IV, in playlist order
+    pub key_uris: Vec<String>,
+}
+
+/// EncryptionKey as a Display type, writing its attribute list
+impl fmt::Display for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "METHOD={}", self.method)?;
+        if let Some(uri) = &self.uri {
+            write!(f, ",URI={}", M3U8::quote(uri))?;
+        }
+        if let Some(iv) = &self.iv {
+            write!(f, ",IV={}", encode_hex(iv))?;
+        }
+        if let Some(keyformat) = &self.keyformat {
+            write!(f, ",KEYFORMAT={}", M3U8::quote(keyformat))?;
+        }
+        if let Some(keyformat_versions) = &self.keyformat_versions {
+            write!(f, ",KEYFORMATVERSIONS={}", M3U8::quote(keyformat_versions))?;
+        }
+        Ok(())
+    }
+}
+
+/// Implementation of key linting for M3U8
+impl M3U8 {
+    /// Flags explicit IVs that are used with more than one key URI
+    pub fn repeated_ivs(&self) -> Vec<IvReuse> {
+        let mut reuses: Vec<IvReuse> = Vec::new();
+        let keys = self.segments.iter().flat_map(|segment| &segment.keys);
+        for key in keys.filter(|key| key.method != KeyMethod::None) {
+            let (Some(iv), Some(uri)) = (key.iv, &key.uri) else {
+                continue;
+            };
+            match reuses.iter_mut().find(|reuse| reuse.iv == iv) {
+                Some(reuse) if !reuse.key_uris.contains(uri) => {
+                    reuse.key_uris.push(uri.to_string())
+                }
+                Some(_) => (),
+                None => reuses.push(IvReuse {
+                    iv,
+                    key_uris: vec![uri.to_string()],
+                }),
+            }
+        }
+        reuses.retain(|reuse| reuse.key_uris.len() > 1);
+        reuses
+    }
+}
+
+/// Distinguishes nonces written in the same nanosecond
+static NONCE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Encrypts or decrypts with AES-128 in counter mode
+fn apply_keystream(secret: &[u8; 16], nonce: u128, data: &mut [u8]) {
+    let cipher = Aes128::new(secret.into());
+    for (index, chunk) in data.chunks_mut(16).enumerate() {
+        let mut block = nonce.wrapping_add(index as u128).to_be_bytes().into();
+        cipher.encrypt_block(&mut block);
+        for (byte, key) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= key;
+        }
+    }
+}
+
+/// A fetched key and when it was fetched
+#[derive(Debug, Clone)]
+struct CachedKey {
+    bytes: Vec<u8>,
+    fetched_at: SystemTime,
+}
+
+/// Caches fetched key material by absolute key URI and KEYFORMAT
+///
+/// Entries expire after the TTL, when one is set. With a disk cache, keys are
+/// persisted AES-128 encrypted under the given secret and survive restarts.
+#[derive(Debug)]
+pub struct KeyCache {
+    entries: HashMap<(String, Option<String>), CachedKey>,
+    ttl: Option<Duration>,
+    disk: Option<(PathBuf, [u8; 16])>,
+    options: FetchOptions,
+    fetch: fn(&FetchOptions, &str) -> Result<Vec<u8>, ParseError>,
+}
+
+/// Fetches key material with the cache's options
+fn fetch_key(options: &FetchOptions, uri: &str) -> Result<Vec<u8>, ParseError> {
+    options.fetch_bytes(uri)
+}
+
+/// Implementation for KeyCache
+impl Default for KeyCache {
+    fn default() -> Self {
+        KeyCache::new()
+    }
+}
+
+/// Implementation for KeyCache
+impl KeyCache {
+    /// In-memory cache where keys never expire
+    pub fn new() -> Self {
+        KeyCache {
+            entries: HashMap::new(),
+            ttl: None,
+            disk: None,
+            options: FetchOptions::default(),
+            fetch: fetch_key,
+        }
+    }
+
+    /// Time after which a cached key is fetched again
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Options used to fetch keys, such as timeouts, headers and scheme handlers
+    pub fn with_options(mut self, options: FetchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Persists keys in `dir`, encrypted with `secret`
+    pub fn with_disk_cache(mut self, dir: impl Into<PathBuf>, secret: [u8; 16]) -> Self {
+        self.disk = Some((dir.into(), secret));
+        self
+    }
+
+    /// Returns the key material for a key of `playlist`, fetching it when not cached
+    /// or expired
+    ///
+    /// The key URI is resolved against the base URI of the playlist.
+    pub fn get(&mut self, playlist: &M3U8, key: &EncryptionKey) -> Result<Vec<u8>, ParseError> {
+        let uri = key.absolute_uri(playlist).ok_or_else(|| {
+            ParseError::InvalidM3U8("Encryption key has no URI to fetch".to_string())
+        })?;
+        let cache_key = (uri.clone(), key.keyformat.clone());
+        let cached = match self.entries.get(&cache_key) {
+            Some(cached) => Some(cached.clone()),
+            None => self.read_disk(&cache_key),
+        };
+        if let Some(cached) = cached.filter(|cached| !self.is_expired(cached)) {
+            self.entries.insert(cache_key, cached.clone());
+            return Ok(cached.bytes);
+        }
+        let cached = CachedKey {
+            bytes: (self.fetch)(&self.options, &uri)?,
+            fetched_at: SystemTime::now(),
+        };
+        self.write_disk(&cache_key, &cached)?;
+        self.entries.insert(cache_key, cached.clone());
+        Ok(cached.bytes)
+    }
+
+    /// Drops a cached key, by absolute URI, so it is fetched again on next use
+    pub fn invalidate(&mut self, uri: &str, keyformat: Option<&str>) {
+        let cache_key = (
+            uri.to_string(),
+            keyformat.map(|keyformat| keyformat.to_string()),
+        );
+        self.entries.remove(&cache_key);
+        if let Some(path) = self.disk_path(&cache_key) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Drops every key held in memory
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Whether a cached key is older than the TTL
+    fn is_expired(&self, cached: &CachedKey) -> bool {
+        self.ttl.is_some_and(|ttl| {
+            cached
+                .fetched_at
+                .elapsed()
+                .map_or(true, |elapsed| elapsed > ttl)
+        })
+    }
+
+    /// File holding a key in the disk cache
+    fn disk_path(&self, (uri, keyformat): &(String, Option<String>)) -> Option<PathBuf> {
+        let (dir, _) = self.disk.as_ref()?;
+        let name = format!("{}\n{}", uri, keyformat.as_deref().unwrap_or("identity"));
+        Some(dir.join(format!("{:016x}.key", M3U8::content_hash(&name))))
+    }
+
+    /// Reads and decrypts a key from the disk cache
+    fn read_disk(&self, cache_key: &(String, Option<String>)) -> Option<CachedKey> {
+        let (_, secret) = self.disk.as_ref()?;
+        let data = std::fs::read(self.disk_path(cache_key)?).ok()?;
+        let nonce = u128::from_be_bytes(data.get(..16)?.try_into().ok()?);
+        let mut plain = data.get(16..)?.to_vec();
+        apply_keystream(secret, nonce, &mut plain);
+        let seconds = u64::from_be_bytes(plain.get(..8)?.try_into().ok()?);
+        Some(CachedKey {
+            bytes: plain[8..].to_vec(),
+            fetched_at: UNIX_EPOCH + Duration::from_secs(seconds),
+        })
+    }
+
+    /// Encrypts and writes a key to the disk cache
+    fn write_disk(
+        &self,
+        cache_key: &(String, Option<String>),
+        cached: &CachedKey,
+    ) -> Result<(), ParseError> {
+        let (Some((dir, secret)), Some(path)) = (&self.disk, self.disk_path(cache_key)) else {
+            return Ok(());
+        };
+        let since_epoch = cached
+            .fetched_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let nonce = (since_epoch.as_nanos() << 16)
+            ^ u128::from(NONCE_COUNTER.fetch_add(1, Ordering::Relaxed));
+        let mut plain = since_epoch.as_secs().to_be_bytes().to_vec();
+        plain.extend_from_slice(&cached.bytes);
+        apply_keystream(secret, nonce, &mut plain);
+        std::fs::create_dir_all(dir)?;
+        let mut data = nonce.to_be_bytes().to_vec();
+        data.extend_from_slice(&plain);
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::{EncryptionKey, FetchOptions, IvReuse, KeyCache, KeyMethod, ParseError, M3U8};
+
+    static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+    /// Fetcher returning the URI as key material and counting calls
+    fn fake_fetch(_: &FetchOptions, uri: &str) -> Result<Vec<u8>, ParseError> {
+        FETCHES.fetch_add(1, Ordering::SeqCst);
+        Ok(uri.as_bytes().to_vec())
+    }
+
+    #[test]
+    /// Tests keys apply to the segments after them and round trip
+    fn it_parses_encryption_keys() {
+        let body = "#EXTM3U\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"key1\",IV=0x000102030405060708090A0B0C0D0E0F\n\
+            #EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"skd://key\",KEYFORMAT=\"com.apple.streamingkeydelivery\"\n\
+            #EXTINF:6,\na.ts\n#EXTINF:6,\nb.ts\n\
+            #EXT-X-KEY:METHOD=NONE\n#EXTINF:6,\nc.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let segments = parsed.segments();
+        assert_eq!(segments[0].keys.len(), 2);
+        assert_eq!(segments[0].keys[0].method, KeyMethod::Aes128);
+        assert_eq!(segments[0].keys[0].iv.unwrap()[15], 0x0F);
+        assert_eq!(segments[1].keys, segments[0].keys);
+        assert!(segments[2].keys.is_empty());
+
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.segments(), parsed.segments());
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-KEY:METHOD=AES-128\n").is_err());
+    }
+
+    #[test]
+    /// Tests keys are cached per KEYFORMAT, expire, and persist on disk
+    fn it_caches_keys() {
+        let key = |keyformat: Option<&str>| EncryptionKey {
+            method: KeyMethod::Aes128,
+            uri: Some("https://keys/1".to_string()),
+            iv: None,
+            keyformat: keyformat.map(|keyformat| keyformat.to_string()),
+            keyformat_versions: None,
+        };
+        let playlist = M3U8::from_body("#EXTM3U\n").unwrap();
+        let dir = crate::testing::FixtureDir::new("key-cache").unwrap();
+        let mut cache = KeyCache::new().with_disk_cache(dir.to_path_buf(), [7; 16]);
+        cache.fetch = fake_fetch;
+
+        assert_eq!(cache.get(&playlist, &key(None)).unwrap(), b"https://keys/1");
+        cache.get(&playlist, &key(None)).unwrap();
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 1);
+        cache.get(&playlist, &key(Some("com.example"))).unwrap();
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 2);
+
+        let stored = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap();
+        let data = std::fs::read(stored.path()).unwrap();
+        assert!(!data.windows(5).any(|window| window == b"keys/"));
+
+        let mut restarted = KeyCache::new().with_disk_cache(dir.to_path_buf(), [7; 16]);
+        restarted.fetch = fake_fetch;
+        assert_eq!(
+            restarted.get(&playlist, &key(None)).unwrap(),
+            b"https://keys/1"
+        );
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 2);
+
+        restarted.invalidate("https://keys/1", None);
+        restarted.get(&playlist, &key(None)).unwrap();
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 3);
+
+        let mut expiring = KeyCache::new().with_ttl(Duration::ZERO);
+        expiring.fetch = fake_fetch;
+        expiring.get(&playlist, &key(None)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        expiring.get(&playlist, &key(None)).unwrap();
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 5);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    /// Tests relative key URIs are fetched against the playlist and errors are not cached
+    fn it_fetches_keys_relative_to_the_playlist() {
+        let dir = crate::testing::FixtureDir::new("key-fetch").unwrap();
+        std::fs::create_dir_all(dir.join("live")).unwrap();
+        std::fs::write(
+            dir.join("live/media.m3u8"),
+            "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"\n#EXTINF:4,\na.ts\n\
+             #EXT-X-KEY:METHOD=AES-128,URI=\"missing.bin\"\n#EXTINF:4,\nb.ts\n\
+             #EXT-X-KEY:METHOD=AES-128,URI=\"data:;base64,AAECAw==\"\n#EXTINF:4,\nc.ts\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("live/key.bin"), [0xFF, 0, 1, 2]).unwrap();
+        let (url, server) = crate::testing::serve_fixture(&dir).unwrap();
+        let playlist = M3U8::from_uri(&format!("{}/live/media.m3u8", url)).unwrap();
+        let key = |index: usize| playlist.segments()[index].keys[0].clone();
+        let mut cache = KeyCache::new().with_options(FetchOptions::new().with_max_size(16));
+
+        assert_eq!(cache.get(&playlist, &key(0)).unwrap(), vec![0xFF, 0, 1, 2]);
+        assert!(matches!(
+            cache.get(&playlist, &key(1)),
+            Err(ParseError::ResponseRejected { .. })
+        ));
+        assert!(cache.get(&playlist, &key(1)).is_err());
+        assert_eq!(cache.get(&playlist, &key(2)).unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(server.requests(), 4);
+    }
+
+    #[test]
+    /// Tests IVs derive from the media sequence and explicit IV reuse is flagged
+    fn it_derives_and_lints_ivs() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA-SEQUENCE:258\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"k1\"\n#EXTINF:6,\na.ts\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"k2\",IV=0x0000000000000000000000000000AB01\n#EXTINF:6,\nb.ts\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"k3\",IV=0x0000000000000000000000000000AB01\n#EXTINF:6,\nc.ts\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"k3\",IV=0x0000000000000000000000000000AB01\n#EXTINF:6,\nd.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let first = &parsed.segments()[0];
+        let iv = first.keys[0].effective_iv(first.sequence);
+        assert_eq!(iv[14..], [0x01, 0x02]);
+        assert!(iv[..14].iter().all(|&byte| byte == 0));
+        let second = &parsed.segments()[1];
+        assert_eq!(
+            second.keys[0].effective_iv(second.sequence)[14..],
+            [0xAB, 0x01]
+        );
+
+        let mut repeated = [0; 16];
+        repeated[14..].copy_from_slice(&[0xAB, 0x01]);
+        assert_eq!(
+            parsed.repeated_ivs(),
+            vec![IvReuse {
+                iv: repeated,
+                key_uris: vec!["k2".to_string(), "k3".to_string()],
+            }]
+        );
+    }
+}
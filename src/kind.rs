@@ -0,0 +1,110 @@
+//! Telling master playlists from media playlists.
+use crate::{FetchOptions, MasterPlaylist, MediaPlaylist, ParseError, M3U8};
+
+/// Whether a playlist lists variant streams or media segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlaylistKind {
+    Master,
+    Media,
+}
+
+/// A parsed playlist, tagged with its kind
+#[derive(Debug, Clone)]
+pub enum Playlist {
+    Master(MasterPlaylist),
+    Media(MediaPlaylist),
+}
+
+/// Implementation for Playlist
+impl Playlist {
+    /// Fetches and parses a playlist, detecting its kind
+    pub fn from_uri(uri: &str) -> Result<Playlist, ParseError> {
+        Ok(M3U8::from_uri(uri)?.into())
+    }
+
+    /// Like from_uri, but fetches with the given options
+    pub fn from_uri_with_options(
+        uri: &str,
+        options: &FetchOptions,
+    ) -> Result<Playlist, ParseError> {
+        Ok(M3U8::from_uri_with_options(uri, options)?.into())
+    }
+
+    /// Returns the kind of the playlist
+    pub fn kind(&self) -> PlaylistKind {
+        match self {
+            Playlist::Master(_) => PlaylistKind::Master,
+            Playlist::Media(_) => PlaylistKind::Media,
+        }
+    }
+
+    /// Returns the master playlist, if this is one
+    pub fn as_master(&self) -> Option<&MasterPlaylist> {
+        match self {
+            Playlist::Master(master) => Some(master),
+            Playlist::Media(_) => None,
+        }
+    }
+
+    /// Returns the media playlist, if this is one
+    pub fn as_media(&self) -> Option<&MediaPlaylist> {
+        match self {
+            Playlist::Media(media) => Some(media),
+            Playlist::Master(_) => None,
+        }
+    }
+
+    /// Returns the parsed playlist whatever its kind
+    pub fn into_inner(self) -> M3U8 {
+        match self {
+            Playlist::Master(m3u8) | Playlist::Media(m3u8) => m3u8,
+        }
+    }
+}
+
+/// Converts a parsed M3U8 by detecting its kind
+impl From<M3U8> for Playlist {
+    fn from(m3u8: M3U8) -> Playlist {
+        match m3u8.kind() {
+            PlaylistKind::Master => Playlist::Master(m3u8),
+            PlaylistKind::Media => Playlist::Media(m3u8),
+        }
+    }
+}
+
+/// Implementation of kind detection for M3U8
+impl M3U8 {
+    /// Returns Master when the playlist lists variant or I-frame streams, else Media
+    pub fn kind(&self) -> PlaylistKind {
+        if self.variant_streams.is_empty() && self.media_resources.is_empty() {
+            PlaylistKind::Media
+        } else {
+            PlaylistKind::Master
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{Playlist, PlaylistKind, M3U8};
+
+    #[test]
+    /// Tests playlists are told apart by variant streams and segments
+    fn it_detects_playlist_kinds() {
+        let master = M3U8::from_body("#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1\nlow.m3u8\n").unwrap();
+        let media = M3U8::from_body("#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+
+        assert_eq!(master.kind(), PlaylistKind::Master);
+        assert_eq!(media.kind(), PlaylistKind::Media);
+
+        let playlist = Playlist::from(master);
+        assert_eq!(playlist.kind(), PlaylistKind::Master);
+        assert!(playlist.as_media().is_none());
+        assert_eq!(playlist.as_master().unwrap().variant_streams().len(), 1);
+        match Playlist::from(media) {
+            Playlist::Media(media) => assert_eq!(media.segments().len(), 1),
+            Playlist::Master(_) => panic!("expected a media playlist"),
+        }
+    }
+}
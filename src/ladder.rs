@@ -0,0 +1,138 @@
+//! Grouping variant streams of a multi-codec ladder.
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::{Resolution, VariantStream, M3U8};
+
+/// Sample entry codes of the same video codec, mapped to one name
+const VIDEO_CODECS: &[(&str, &str)] = &[
+    ("avc1", "avc1"),
+    ("avc3", "avc1"),
+    ("hvc1", "hvc1"),
+    ("hev1", "hvc1"),
+    ("dvh1", "dvh1"),
+    ("dvhe", "dvh1"),
+    ("av01", "av01"),
+    ("vp09", "vp09"),
+];
+
+/// Variants carrying the same rendition in different video codecs
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodecGroup {
+    pub resolution: Option<Resolution>,
+    pub frame_rate: Option<f64>,
+    /// Variants of the group, highest BANDWIDTH first
+    pub variants: Vec<VariantStream>,
+}
+
+/// Implementation for CodecGroup
+impl CodecGroup {
+    /// Returns the video codecs available in this group
+    pub fn codecs(&self) -> Vec<&'static str> {
+        self.variants
+            .iter()
+            .filter_map(|variant| variant.video_codec())
+            .collect()
+    }
+
+    /// Returns the variant of this group using a video codec, such as `hvc1`
+    pub fn variant_for(&self, codec: &str) -> Option<&VariantStream> {
+        let codec = normalize(codec)?;
+        self.variants
+            .iter()
+            .find(|variant| variant.video_codec() == Some(codec))
+    }
+}
+
+/// Returns the shared name of a video sample entry code
+fn normalize(codec: &str) -> Option<&'static str> {
+    let entry = codec.split('.').next()?.trim();
+    VIDEO_CODECS
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(entry))
+        .map(|(_, name)| *name)
+}
+
+/// Implementation of codec lookups for VariantStream
+impl VariantStream {
+    /// Returns the video codec of the variant, with aliases such as `hev1` reported
+    /// as `hvc1`
+    pub fn video_codec(&self) -> Option<&'static str> {
+        self.codecs.iter().find_map(|codec| normalize(codec))
+    }
+}
+
+/// Implementation of codec grouping for M3U8
+impl M3U8 {
+    /// Returns variant streams grouped into renditions offered in several video codecs
+    ///
+    /// Variants with the same RESOLUTION and FRAME-RATE form a tier. When a tier holds
+    /// more than one variant of a codec, the variants of each codec are paired by
+    /// descending BANDWIDTH. Variants whose attributes cannot be parsed are skipped.
+    pub fn codec_groups(&self) -> Vec<CodecGroup> {
+        let mut tiers: Vec<(Option<Resolution>, Option<i64>, Vec<VariantStream>)> = vec![];
+        for variant in self.iter_variants().filter_map(Result::ok) {
+            let frame_rate = variant.frame_rate.map(|rate| rate.round() as i64);
+            match tiers.iter_mut().find(|(resolution, rate, _)| {
+                *resolution == variant.resolution && *rate == frame_rate
+            }) {
+                Some((_, _, variants)) => variants.push(variant),
+                None => tiers.push((variant.resolution, frame_rate, vec![variant])),
+            }
+        }
+
+        let mut groups = vec![];
+        for (resolution, _, mut variants) in tiers {
+            variants.sort_by_key(|variant| Reverse(variant.bandwidth));
+            let mut ranks: HashMap<Option<&'static str>, usize> = HashMap::new();
+            let mut tier_groups: Vec<CodecGroup> = vec![];
+            for variant in variants {
+                let rank = ranks.entry(variant.video_codec()).or_default();
+                if *rank == tier_groups.len() {
+                    tier_groups.push(CodecGroup {
+                        resolution,
+                        frame_rate: variant.frame_rate,
+                        variants: vec![],
+                    });
+                }
+                tier_groups[*rank].variants.push(variant);
+                *rank += 1;
+            }
+            groups.extend(tier_groups);
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::M3U8;
+
+    #[test]
+    /// Tests variants of the same tier in different codecs are grouped together
+    fn it_groups_variants_by_codec() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=6000000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080\n\
+            avc-1080.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=3500000,CODECS=\"hev1.2.4.L123.B0\",RESOLUTION=1920x1080\n\
+            hevc-1080.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2800000,CODECS=\"av01.0.08M.08\",RESOLUTION=1920x1080\n\
+            av1-1080.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=4500000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080\n\
+            avc-1080-low.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1500000,CODECS=\"avc1.4d401f\",RESOLUTION=640x360\n\
+            avc-360.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let groups = parsed.codec_groups();
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].codecs(), vec!["avc1", "hvc1", "av01"]);
+        assert_eq!(groups[0].variant_for("hvc1").unwrap().uri, "hevc-1080.m3u8");
+        assert_eq!(groups[1].codecs(), vec!["avc1"]);
+        assert_eq!(groups[1].variants[0].uri, "avc-1080-low.m3u8");
+        assert_eq!(groups[2].resolution.unwrap().height, 360);
+        assert!(groups[2].variant_for("av01").is_none());
+    }
+}
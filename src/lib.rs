@@ -5,9 +5,124 @@
 //! let uri = "http://<domain>/path/playlist.m3u8"
 //! let parsed_m3u8 = M3U8::from_uri(uri).unwrap();
 //!
+//! let parsed_m3u8: M3U8 = body.parse().unwrap();
+//!
+//! The common types can be imported at once with `use m3u8parse::prelude::*;`.
+//!
+//! # Stability
+//!
+//! Enums that grow as HLS adds tags and attributes, such as `ParseError`, `Tag` and
+//! `MediaType`, are `#[non_exhaustive]`: match them with a wildcard arm. New variants
+//! and new accessors are added in minor releases; removing or renaming public items
+//! only happens in major releases.
+//!
 use std::collections::HashMap;
 use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+
+use builder::TagHandler;
+use fetch::Fetched;
+
+#[cfg(all(feature = "http", target_arch = "wasm32"))]
+compile_error!(
+    "the http feature uses reqwest's blocking client, which wasm32 lacks: build with \
+     default-features = false and fetch with an AsyncPlaylistFetcher"
+);
+
+mod analysis;
+mod builder;
+#[cfg(feature = "async")]
+mod bulk;
+mod capture;
+mod chaos;
+mod codec;
+mod cue;
+mod daterange;
+mod delta;
+mod fetch;
+mod fingerprint;
+mod handle;
+mod interstitial;
+mod key;
+mod kind;
+mod ladder;
+mod live;
+mod looping;
+mod monitor;
+mod playlist_builder;
+mod policy;
+pub mod prelude;
+mod program_date_time;
+mod query;
+mod recover;
+mod rendition;
+mod repair;
+mod segment;
+mod select;
+mod spec;
+mod steering;
+mod tags;
+mod telemetry;
+mod template;
+pub mod testing;
+mod timeline;
+mod tree;
+mod uri;
+mod variant;
+mod version;
+mod webvtt;
+
+pub use analysis::{BitrateReport, UriAnomaly};
+pub use builder::ParserBuilder;
+pub use capture::{FetchProfile, ManifestDifference, ProfileCapture};
+pub use chaos::{ChaosFetcher, Fault};
+pub use codec::{Av1Profile, AvcProfile, Codec, CodecError, CodecTier, HevcProfile, VideoCodec};
+pub use cue::{AdBreak, AdCue};
+pub use daterange::DateRange;
+pub use fetch::{
+    AsyncPlaylistFetcher, FetchError, FetchFuture, FetchOptions, PlaylistFetcher, SchemeHandler,
+    UserAgent,
+};
+pub use fingerprint::{OriginFingerprint, Packager};
+pub use handle::PlaylistHandle;
+pub use interstitial::Interstitial;
+pub use key::{EncryptionKey, IvReuse, KeyCache, KeyMethod};
+pub use kind::{Playlist, PlaylistKind};
+pub use ladder::CodecGroup;
+pub use live::LivePlaylist;
+pub use looping::LiveLoop;
+pub use monitor::{ContentChange, Monitor, Refresh};
+pub use playlist_builder::{MasterPlaylistBuilder, MediaPlaylistBuilder};
+pub use policy::{Condition, Policy, PolicyAction, PolicyRule, PolicyTarget};
+pub use program_date_time::PdtInterval;
+pub use query::VariantQuery;
+pub use recover::ParseWarning;
+pub use rendition::Rendition;
+pub use repair::Repair;
+/// Cookie store shared between fetches, see `FetchOptions::with_cookie_jar`
+#[cfg(feature = "cookies")]
+pub use reqwest::cookie::Jar as CookieJar;
+pub use segment::{ByteRange, InitSection, PartialSegment, Segment};
+pub use select::ForcedSubtitles;
+pub use spec::{SpecVersion, SpecViolation};
+pub use steering::{ContentSteering, PathwayClone, SteeringManifest, UriReplacement};
+pub use tags::{
+    PlaylistType, PreloadHint, PreloadHintType, RenditionReport, ServerControl, SessionData, Skip,
+    StartPoint, Tiles, UnknownLine,
+};
+pub use telemetry::TelemetrySummary;
+pub use template::UriTemplate;
+pub use tree::PlaylistTree;
+pub use variant::{
+    AttributeError, ClosedCaptions, HdcpLevel, Resolution, VariantStream, VideoRange,
+};
+pub use webvtt::{CueTimingIssue, TimestampMap};
 
 const EXTM3U: &str = "#EXTM3U";
 const EXT_X_INDEPENDENT_SEGMENTS: &str = "#EXT-X-INDEPENDENT-SEGMENTS";
@@ -15,30 +130,199 @@ const EXT_X_VERSION: &str = "#EXT-X-VERSION";
 const EXT_X_MEDIA: &str = "#EXT-X-MEDIA";
 const EXT_X_I_FRAME_STREAM_INF: &str = "#EXT-X-I-FRAME-STREAM-INF";
 const EXT_X_STREAM_INF: &str = "#EXT-X-STREAM-INF";
+const EXTINF: &str = "#EXTINF";
+const EXT_X_BYTERANGE: &str = "#EXT-X-BYTERANGE";
+const EXT_X_DISCONTINUITY: &str = "#EXT-X-DISCONTINUITY";
+const EXT_X_DISCONTINUITY_SEQUENCE: &str = "#EXT-X-DISCONTINUITY-SEQUENCE";
+const EXT_X_PROGRAM_DATE_TIME: &str = "#EXT-X-PROGRAM-DATE-TIME";
+const EXT_X_ENDLIST: &str = "#EXT-X-ENDLIST";
+const EXT_X_DATERANGE: &str = "#EXT-X-DATERANGE";
+const EXT_X_MEDIA_SEQUENCE: &str = "#EXT-X-MEDIA-SEQUENCE";
+const EXT_X_GAP: &str = "#EXT-X-GAP";
+const EXT_X_BITRATE: &str = "#EXT-X-BITRATE";
+const EXT_X_START: &str = "#EXT-X-START";
+const EXT_X_SESSION_DATA: &str = "#EXT-X-SESSION-DATA";
+const EXT_X_CONTENT_STEERING: &str = "#EXT-X-CONTENT-STEERING";
+const EXT_X_PART_INF: &str = "#EXT-X-PART-INF";
+const EXT_X_PART: &str = "#EXT-X-PART";
+const EXT_X_PRELOAD_HINT: &str = "#EXT-X-PRELOAD-HINT";
+const EXT_X_RENDITION_REPORT: &str = "#EXT-X-RENDITION-REPORT";
+const EXT_X_SERVER_CONTROL: &str = "#EXT-X-SERVER-CONTROL";
+const EXT_X_SKIP: &str = "#EXT-X-SKIP";
+const EXT_X_KEY: &str = "#EXT-X-KEY";
+const EXT_X_I_FRAMES_ONLY: &str = "#EXT-X-I-FRAMES-ONLY";
+const EXT_X_ALLOW_CACHE: &str = "#EXT-X-ALLOW-CACHE";
+const EXT_X_CUE_OUT: &str = "#EXT-X-CUE-OUT";
+const EXT_X_CUE_OUT_CONT: &str = "#EXT-X-CUE-OUT-CONT";
+const EXT_X_CUE_IN: &str = "#EXT-X-CUE-IN";
+const EXT_X_IMAGE_STREAM_INF: &str = "#EXT-X-IMAGE-STREAM-INF";
+const EXT_X_IMAGES_ONLY: &str = "#EXT-X-IMAGES-ONLY";
+const EXT_X_TILES: &str = "#EXT-X-TILES";
+const EXT_X_TARGETDURATION: &str = "#EXT-X-TARGETDURATION";
+const EXT_X_PLAYLIST_TYPE: &str = "#EXT-X-PLAYLIST-TYPE";
+const EXT_X_MAP: &str = "#EXT-X-MAP";
+
+const AUDIO: &str = "AUDIO";
+const VIDEO: &str = "VIDEO";
+const SUBTITLES: &str = "SUBTITLES";
+const CLOSED_CAPTIONS: &str = "CLOSED-CAPTIONS";
+
+/// Protocol version assumed when a playlist has no EXT-X-VERSION, per RFC 8216
+const MIN_VERSION: u8 = 1;
+
+/// Number of characters of an unexpected body kept for error reporting
+const SNIPPET_LENGTH: usize = 120;
+
+/// Attributes whose values are quoted strings per RFC 8216
+const QUOTED_ATTRIBUTES: &[&str] = &[
+    "ASSOC-LANGUAGE",
+    "AUDIO",
+    "BYTERANGE",
+    "CHANNELS",
+    "CHARACTERISTICS",
+    "CLOSED-CAPTIONS",
+    "CODECS",
+    "GROUP-ID",
+    "INSTREAM-ID",
+    "LANGUAGE",
+    "NAME",
+    "PATHWAY-ID",
+    "STABLE-RENDITION-ID",
+    "STABLE-VARIANT-ID",
+    "SUBTITLES",
+    "URI",
+    "VIDEO",
+];
 
 /// Error Wrapper for M3U8 Parsing
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ParseError {
     InvalidM3U8(String),
+    #[cfg(feature = "http")]
     ReqwestError(reqwest::Error),
+    JsonError(serde_json::Error),
+    IoError(std::io::Error),
+    /// The response was not a playlist, such as an HTML error page served with a 200
+    NotAPlaylist {
+        content_type: Option<String>,
+        snippet: String,
+    },
+    /// The playlist ends part way through, such as a partially written file
+    Truncated {
+        at_line: usize,
+    },
+    /// A response was refused by the size limit or content type check of the fetch options
+    ResponseRejected {
+        uri: String,
+        reason: String,
+    },
+}
+
+/// ParseError as a Display type, describing the failure
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidM3U8(message) => write!(f, "invalid playlist: {}", message),
+            #[cfg(feature = "http")]
+            ParseError::ReqwestError(err) => write!(f, "request failed: {}", err),
+            ParseError::JsonError(err) => write!(f, "invalid JSON: {}", err),
+            ParseError::IoError(err) => write!(f, "IO error: {}", err),
+            ParseError::NotAPlaylist {
+                content_type,
+                snippet,
+            } => write!(
+                f,
+                "response is not a playlist (content type {}): {}",
+                content_type.as_deref().unwrap_or("unknown"),
+                snippet
+            ),
+            ParseError::Truncated { at_line } => {
+                write!(f, "playlist is truncated at line {}", at_line)
+            }
+            ParseError::ResponseRejected { uri, reason } => {
+                write!(f, "response from {} rejected: {}", uri, reason)
+            }
+        }
+    }
+}
+
+/// Implementation of the standard Error trait for ParseError
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "http")]
+            ParseError::ReqwestError(err) => Some(err),
+            ParseError::JsonError(err) => Some(err),
+            ParseError::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 /// Map a Reqwest Error to our Error Wrapper
+#[cfg(feature = "http")]
 impl From<reqwest::Error> for ParseError {
     fn from(err: reqwest::Error) -> ParseError {
         ParseError::ReqwestError(err)
     }
 }
 
+/// Map a JSON Error to our Error Wrapper
+impl From<serde_json::Error> for ParseError {
+    fn from(err: serde_json::Error) -> ParseError {
+        ParseError::JsonError(err)
+    }
+}
+
+/// Map an IO Error to our Error Wrapper
+impl From<std::io::Error> for ParseError {
+    fn from(err: std::io::Error) -> ParseError {
+        ParseError::IoError(err)
+    }
+}
+
 /// Represent M3U8 tag types
-#[derive(Debug, PartialEq)]
-enum TagTypes {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TagTypes {
     ExtM3U,
     ExtXIndependentSegments,
     ExtXVersion,
     ExtXMedia,
     ExtXIFrameStreamInf,
     ExtXStreamInf,
+    ExtInf,
+    ExtXByteRange,
+    ExtXDiscontinuity,
+    ExtXDiscontinuitySequence,
+    ExtXProgramDateTime,
+    ExtXEndList,
+    ExtXDateRange,
+    ExtXMediaSequence,
+    ExtXGap,
+    ExtXBitrate,
+    ExtXStart,
+    ExtXSessionData,
+    ExtXContentSteering,
+    ExtXPartInf,
+    ExtXPart,
+    ExtXPreloadHint,
+    ExtXRenditionReport,
+    ExtXServerControl,
+    ExtXSkip,
+    ExtXKey,
+    ExtXIFramesOnly,
+    ExtXAllowCache,
+    ExtXCueOut,
+    ExtXCueOutCont,
+    ExtXCueIn,
+    ExtXImageStreamInf,
+    ExtXImagesOnly,
+    ExtXTiles,
+    ExtXTargetDuration,
+    ExtXPlaylistType,
+    ExtXMap,
 }
 
 /// Tag types fromStr
@@ -52,6 +336,37 @@ impl FromStr for TagTypes {
             EXT_X_MEDIA => Ok(TagTypes::ExtXMedia),
             EXT_X_I_FRAME_STREAM_INF => Ok(TagTypes::ExtXIFrameStreamInf),
             EXT_X_STREAM_INF => Ok(TagTypes::ExtXStreamInf),
+            EXTINF => Ok(TagTypes::ExtInf),
+            EXT_X_BYTERANGE => Ok(TagTypes::ExtXByteRange),
+            EXT_X_DISCONTINUITY => Ok(TagTypes::ExtXDiscontinuity),
+            EXT_X_DISCONTINUITY_SEQUENCE => Ok(TagTypes::ExtXDiscontinuitySequence),
+            EXT_X_PROGRAM_DATE_TIME => Ok(TagTypes::ExtXProgramDateTime),
+            EXT_X_ENDLIST => Ok(TagTypes::ExtXEndList),
+            EXT_X_DATERANGE => Ok(TagTypes::ExtXDateRange),
+            EXT_X_MEDIA_SEQUENCE => Ok(TagTypes::ExtXMediaSequence),
+            EXT_X_GAP => Ok(TagTypes::ExtXGap),
+            EXT_X_BITRATE => Ok(TagTypes::ExtXBitrate),
+            EXT_X_START => Ok(TagTypes::ExtXStart),
+            EXT_X_SESSION_DATA => Ok(TagTypes::ExtXSessionData),
+            EXT_X_CONTENT_STEERING => Ok(TagTypes::ExtXContentSteering),
+            EXT_X_PART_INF => Ok(TagTypes::ExtXPartInf),
+            EXT_X_PART => Ok(TagTypes::ExtXPart),
+            EXT_X_PRELOAD_HINT => Ok(TagTypes::ExtXPreloadHint),
+            EXT_X_RENDITION_REPORT => Ok(TagTypes::ExtXRenditionReport),
+            EXT_X_SERVER_CONTROL => Ok(TagTypes::ExtXServerControl),
+            EXT_X_SKIP => Ok(TagTypes::ExtXSkip),
+            EXT_X_KEY => Ok(TagTypes::ExtXKey),
+            EXT_X_I_FRAMES_ONLY => Ok(TagTypes::ExtXIFramesOnly),
+            EXT_X_ALLOW_CACHE => Ok(TagTypes::ExtXAllowCache),
+            EXT_X_CUE_OUT => Ok(TagTypes::ExtXCueOut),
+            EXT_X_CUE_OUT_CONT => Ok(TagTypes::ExtXCueOutCont),
+            EXT_X_CUE_IN => Ok(TagTypes::ExtXCueIn),
+            EXT_X_IMAGE_STREAM_INF => Ok(TagTypes::ExtXImageStreamInf),
+            EXT_X_IMAGES_ONLY => Ok(TagTypes::ExtXImagesOnly),
+            EXT_X_TILES => Ok(TagTypes::ExtXTiles),
+            EXT_X_TARGETDURATION => Ok(TagTypes::ExtXTargetDuration),
+            EXT_X_PLAYLIST_TYPE => Ok(TagTypes::ExtXPlaylistType),
+            EXT_X_MAP => Ok(TagTypes::ExtXMap),
             _ => Err(()),
         }
     }
@@ -67,26 +382,151 @@ impl fmt::Display for TagTypes {
             TagTypes::ExtXMedia => write!(f, "{}", EXT_X_MEDIA),
             TagTypes::ExtXIFrameStreamInf => write!(f, "{}", EXT_X_I_FRAME_STREAM_INF),
             TagTypes::ExtXStreamInf => write!(f, "{}", EXT_X_STREAM_INF),
+            TagTypes::ExtInf => write!(f, "{}", EXTINF),
+            TagTypes::ExtXByteRange => write!(f, "{}", EXT_X_BYTERANGE),
+            TagTypes::ExtXDiscontinuity => write!(f, "{}", EXT_X_DISCONTINUITY),
+            TagTypes::ExtXDiscontinuitySequence => write!(f, "{}", EXT_X_DISCONTINUITY_SEQUENCE),
+            TagTypes::ExtXProgramDateTime => write!(f, "{}", EXT_X_PROGRAM_DATE_TIME),
+            TagTypes::ExtXEndList => write!(f, "{}", EXT_X_ENDLIST),
+            TagTypes::ExtXDateRange => write!(f, "{}", EXT_X_DATERANGE),
+            TagTypes::ExtXMediaSequence => write!(f, "{}", EXT_X_MEDIA_SEQUENCE),
+            TagTypes::ExtXGap => write!(f, "{}", EXT_X_GAP),
+            TagTypes::ExtXBitrate => write!(f, "{}", EXT_X_BITRATE),
+            TagTypes::ExtXStart => write!(f, "{}", EXT_X_START),
+            TagTypes::ExtXSessionData => write!(f, "{}", EXT_X_SESSION_DATA),
+            TagTypes::ExtXContentSteering => write!(f, "{}", EXT_X_CONTENT_STEERING),
+            TagTypes::ExtXPartInf => write!(f, "{}", EXT_X_PART_INF),
+            TagTypes::ExtXPart => write!(f, "{}", EXT_X_PART),
+            TagTypes::ExtXPreloadHint => write!(f, "{}", EXT_X_PRELOAD_HINT),
+            TagTypes::ExtXRenditionReport => write!(f, "{}", EXT_X_RENDITION_REPORT),
+            TagTypes::ExtXServerControl => write!(f, "{}", EXT_X_SERVER_CONTROL),
+            TagTypes::ExtXSkip => write!(f, "{}", EXT_X_SKIP),
+            TagTypes::ExtXKey => write!(f, "{}", EXT_X_KEY),
+            TagTypes::ExtXIFramesOnly => write!(f, "{}", EXT_X_I_FRAMES_ONLY),
+            TagTypes::ExtXAllowCache => write!(f, "{}", EXT_X_ALLOW_CACHE),
+            TagTypes::ExtXCueOut => write!(f, "{}", EXT_X_CUE_OUT),
+            TagTypes::ExtXCueOutCont => write!(f, "{}", EXT_X_CUE_OUT_CONT),
+            TagTypes::ExtXCueIn => write!(f, "{}", EXT_X_CUE_IN),
+            TagTypes::ExtXImageStreamInf => write!(f, "{}", EXT_X_IMAGE_STREAM_INF),
+            TagTypes::ExtXImagesOnly => write!(f, "{}", EXT_X_IMAGES_ONLY),
+            TagTypes::ExtXTiles => write!(f, "{}", EXT_X_TILES),
+            TagTypes::ExtXTargetDuration => write!(f, "{}", EXT_X_TARGETDURATION),
+            TagTypes::ExtXPlaylistType => write!(f, "{}", EXT_X_PLAYLIST_TYPE),
+            TagTypes::ExtXMap => write!(f, "{}", EXT_X_MAP),
         }
     }
 }
 
+/// Represent EXT-X-MEDIA TYPE values
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
+pub enum MediaType {
+    Audio,
+    Video,
+    Subtitles,
+    ClosedCaptions,
+}
+
+/// Media type fromStr
+impl FromStr for MediaType {
+    type Err = ();
+    fn from_str(input: &str) -> Result<MediaType, Self::Err> {
+        match input {
+            AUDIO => Ok(MediaType::Audio),
+            VIDEO => Ok(MediaType::Video),
+            SUBTITLES => Ok(MediaType::Subtitles),
+            CLOSED_CAPTIONS => Ok(MediaType::ClosedCaptions),
+            _ => Err(()),
+        }
+    }
+}
+
+/// MediaType as a Display type for string formatting
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MediaType::Audio => write!(f, "{}", AUDIO),
+            MediaType::Video => write!(f, "{}", VIDEO),
+            MediaType::Subtitles => write!(f, "{}", SUBTITLES),
+            MediaType::ClosedCaptions => write!(f, "{}", CLOSED_CAPTIONS),
+        }
+    }
+}
+
+/// Direction of one key when sorting attribute lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A recognized playlist tag, as counted by `M3U8::tag_counts`
+pub type Tag = TagTypes;
+
+/// A master playlist of variant streams and renditions, parsed into M3U8
+pub type MasterPlaylist = M3U8;
+
+/// A media playlist of segments, parsed into the same M3U8 type as master playlists
+pub type MediaPlaylist = M3U8;
+
+/// GROUP-ID of an EXT-X-MEDIA rendition group
+pub type GroupId = String;
+
+/// Attributes of a single EXT-X-MEDIA tag
+pub type MediaTag = HashMap<String, String>;
+
 /// Our Parsed M3U8
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct M3U8 {
     independent_segments: bool,
-    version: String,
+    i_frames_only: bool,
+    images_only: bool,
+    version: u8,
+    allow_cache: Option<bool>,
+    start: Option<StartPoint>,
+    session_data: Vec<SessionData>,
+    content_steering: Option<ContentSteering>,
+    server_control: Option<ServerControl>,
     media_tags: Vec<HashMap<String, String>>,
     variant_streams: Vec<HashMap<String, String>>,
+    /// Typed variant streams, parsed on first selection and cleared on changes
+    selectable_variants: OnceLock<Vec<VariantStream>>,
     media_resources: Vec<HashMap<String, String>>,
+    /// Thumbnail tracks from EXT-X-IMAGE-STREAM-INF
+    image_streams: Vec<HashMap<String, String>>,
+    /// Maximum segment duration in seconds from EXT-X-TARGETDURATION
+    target_duration: Option<u64>,
+    playlist_type: Option<PlaylistType>,
+    media_sequence: u64,
+    discontinuity_sequence: u64,
+    /// Segments left out of a delta update, before the first listed segment
+    skip: Option<Skip>,
+    segments: Vec<Segment>,
+    part_target: Option<f64>,
+    /// Parts of the segment still being produced, after the last complete segment
+    pending_parts: Vec<PartialSegment>,
+    /// Ad cue after the last segment, such as a closing EXT-X-CUE-IN
+    pending_ad_cue: Option<AdCue>,
+    preload_hints: Vec<PreloadHint>,
+    rendition_reports: Vec<RenditionReport>,
+    end_list: bool,
+    date_ranges: Vec<DateRange>,
+    /// Comments and tags the parser does not recognize, in playlist order
+    unknown_lines: Vec<UnknownLine>,
+    tag_counts: HashMap<Tag, usize>,
+    /// Defects in the body worked around while splitting it into lines
+    warnings: Vec<ParseWarning>,
+    /// URI the playlist was served from after any redirects, to resolve relative
+    /// URIs against
+    base_uri: Option<String>,
 }
 
 /// Implementation for M3U8
 impl M3U8 {
-    /// New sets default version to 2 (Lowest Protocol) and returns M3U8
+    /// New sets default version to 1 (Lowest Protocol) and returns M3U8
     fn new() -> Self {
         M3U8 {
-            version: "2".to_string(), // Default if no version supplied
+            version: MIN_VERSION, // Default if no version supplied
             ..Default::default()
         }
     }
@@ -94,7 +534,8 @@ impl M3U8 {
     /// Validates our data
     fn validate(lines: &[String]) -> Result<(), ParseError> {
         let intro = lines
-            .first()
+            .iter()
+            .find(|line| !line.is_empty())
             .ok_or_else(|| ParseError::InvalidM3U8("Invalid M3U8 format".to_string()))?;
 
         // If no ExtM3U
@@ -116,26 +557,76 @@ impl M3U8 {
         Ok(())
     }
 
+    /// Splits an attribute list on commas, ignoring commas inside quoted strings
+    fn split_attributes(data: &str) -> Vec<&str> {
+        let mut items = Vec::new();
+        let mut start = 0;
+        let mut in_quotes = false;
+        let mut escaped = false;
+        for (index, c) in data.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_quotes => escaped = true,
+                '"' => in_quotes = !in_quotes,
+                ',' if !in_quotes => {
+                    items.push(&data[start..index]);
+                    start = index + c.len_utf8();
+                }
+                _ => (),
+            }
+        }
+        items.push(&data[start..]);
+        items
+    }
+
+    /// Removes surrounding quotes and resolves escaped quotes and backslashes
+    fn unquote(value: &str) -> String {
+        let inner = match value.strip_prefix('"') {
+            Some(inner) => inner.strip_suffix('"').unwrap_or(inner),
+            None => return value.to_string(),
+        };
+        let mut unquoted = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => unquoted.extend(chars.next()),
+                '\r' | '\n' => (), // Not permitted in quoted strings
+                _ => unquoted.push(c),
+            }
+        }
+        unquoted
+    }
+
     /// Parses single KEY=VALUE line
     fn get_key_value_pair(item: &str) -> Option<(String, String)> {
-        let mut attr = item.split('=');
-        let key = match attr.next() {
-            Some(key) => key.to_string(),
-            None => return None,
-        };
-        let value = match attr.next() {
-            Some(value) => value.replace(&['\"', '\''][..], ""), // Replace escape chars
-            None => return None,
-        };
-        Some((key, value))
+        let (key, value) = item.split_once('=')?;
+        let key = key.trim();
+        if key.is_empty() {
+            return None;
+        }
+        Some((key.to_string(), M3U8::unquote(value.trim())))
     }
 
     /// Parses all attribute lines containing KEY=VALUE
     fn by_attribute(&mut self, data: &str) -> HashMap<String, String> {
         let mut attribute_map = HashMap::new();
-        for item in data.split(',') {
+        for item in M3U8::split_attributes(data) {
             if let Some((key, value)) = M3U8::get_key_value_pair(item) {
-                attribute_map.insert(key.to_string(), value.to_string());
+                let unquoted = !item
+                    .split_once('=')
+                    .is_some_and(|(_, raw)| raw.trim().starts_with('"'));
+                if unquoted
+                    && QUOTED_ATTRIBUTES.contains(&key.as_str())
+                    && M3U8::needs_quotes(&key, &value)
+                {
+                    let warning = ParseWarning::UnquotedAttribute {
+                        key: key.to_string(),
+                    };
+                    if !self.warnings.contains(&warning) {
+                        self.warnings.push(warning);
+                    }
+                }
+                attribute_map.insert(key, value);
             }
         }
         attribute_map
@@ -143,30 +634,174 @@ impl M3U8 {
 
     /// Parses simple key,value type
     fn by_value(line: &str) -> (&str, &str) {
-        let mut attribute = line.split(':');
-        let tag = attribute.next().unwrap_or("");
-        let data = attribute.next().unwrap_or("");
-        (tag, data)
+        line.split_once(':').unwrap_or((line, ""))
+    }
+
+    /// Wraps a value in quotes, escaping quotes and backslashes and dropping CR/LF
+    fn quote(value: &str) -> String {
+        let mut quoted = String::with_capacity(value.len() + 2);
+        quoted.push('"');
+        for c in value.chars() {
+            match c {
+                '"' | '\\' => {
+                    quoted.push('\\');
+                    quoted.push(c);
+                }
+                '\r' | '\n' => (), // Not permitted in quoted strings
+                _ => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    /// Whether an attribute value must be written as a quoted string
+    fn needs_quotes(key: &str, value: &str) -> bool {
+        // CLOSED-CAPTIONS=NONE is an enumerated string, not a group id
+        if key == "CLOSED-CAPTIONS" && value == "NONE" {
+            return false;
+        }
+        QUOTED_ATTRIBUTES.contains(&key) || value.is_empty() || value.contains([',', '"', ' '])
+    }
+
+    /// Writes an attribute map back out as a KEY=VALUE list, sorted by key
+    fn write_attributes(attributes: &HashMap<String, String>, skip: &[&str]) -> String {
+        let mut keys: Vec<&String> = attributes
+            .keys()
+            .filter(|key| !skip.contains(&key.as_str()))
+            .collect();
+        keys.sort();
+        keys.iter()
+            .map(|key| {
+                let value = &attributes[*key];
+                if M3U8::needs_quotes(key, value) {
+                    format!("{}={}", key, M3U8::quote(value))
+                } else {
+                    format!("{}={}", key, value)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(",")
     }
 
     /// Parse and match by our tag types
-    fn parse(&mut self, lines: &[String]) {
-        let mut iter_lines = lines.iter();
-        while let Some(line) = iter_lines.next() {
+    fn parse(
+        &mut self,
+        lines: &[String],
+        handlers: &mut HashMap<String, TagHandler>,
+    ) -> Result<(), ParseError> {
+        // Line numbers are kept for error reporting, blank lines are skipped
+        let mut iter_lines = lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .filter(|(_, line)| !line.is_empty());
+        let mut segment = Segment::default();
+        let mut inf_line: Option<usize> = None;
+        let mut byte_range: Option<(u64, Option<u64>)> = None;
+        let mut bitrate: Option<u64> = None;
+        // Keys apply until replaced; adjacent EXT-X-KEY tags add KEYFORMATs
+        let mut keys: Vec<EncryptionKey> = Vec::new();
+        let mut keys_replaced = true;
+        let mut map: Option<InitSection> = None;
+        let mut in_ad_break = false;
+        while let Some((line_number, line)) = iter_lines.next() {
+            // Any line that is not a tag or comment is a segment URI
+            if !line.starts_with('#') {
+                inf_line = None;
+                segment.uri = line.to_string();
+                if let Some((length, offset)) = byte_range.take() {
+                    // Without an offset the range continues from the previous sub-range
+                    let offset = offset
+                        .or_else(|| {
+                            self.segments
+                                .last()
+                                .filter(|previous| previous.uri == segment.uri)
+                                .and_then(|previous| previous.byte_range)
+                                .and_then(|previous| previous.end())
+                        })
+                        .unwrap_or(0);
+                    let range = ByteRange { length, offset };
+                    range.end().ok_or_else(|| {
+                        ParseError::InvalidM3U8(format!("{} overflows: {}", EXT_X_BYTERANGE, line))
+                    })?;
+                    segment.byte_range = Some(range);
+                }
+                // EXT-X-BITRATE applies until the next one, but never to byte range segments
+                if segment.byte_range.is_none() {
+                    segment.bitrate = bitrate;
+                }
+                // Carry wall-clock time forward, but not across an unannounced discontinuity
+                segment.date_time =
+                    segment
+                        .program_date_time
+                        .or_else(|| match self.segments.last() {
+                            Some(previous) if !segment.discontinuity => previous.end_date_time(),
+                            _ => None,
+                        });
+                let skipped = self.skip.as_ref().map_or(0, |skip| skip.skipped_segments);
+                segment.sequence = self.media_sequence + skipped + self.segments.len() as u64;
+                segment.keys = keys.clone();
+                segment.map = map.clone();
+                segment.in_ad_break = in_ad_break;
+                keys_replaced = false;
+                self.segments.push(std::mem::take(&mut segment));
+                continue;
+            }
             let tag: Vec<&str> = line.split(':').collect();
             let tag_type = if let Some(tag) = tag.first() {
                 TagTypes::from_str(tag)
             } else {
                 break;
             };
+            if let Ok(tag) = tag_type {
+                *self.tag_counts.entry(tag).or_insert(0) += 1;
+            }
             match tag_type {
                 Ok(TagTypes::ExtM3U) => (),
                 Ok(TagTypes::ExtXIndependentSegments) => {
                     self.independent_segments = true;
                 }
+                Ok(TagTypes::ExtXAllowCache) => {
+                    let (_, data) = M3U8::by_value(line);
+                    self.allow_cache = match data.trim() {
+                        "YES" => Some(true),
+                        "NO" => Some(false),
+                        _ => {
+                            return Err(ParseError::InvalidM3U8(format!(
+                                "Invalid {}: {}",
+                                EXT_X_ALLOW_CACHE, line
+                            )))
+                        }
+                    };
+                }
+                Ok(TagTypes::ExtXIFramesOnly) => {
+                    self.i_frames_only = true;
+                }
+                Ok(TagTypes::ExtXImagesOnly) => {
+                    self.images_only = true;
+                }
                 Ok(TagTypes::ExtXVersion) => {
                     let (_, data) = M3U8::by_value(line);
-                    self.version = data.to_string();
+                    self.version = data.trim().parse().map_err(|_| {
+                        ParseError::InvalidM3U8(format!("Invalid {}: {}", EXT_X_VERSION, line))
+                    })?;
+                }
+                Ok(TagTypes::ExtXStart) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.start = Some(StartPoint::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXSessionData) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.session_data
+                        .push(SessionData::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXContentSteering) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.content_steering = Some(ContentSteering::from_attributes(&attributes)?);
                 }
                 Ok(TagTypes::ExtXMedia) => {
                     let (_, data) = M3U8::by_value(line);
@@ -178,34 +813,387 @@ impl M3U8 {
                     let attributes = self.by_attribute(data);
                     self.media_resources.push(attributes);
                 }
+                Ok(TagTypes::ExtXImageStreamInf) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.image_streams.push(attributes);
+                }
                 Ok(TagTypes::ExtXStreamInf) => {
                     let (_, data) = M3U8::by_value(line);
                     let mut attributes = self.by_attribute(data);
-                    let uri = iter_lines.next().unwrap_or(&"".to_string()).to_string();
-                    attributes.insert("uri".to_string(), uri);
-                    self.variant_streams.push(attributes);
+                    let (_, uri) = iter_lines.next().ok_or(ParseError::Truncated {
+                        at_line: line_number,
+                    })?;
+                    attributes.insert("uri".to_string(), uri.to_string());
+                    self.variant_streams_mut().push(attributes);
+                }
+                Ok(TagTypes::ExtInf) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let (duration, title) = Segment::parse_inf(data).ok_or_else(|| {
+                        ParseError::InvalidM3U8(format!("Invalid {}: {}", EXTINF, line))
+                    })?;
+                    segment.duration = duration;
+                    segment.title = title;
+                    inf_line = Some(line_number);
+                }
+                Ok(TagTypes::ExtXDiscontinuity) => {
+                    segment.discontinuity = true;
+                }
+                Ok(TagTypes::ExtXMediaSequence) => {
+                    let (_, data) = M3U8::by_value(line);
+                    self.media_sequence = data.trim().parse().map_err(|_| {
+                        ParseError::InvalidM3U8(format!(
+                            "Invalid {}: {}",
+                            EXT_X_MEDIA_SEQUENCE, line
+                        ))
+                    })?;
+                }
+                Ok(TagTypes::ExtXTargetDuration) => {
+                    let (_, data) = M3U8::by_value(line);
+                    self.target_duration = Some(data.trim().parse().map_err(|_| {
+                        ParseError::InvalidM3U8(format!(
+                            "Invalid {}: {}",
+                            EXT_X_TARGETDURATION, line
+                        ))
+                    })?);
+                }
+                Ok(TagTypes::ExtXPlaylistType) => {
+                    let (_, data) = M3U8::by_value(line);
+                    self.playlist_type = Some(data.trim().parse()?);
+                }
+                Ok(TagTypes::ExtXMap) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    map = Some(InitSection::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXDiscontinuitySequence) => {
+                    let (_, data) = M3U8::by_value(line);
+                    self.discontinuity_sequence = data.trim().parse().map_err(|_| {
+                        ParseError::InvalidM3U8(format!(
+                            "Invalid {}: {}",
+                            EXT_X_DISCONTINUITY_SEQUENCE, line
+                        ))
+                    })?;
+                }
+                Ok(TagTypes::ExtXProgramDateTime) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let date_time = DateTime::parse_from_rfc3339(data.trim()).map_err(|_| {
+                        ParseError::InvalidM3U8(format!(
+                            "Invalid {}: {}",
+                            EXT_X_PROGRAM_DATE_TIME, line
+                        ))
+                    })?;
+                    segment.program_date_time = Some(date_time);
+                }
+                Ok(TagTypes::ExtXDateRange) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.date_ranges
+                        .push(DateRange::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXBitrate) => {
+                    let (_, data) = M3U8::by_value(line);
+                    bitrate = Some(data.trim().parse().map_err(|_| {
+                        ParseError::InvalidM3U8(format!("Invalid {}: {}", EXT_X_BITRATE, line))
+                    })?);
+                }
+                Ok(TagTypes::ExtXKey) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    let key = EncryptionKey::from_attributes(&attributes)?;
+                    if !keys_replaced {
+                        keys.clear();
+                        keys_replaced = true;
+                    }
+                    if key.method == KeyMethod::None {
+                        keys.clear();
+                    } else {
+                        keys.push(key);
+                    }
+                }
+                Ok(TagTypes::ExtXCueOut) => {
+                    let (_, data) = M3U8::by_value(line);
+                    segment.ad_cue = Some(AdCue::parse_out(data)?);
+                    in_ad_break = true;
                 }
-                // Todo, Add Full Implementation
+                Ok(TagTypes::ExtXCueOutCont) => {
+                    let (_, data) = M3U8::by_value(line);
+                    segment.ad_cue = Some(AdCue::parse_out_cont(data)?);
+                    in_ad_break = true;
+                }
+                Ok(TagTypes::ExtXCueIn) => {
+                    segment.ad_cue = Some(AdCue::In);
+                    in_ad_break = false;
+                }
+                Ok(TagTypes::ExtXGap) => {
+                    segment.gap = true;
+                }
+                Ok(TagTypes::ExtXTiles) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    segment.tiles = Some(Tiles::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXPartInf) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.part_target =
+                        Some(tags::required(&attributes, EXT_X_PART_INF, "PART-TARGET")?);
+                }
+                Ok(TagTypes::ExtXServerControl) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.server_control = Some(ServerControl::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXSkip) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.skip = Some(Skip::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXPreloadHint) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.preload_hints
+                        .push(PreloadHint::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXRenditionReport) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    self.rendition_reports
+                        .push(RenditionReport::from_attributes(&attributes)?);
+                }
+                Ok(TagTypes::ExtXPart) => {
+                    let (_, data) = M3U8::by_value(line);
+                    let attributes = self.by_attribute(data);
+                    let previous = segment.parts.last().or_else(|| {
+                        self.segments
+                            .last()
+                            .and_then(|previous| previous.parts.last())
+                    });
+                    let part = PartialSegment::from_attributes(&attributes, previous)?;
+                    segment.parts.push(part);
+                }
+                Ok(TagTypes::ExtXEndList) => {
+                    self.end_list = true;
+                }
+                Ok(TagTypes::ExtXByteRange) => {
+                    let (_, data) = M3U8::by_value(line);
+                    byte_range = Some(ByteRange::parse(data).ok_or_else(|| {
+                        ParseError::InvalidM3U8(format!("Invalid {}: {}", EXT_X_BYTERANGE, line))
+                    })?);
+                }
+                // Kept so rewriting proxies can pass vendor tags through
                 _ => {
-                    println!("Unhandled: {}", line);
+                    let unknown = UnknownLine {
+                        line_number,
+                        line: line.to_string(),
+                        segment_index: self.segments.len(),
+                    };
+                    let (name, _) = M3U8::by_value(line);
+                    if let Some(handler) = handlers.get_mut(name) {
+                        handler(&unknown)?;
+                    }
+                    self.unknown_lines.push(unknown);
                 }
             }
         }
+        // A segment without its URI line means the playlist was cut short
+        if let Some(at_line) = inf_line {
+            return Err(ParseError::Truncated { at_line });
+        }
+        self.pending_parts = segment.parts;
+        self.pending_ad_cue = segment.ad_cue;
+        Ok(())
+    }
+
+    /// Detects a final line cut off part way through a tag
+    ///
+    /// Without a trailing newline, a last line that is an unknown tag or has an
+    /// unterminated quoted string was most likely still being written.
+    fn check_truncated(body: &str, lines: &[String]) -> Result<(), ParseError> {
+        let Some(at_line) = lines.iter().rposition(|line| !line.is_empty()) else {
+            return Ok(());
+        };
+        let last = &lines[at_line];
+        let (tag, data) = M3U8::by_value(last);
+        let unterminated = last.starts_with('#')
+            && M3U8::split_attributes(data)
+                .last()
+                .is_some_and(|item| item.matches('"').count() - item.matches("\\\"").count() == 1);
+        let unknown_tag = last.starts_with("#EXT") && TagTypes::from_str(tag).is_err();
+        if unterminated || (unknown_tag && !body.ends_with('\n')) {
+            return Err(ParseError::Truncated {
+                at_line: at_line + 1,
+            });
+        }
+        Ok(())
     }
 
     /// Used to sort Parsed Vectors
-    fn sort_list_by_key(list: &mut Vec<HashMap<String, String>>, sort_by: &str) {
-        list.sort_by(|a, b| {
-            let item1 = match a.get(sort_by) {
-                Some(item1) => item1,
-                None => "",
-            };
-            let item2 = match b.get(sort_by) {
-                Some(item1) => item1,
-                None => "",
-            };
-            item1.cmp(item2)
-        });
+    fn sort_list_by_key(list: &mut [HashMap<String, String>], sort_by: &str) {
+        M3U8::sort_list_by_keys(list, &[(sort_by, SortOrder::Asc)]);
+    }
+
+    /// Sorts by each key in turn, keeping playlist order between equal entries
+    fn sort_list_by_keys(list: &mut [HashMap<String, String>], keys: &[(&str, SortOrder)]) {
+        list.sort_by(|a, b| M3U8::compare_by_keys(a, b, keys));
+    }
+
+    /// Compares two attribute lists by each key in turn
+    fn compare_by_keys(
+        a: &HashMap<String, String>,
+        b: &HashMap<String, String>,
+        keys: &[(&str, SortOrder)],
+    ) -> std::cmp::Ordering {
+        keys.iter()
+            .map(|&(key, order)| {
+                let item1 = a.get(key).map_or("", |item| item);
+                let item2 = b.get(key).map_or("", |item| item);
+                match order {
+                    SortOrder::Asc => M3U8::compare_values(item1, item2),
+                    SortOrder::Desc => M3U8::compare_values(item2, item1),
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+
+    /// Returns references to a list sorted by several keys, without cloning it
+    fn sorted_view<'a>(
+        list: &'a [HashMap<String, String>],
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<&'a HashMap<String, String>> {
+        let mut view: Vec<_> = list.iter().collect();
+        view.sort_by(|a, b| M3U8::compare_by_keys(a, b, keys));
+        view
+    }
+
+    /// Compares attribute values as numbers or resolutions when both parse as one
+    ///
+    /// Missing values sort first, then numbers, resolutions and other text, so the
+    /// order stays total for mixed lists. Count-prefixed values such as the CHANNELS
+    /// value `16/JOC` sort by their leading number.
+    fn compare_values(a: &str, b: &str) -> std::cmp::Ordering {
+        let number = |value: &str| {
+            value
+                .parse::<f64>()
+                .ok()
+                .or_else(|| value.split_once('/')?.0.parse().ok())
+        };
+        let class = |value: &str| match value {
+            "" => 0,
+            _ if number(value).is_some() => 1,
+            _ if value.parse::<Resolution>().is_ok() => 2,
+            _ => 3,
+        };
+        class(a).cmp(&class(b)).then_with(|| match class(a) {
+            1 => number(a)
+                .unwrap()
+                .total_cmp(&number(b).unwrap())
+                .then_with(|| a.cmp(b)),
+            2 => a.parse::<Resolution>().unwrap().cmp(&b.parse().unwrap()),
+            _ => a.cmp(b),
+        })
+    }
+
+    /// Returns a sorted copy of a list, leaving the playlist order untouched
+    fn sorted_list(
+        list: &[HashMap<String, String>],
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<HashMap<String, String>> {
+        let mut list = list.to_vec();
+        M3U8::sort_list_by_keys(&mut list, keys);
+        list
+    }
+
+    /// Returns the EXT-X-STREAM-INF attributes in playlist order, with the URI under `uri`
+    pub fn variant_streams(&self) -> &[HashMap<String, String>] {
+        &self.variant_streams
+    }
+
+    /// Returns the variant stream attributes for changing, dropping typed variants
+    /// parsed from them
+    pub(crate) fn variant_streams_mut(&mut self) -> &mut Vec<HashMap<String, String>> {
+        self.selectable_variants = OnceLock::new();
+        &mut self.variant_streams
+    }
+
+    /// Returns the EXT-X-MEDIA attributes in playlist order
+    pub fn media_tags(&self) -> &[HashMap<String, String>] {
+        &self.media_tags
+    }
+
+    /// Returns the EXT-X-I-FRAME-STREAM-INF attributes in playlist order
+    pub fn iframe_streams(&self) -> &[HashMap<String, String>] {
+        &self.media_resources
+    }
+
+    /// Returns the EXT-X-IMAGE-STREAM-INF attributes in playlist order
+    pub fn image_streams(&self) -> &[HashMap<String, String>] {
+        &self.image_streams
+    }
+
+    /// Returns references to variant streams sorted by several keys
+    pub fn variant_streams_sorted_by(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<&HashMap<String, String>> {
+        M3U8::sorted_view(&self.variant_streams, keys)
+    }
+
+    /// Returns references to media tags sorted by several keys
+    pub fn media_tags_sorted_by(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<&HashMap<String, String>> {
+        M3U8::sorted_view(&self.media_tags, keys)
+    }
+
+    /// Returns references to i-frame streams sorted by several keys
+    pub fn iframe_streams_sorted_by(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<&HashMap<String, String>> {
+        M3U8::sorted_view(&self.media_resources, keys)
+    }
+
+    /// Returns references to image streams sorted by several keys
+    pub fn image_streams_sorted_by(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<&HashMap<String, String>> {
+        M3U8::sorted_view(&self.image_streams, keys)
+    }
+
+    /// Returns media resources sorted by several keys, each ascending or descending
+    pub fn get_media_resources_sorted(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<HashMap<String, String>> {
+        M3U8::sorted_list(&self.media_resources, keys)
+    }
+
+    /// Returns image streams sorted by several keys, each ascending or descending
+    pub fn get_image_streams_sorted(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<HashMap<String, String>> {
+        M3U8::sorted_list(&self.image_streams, keys)
+    }
+
+    /// Returns media tags sorted by several keys, each ascending or descending
+    pub fn get_media_tags_sorted(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<HashMap<String, String>> {
+        M3U8::sorted_list(&self.media_tags, keys)
+    }
+
+    /// Returns variant streams sorted by several keys, each ascending or descending
+    pub fn get_variant_streams_sorted(
+        &self,
+        keys: &[(&str, SortOrder)],
+    ) -> Vec<HashMap<String, String>> {
+        M3U8::sorted_list(&self.variant_streams, keys)
     }
 
     /// Returns Cloned Vec of media resources sorted by provided key
@@ -214,6 +1202,12 @@ impl M3U8 {
         self.media_resources.clone()
     }
 
+    /// Returns Cloned Vec of image streams sorted by provided key
+    pub fn get_image_streams(&mut self, sort_by: &str) -> Vec<HashMap<String, String>> {
+        M3U8::sort_list_by_key(&mut self.image_streams, sort_by);
+        self.image_streams.clone()
+    }
+
     /// Returns Cloned Vec of media tags sorted by provided key
     pub fn get_media_tags(&mut self, sort_by: &str) -> Vec<HashMap<String, String>> {
         M3U8::sort_list_by_key(&mut self.media_tags, sort_by);
@@ -222,85 +1216,614 @@ impl M3U8 {
 
     /// Returns Cloned Vec of variant streams sorted by provided key
     pub fn get_variant_streams(&mut self, sort_by: &str) -> Vec<HashMap<String, String>> {
-        M3U8::sort_list_by_key(&mut self.variant_streams, sort_by);
+        M3U8::sort_list_by_key(self.variant_streams_mut(), sort_by);
         self.variant_streams.clone()
     }
 
-    /// Takes URI return parsed M3U8 otherwise raises ParseError
-    pub fn from_uri(uri: &str) -> Result<M3U8, ParseError> {
-        let respose = reqwest::blocking::get(uri)?;
-        let body = respose.text()?;
-        let lines: Vec<String> = body
-            .lines()
-            .map(|m| m.to_string())
-            .filter(|m| !m.is_empty())
-            .collect();
-        M3U8::validate(&lines)?;
-        let mut m3u8 = M3U8::new();
-        m3u8.parse(&lines);
-        Ok(m3u8)
+    /// Type and group of a media tag, if both are present and valid
+    fn media_group_key(media_tag: &MediaTag) -> Option<(MediaType, GroupId)> {
+        let media_type = MediaType::from_str(media_tag.get("TYPE")?).ok()?;
+        let group_id = media_tag.get("GROUP-ID")?;
+        Some((media_type, group_id.to_string()))
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Indexes media tags by TYPE and GROUP-ID, keeping playlist order within a group
+    ///
+    /// Build this once and reuse it when switching tracks instead of scanning media tags.
+    pub fn media_group_index(&self) -> HashMap<(MediaType, GroupId), Vec<&MediaTag>> {
+        let mut index: HashMap<(MediaType, GroupId), Vec<&MediaTag>> = HashMap::new();
+        for media_tag in &self.media_tags {
+            if let Some(key) = M3U8::media_group_key(media_tag) {
+                index.entry(key).or_default().push(media_tag);
+            }
+        }
+        index
+    }
 
-    use crate::M3U8;
+    /// Returns the media tags of one rendition group, in playlist order
+    pub fn group(&self, media_type: MediaType, group_id: &str) -> Vec<&MediaTag> {
+        self.media_tags
+            .iter()
+            .filter(|media_tag| {
+                M3U8::media_group_key(media_tag).is_some_and(|(tag_type, tag_group)| {
+                    tag_type == media_type && tag_group == group_id
+                })
+            })
+            .collect()
+    }
 
-    /// Process our example
-    #[test]
-    fn it_parses_example_uri() {
-        let uri =
-            "https://lw.bamgrid.com/2.0/hls/vod/bam/ms02/hls/dplus/bao/master_unenc_hdr10_all.m3u8";
+    /// Indexes media tags by TYPE, GROUP-ID and NAME, which are unique together per RFC
+    pub fn media_name_index(&self) -> HashMap<(MediaType, GroupId, String), &MediaTag> {
+        let mut index = HashMap::new();
+        for media_tag in &self.media_tags {
+            let name = media_tag.get("NAME");
+            if let (Some((media_type, group_id)), Some(name)) =
+                (M3U8::media_group_key(media_tag), name)
+            {
+                index
+                    .entry((media_type, group_id, name.to_string()))
+                    .or_insert(media_tag);
+            }
+        }
+        index
+    }
 
-        let result = M3U8::from_uri(uri);
+    /// Returns the preferred start position from EXT-X-START
+    pub fn start(&self) -> Option<StartPoint> {
+        self.start
+    }
 
-        assert!(result.is_ok());
+    /// Returns the EXT-X-SESSION-DATA entries in playlist order
+    pub fn session_data(&self) -> &[SessionData] {
+        &self.session_data
+    }
 
-        let mut parsed = result.unwrap();
+    /// Returns the session data entries with a DATA-ID, one per LANGUAGE
+    pub fn session_data_by_id(&self, data_id: &str) -> Vec<&SessionData> {
+        self.session_data
+            .iter()
+            .filter(|session_data| session_data.data_id == data_id)
+            .collect()
+    }
 
-        assert_eq!(parsed.version, "2");
-        assert_eq!(parsed.independent_segments, true);
-        assert_eq!(parsed.media_tags.len(), 4);
-        assert_eq!(
-            parsed.media_tags.first().unwrap().get("TYPE"),
-            Some(&"AUDIO".to_string())
-        );
-        assert_eq!(
-            parsed.media_tags.first().unwrap().get("URI"),
-            Some(&"audio/unenc/aac_128k/vod.m3u8".to_string())
-        );
-        assert_eq!(parsed.media_resources.len(), 2);
-        assert_eq!(
-            parsed.media_resources.first().unwrap().get("URI"),
-            Some(&"hdr10/unenc/3300k/vod-iframe.m3u8".to_string())
-        );
-        assert_eq!(parsed.media_tags.len(), 4);
-        assert_eq!(
-            parsed.media_tags.first().unwrap().get("URI"),
-            Some(&"audio/unenc/aac_128k/vod.m3u8".to_string())
-        );
+    /// Returns the steering server from EXT-X-CONTENT-STEERING
+    pub fn content_steering(&self) -> Option<&ContentSteering> {
+        self.content_steering.as_ref()
+    }
 
-        // Test fetch and sorting media resources
+    /// Returns the EXT-X-VERSION, or 1 when the playlist does not declare one
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the legacy EXT-X-ALLOW-CACHE value, removed from the spec in version 7
+    pub fn allow_cache(&self) -> Option<bool> {
+        self.allow_cache
+    }
+
+    /// Whether each segment is a single I-frame, as in trick play playlists
+    pub fn i_frames_only(&self) -> bool {
+        self.i_frames_only
+    }
+
+    /// Whether each segment is a thumbnail image, as in image media playlists
+    pub fn images_only(&self) -> bool {
+        self.images_only
+    }
+
+    /// Returns the EXT-X-SERVER-CONTROL delivery directives
+    pub fn server_control(&self) -> Option<ServerControl> {
+        self.server_control
+    }
+
+    /// Returns the EXT-X-SKIP of a delta update playlist
+    pub fn skip(&self) -> Option<&Skip> {
+        self.skip.as_ref()
+    }
+
+    /// Returns the EXT-X-PART-INF PART-TARGET duration in seconds
+    pub fn part_target(&self) -> Option<f64> {
+        self.part_target
+    }
+
+    /// Returns the partial segments published after the last complete segment
+    pub fn pending_parts(&self) -> &[PartialSegment] {
+        &self.pending_parts
+    }
+
+    /// Returns the EXT-X-PRELOAD-HINT resources clients may request early
+    pub fn preload_hints(&self) -> &[PreloadHint] {
+        &self.preload_hints
+    }
+
+    /// Returns the EXT-X-RENDITION-REPORT entries for other renditions
+    pub fn rendition_reports(&self) -> &[RenditionReport] {
+        &self.rendition_reports
+    }
+
+    /// Returns the rendition report for a rendition URI
+    pub fn rendition_report(&self, uri: &str) -> Option<&RenditionReport> {
+        self.rendition_reports
+            .iter()
+            .find(|rendition_report| rendition_report.uri == uri)
+    }
+
+    /// Returns how many times each recognized tag occurs
+    pub fn tag_counts(&self) -> &HashMap<Tag, usize> {
+        &self.tag_counts
+    }
+
+    /// Returns the line break defects recovered from while parsing
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Returns the number of unrecognized `#EXT` tags
+    pub fn unknown_tag_count(&self) -> usize {
+        self.unknown_lines
+            .iter()
+            .filter(|unknown| unknown.line.starts_with("#EXT"))
+            .count()
+    }
+
+    /// Returns the number of comment lines
+    pub fn comment_count(&self) -> usize {
+        self.unknown_lines.len() - self.unknown_tag_count()
+    }
+
+    /// Returns comments and unrecognized tags, such as vendor extensions, with their positions
+    pub fn unknown_lines(&self) -> &[UnknownLine] {
+        &self.unknown_lines
+    }
+
+    /// Returns the media segments in playlist order
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the EXT-X-TARGETDURATION declared by the playlist, in seconds
+    pub fn target_duration(&self) -> Option<u64> {
+        self.target_duration
+    }
+
+    /// Returns the EXT-X-PLAYLIST-TYPE declared by the playlist
+    pub fn playlist_type(&self) -> Option<PlaylistType> {
+        self.playlist_type
+    }
+
+    /// Returns the EXT-X-MEDIA-SEQUENCE of the first segment
+    pub fn media_sequence(&self) -> u64 {
+        self.media_sequence
+    }
+
+    /// Returns the EXT-X-DISCONTINUITY-SEQUENCE of the first segment
+    pub fn discontinuity_sequence(&self) -> u64 {
+        self.discontinuity_sequence
+    }
+
+    /// Returns the number of segments preceded by EXT-X-DISCONTINUITY
+    pub fn discontinuity_count(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.discontinuity)
+            .count()
+    }
+
+    /// Returns the segment playing at a wall-clock time, for seeking by date
+    pub fn segment_at(&self, date_time: DateTime<FixedOffset>) -> Option<&Segment> {
+        self.segments.iter().find(|segment| {
+            matches!(
+                (segment.date_time, segment.end_date_time()),
+                (Some(start), Some(end)) if start <= date_time && date_time < end
+            )
+        })
+    }
+
+    /// Returns the EXT-X-DATERANGE tags in playlist order
+    pub fn date_ranges(&self) -> &[DateRange] {
+        &self.date_ranges
+    }
+
+    /// Whether EXT-X-ENDLIST marks the playlist as complete
+    pub fn end_list(&self) -> bool {
+        self.end_list
+    }
+
+    /// Takes URI return parsed M3U8 otherwise raises ParseError
+    pub fn from_uri(uri: &str) -> Result<M3U8, ParseError> {
+        M3U8::from_uri_with_options(uri, &FetchOptions::default())
+    }
+
+    /// Like from_uri, but fetches with the given options
+    pub fn from_uri_with_options(uri: &str, options: &FetchOptions) -> Result<M3U8, ParseError> {
+        let fetched = M3U8::fetch_playlist(uri, options)?;
+        M3U8::from_body_at(&fetched.body, &fetched.url)
+    }
+
+    /// Like from_uri, but requests the playlist without blocking the calling thread
+    #[cfg(feature = "async")]
+    pub async fn from_uri_async(uri: &str) -> Result<M3U8, ParseError> {
+        M3U8::from_uri_with_options_async(uri, &FetchOptions::default()).await
+    }
+
+    /// Like from_uri_with_options, but requests the playlist without blocking
+    #[cfg(feature = "async")]
+    pub async fn from_uri_with_options_async(
+        uri: &str,
+        options: &FetchOptions,
+    ) -> Result<M3U8, ParseError> {
+        let fetched = options.fetch_async(uri).await?;
+        M3U8::check_playlist_body(fetched.content_type, &fetched.body)?;
+        M3U8::from_body_at(&fetched.body, &fetched.url)
+    }
+
+    /// Like from_uri, but sends the request with a caller's client
+    #[cfg(feature = "http")]
+    pub fn from_uri_with_client(
+        client: &reqwest::blocking::Client,
+        uri: &str,
+    ) -> Result<M3U8, ParseError> {
+        M3U8::from_uri_with_options(uri, &FetchOptions::new().with_client(client.clone()))
+    }
+
+    /// Like from_uri, but loads the playlist with a custom fetcher
+    pub fn from_uri_with_fetcher(
+        uri: &str,
+        fetcher: &dyn PlaylistFetcher,
+    ) -> Result<M3U8, ParseError> {
+        let body = M3U8::fetch_body_with(uri, fetcher)?;
+        M3U8::from_body_at(&body, uri)
+    }
+
+    /// Like from_uri_with_fetcher, but awaits an async fetcher
+    ///
+    /// This is the way to fetch playlists on `wasm32-unknown-unknown`, where the
+    /// blocking HTTP client is not available.
+    pub async fn from_uri_with_async_fetcher(
+        uri: &str,
+        fetcher: &dyn AsyncPlaylistFetcher,
+    ) -> Result<M3U8, ParseError> {
+        let body = fetcher.fetch(uri).await?;
+        M3U8::check_playlist_body(None, &body)?;
+        M3U8::from_body_at(&body, uri)
+    }
+
+    /// Loads a playlist body with a fetcher, rejecting bodies that are clearly not
+    /// playlists
+    fn fetch_body_with(uri: &str, fetcher: &dyn PlaylistFetcher) -> Result<String, ParseError> {
+        let body = fetcher.fetch(uri)?;
+        M3U8::check_playlist_body(None, &body)?;
+        Ok(body)
+    }
+
+    /// Fetches a playlist body, rejecting responses that are clearly not playlists
+    fn fetch_body(uri: &str) -> Result<String, ParseError> {
+        M3U8::fetch_body_with_options(uri, &FetchOptions::default())
+    }
+
+    /// Fetches a playlist body with the given options
+    fn fetch_body_with_options(uri: &str, options: &FetchOptions) -> Result<String, ParseError> {
+        Ok(M3U8::fetch_playlist(uri, options)?.body)
+    }
+
+    /// Fetches a playlist with the given options, keeping the URL it was served from
+    fn fetch_playlist(uri: &str, options: &FetchOptions) -> Result<Fetched, ParseError> {
+        let fetched = options.fetch(uri)?;
+        M3U8::check_playlist_body(fetched.content_type.clone(), &fetched.body)?;
+        Ok(fetched)
+    }
+
+    /// Like from_uri, but fetches again up to `retries` times when an origin error page is served
+    pub fn from_uri_with_retries(uri: &str, retries: usize) -> Result<M3U8, ParseError> {
+        let mut result = M3U8::from_uri(uri);
+        for _ in 0..retries {
+            match result {
+                Err(ParseError::NotAPlaylist { .. }) => result = M3U8::from_uri(uri),
+                _ => break,
+            }
+        }
+        result
+    }
+
+    /// Rejects bodies that are clearly not playlists, such as HTML error pages
+    fn check_playlist_body(content_type: Option<String>, body: &str) -> Result<(), ParseError> {
+        let trimmed = body.trim_start_matches('\u{feff}').trim_start();
+        if trimmed.starts_with(EXTM3U) {
+            return Ok(());
+        }
+        let is_html_type = content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.to_ascii_lowercase().contains("html"));
+        let lowercase_start: String = trimmed.chars().take(15).collect::<String>().to_lowercase();
+        if is_html_type
+            || lowercase_start.starts_with("<!doctype")
+            || lowercase_start.starts_with("<html")
+        {
+            return Err(ParseError::NotAPlaylist {
+                content_type,
+                snippet: trimmed.chars().take(SNIPPET_LENGTH).collect(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads and parses a playlist stored on disk
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<M3U8, ParseError> {
+        let mut m3u8 = M3U8::from_reader(BufReader::new(File::open(&path)?))?;
+        m3u8.base_uri = Some(path.as_ref().to_string_lossy().into_owned());
+        Ok(m3u8)
+    }
+
+    /// Reads a playlist to the end and parses it
+    pub fn from_reader<R: BufRead>(mut reader: R) -> Result<M3U8, ParseError> {
+        let mut body = String::new();
+        reader.read_to_string(&mut body)?;
+        M3U8::from_body(&body)
+    }
+
+    /// Parses a playlist body that has already been fetched, without any request
+    pub fn from_body(body: &str) -> Result<M3U8, ParseError> {
+        M3U8::from_body_with_handlers(body, &mut HashMap::new())
+    }
+
+    /// Parses a playlist body loaded from `uri`
+    fn from_body_at(body: &str, uri: &str) -> Result<M3U8, ParseError> {
+        let mut m3u8 = M3U8::from_body(body)?;
+        m3u8.base_uri = Some(uri.to_string());
+        Ok(m3u8)
+    }
+
+    /// Parses a playlist body, calling custom handlers for their tags
+    fn from_body_with_handlers(
+        body: &str,
+        handlers: &mut HashMap<String, TagHandler>,
+    ) -> Result<M3U8, ParseError> {
+        let (lines, warnings) = M3U8::split_lines(body);
+        M3U8::validate(&lines)?;
+        M3U8::check_truncated(body, &lines)?;
+        let mut m3u8 = M3U8::new();
+        m3u8.warnings = warnings;
+        m3u8.parse(&lines, handlers)?;
+        Ok(m3u8)
+    }
+}
+
+/// M3U8 fromStr, parsing an in-memory playlist body
+impl FromStr for M3U8 {
+    type Err = ParseError;
+    fn from_str(input: &str) -> Result<M3U8, Self::Err> {
+        M3U8::from_body(input)
+    }
+}
+
+/// M3U8 as a Display type, serializing the playlist back out
+impl fmt::Display for M3U8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", TagTypes::ExtM3U)?;
+        writeln!(f, "{}:{}", TagTypes::ExtXVersion, self.version)?;
+        if self.independent_segments {
+            writeln!(f, "{}", TagTypes::ExtXIndependentSegments)?;
+        }
+        if let Some(allow_cache) = self.allow_cache {
+            let allow_cache = if allow_cache { "YES" } else { "NO" };
+            writeln!(f, "{}:{}", TagTypes::ExtXAllowCache, allow_cache)?;
+        }
+        if self.i_frames_only {
+            writeln!(f, "{}", TagTypes::ExtXIFramesOnly)?;
+        }
+        if self.images_only {
+            writeln!(f, "{}", TagTypes::ExtXImagesOnly)?;
+        }
+        if let Some(start) = self.start {
+            writeln!(f, "{}:{}", TagTypes::ExtXStart, start)?;
+        }
+        if let Some(content_steering) = &self.content_steering {
+            writeln!(f, "{}:{}", TagTypes::ExtXContentSteering, content_steering)?;
+        }
+        for session_data in &self.session_data {
+            writeln!(f, "{}:{}", TagTypes::ExtXSessionData, session_data)?;
+        }
+        for media_tag in &self.media_tags {
+            let attributes = M3U8::write_attributes(media_tag, &[]);
+            writeln!(f, "{}:{}", TagTypes::ExtXMedia, attributes)?;
+        }
+        for variant_stream in &self.variant_streams {
+            let attributes = M3U8::write_attributes(variant_stream, &["uri"]);
+            writeln!(f, "{}:{}", TagTypes::ExtXStreamInf, attributes)?;
+            writeln!(f, "{}", variant_stream.get("uri").map_or("", |uri| uri))?;
+        }
+        for media_resource in &self.media_resources {
+            let attributes = M3U8::write_attributes(media_resource, &[]);
+            writeln!(f, "{}:{}", TagTypes::ExtXIFrameStreamInf, attributes)?;
+        }
+        for image_stream in &self.image_streams {
+            let attributes = M3U8::write_attributes(image_stream, &[]);
+            writeln!(f, "{}:{}", TagTypes::ExtXImageStreamInf, attributes)?;
+        }
+        if let Some(target_duration) = self.target_duration {
+            writeln!(f, "{}:{}", TagTypes::ExtXTargetDuration, target_duration)?;
+        }
+        if let Some(playlist_type) = self.playlist_type {
+            writeln!(f, "{}:{}", TagTypes::ExtXPlaylistType, playlist_type)?;
+        }
+        if self.media_sequence > 0 {
+            writeln!(f, "{}:{}", TagTypes::ExtXMediaSequence, self.media_sequence)?;
+        }
+        if self.discontinuity_sequence > 0 {
+            writeln!(
+                f,
+                "{}:{}",
+                TagTypes::ExtXDiscontinuitySequence,
+                self.discontinuity_sequence
+            )?;
+        }
+        if let Some(server_control) = self.server_control {
+            writeln!(f, "{}:{}", TagTypes::ExtXServerControl, server_control)?;
+        }
+        if let Some(part_target) = self.part_target {
+            writeln!(f, "{}:PART-TARGET={}", TagTypes::ExtXPartInf, part_target)?;
+        }
+        for date_range in &self.date_ranges {
+            writeln!(f, "{}:{}", TagTypes::ExtXDateRange, date_range)?;
+        }
+        if let Some(skip) = &self.skip {
+            writeln!(f, "{}:{}", TagTypes::ExtXSkip, skip)?;
+        }
+        let mut bitrate = None;
+        let mut keys: &[EncryptionKey] = &[];
+        let mut map = None;
+        let write_unknown = |f: &mut fmt::Formatter, index: usize| -> fmt::Result {
+            for unknown in &self.unknown_lines {
+                if unknown.segment_index == index {
+                    writeln!(f, "{}", unknown.line)?;
+                }
+            }
+            Ok(())
+        };
+        for (index, segment) in self.segments.iter().enumerate() {
+            write_unknown(f, index)?;
+            if segment.keys != keys {
+                if segment.keys.is_empty() {
+                    writeln!(f, "{}:METHOD={}", TagTypes::ExtXKey, KeyMethod::None)?;
+                }
+                for key in &segment.keys {
+                    writeln!(f, "{}:{}", TagTypes::ExtXKey, key)?;
+                }
+                keys = &segment.keys;
+            }
+            if let Some(value) = segment.bitrate.filter(|&value| Some(value) != bitrate) {
+                bitrate = Some(value);
+                writeln!(f, "{}:{}", TagTypes::ExtXBitrate, value)?;
+            }
+            if segment.discontinuity {
+                writeln!(f, "{}", TagTypes::ExtXDiscontinuity)?;
+            }
+            // Decoders reset at a discontinuity, so the init section is repeated there
+            let repeat = segment.discontinuity;
+            if let Some(value) = segment
+                .map
+                .as_ref()
+                .filter(|&value| repeat || Some(value) != map)
+            {
+                map = Some(value);
+                writeln!(f, "{}:{}", TagTypes::ExtXMap, value)?;
+            }
+            if let Some(ad_cue) = &segment.ad_cue {
+                writeln!(f, "{}", ad_cue)?;
+            }
+            if let Some(date_time) = segment.program_date_time {
+                let date_time = date_time.to_rfc3339_opts(SecondsFormat::Millis, true);
+                writeln!(f, "{}:{}", TagTypes::ExtXProgramDateTime, date_time)?;
+            }
+            for part in &segment.parts {
+                writeln!(f, "{}:{}", TagTypes::ExtXPart, part)?;
+            }
+            if let Some(tiles) = segment.tiles {
+                writeln!(f, "{}:{}", TagTypes::ExtXTiles, tiles)?;
+            }
+            writeln!(
+                f,
+                "{}:{},{}",
+                TagTypes::ExtInf,
+                segment.duration,
+                segment.title
+            )?;
+            if let Some(byte_range) = segment.byte_range {
+                writeln!(f, "{}:{}", TagTypes::ExtXByteRange, byte_range)?;
+            }
+            if segment.gap {
+                writeln!(f, "{}", TagTypes::ExtXGap)?;
+            }
+            writeln!(f, "{}", segment.uri)?;
+        }
+        write_unknown(f, self.segments.len())?;
+        if let Some(ad_cue) = &self.pending_ad_cue {
+            writeln!(f, "{}", ad_cue)?;
+        }
+        for part in &self.pending_parts {
+            writeln!(f, "{}:{}", TagTypes::ExtXPart, part)?;
+        }
+        for preload_hint in &self.preload_hints {
+            writeln!(f, "{}:{}", TagTypes::ExtXPreloadHint, preload_hint)?;
+        }
+        for rendition_report in &self.rendition_reports {
+            writeln!(f, "{}:{}", TagTypes::ExtXRenditionReport, rendition_report)?;
+        }
+        if self.end_list {
+            writeln!(f, "{}", TagTypes::ExtXEndList)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        ByteRange, MediaType, ParseError, PlaylistType, PreloadHintType, ServerControl, SortOrder,
+        Tag, M3U8,
+    };
+    use chrono::DateTime;
+
+    /// Process our example
+    #[cfg(feature = "http")]
+    #[test]
+    fn it_parses_example_uri() {
+        let uri =
+            "https://lw.bamgrid.com/2.0/hls/vod/bam/ms02/hls/dplus/bao/master_unenc_hdr10_all.m3u8";
+
+        let result = M3U8::from_uri(uri);
+
+        assert!(result.is_ok());
+
+        let mut parsed = result.unwrap();
+
+        assert_eq!(parsed.version, 2);
+        assert!(parsed.independent_segments);
+        assert_eq!(parsed.media_tags.len(), 4);
+        assert_eq!(
+            parsed.media_tags.first().unwrap().get("TYPE"),
+            Some(&"AUDIO".to_string())
+        );
+        assert_eq!(
+            parsed.media_tags.first().unwrap().get("URI"),
+            Some(&"audio/unenc/aac_128k/vod.m3u8".to_string())
+        );
+        assert_eq!(parsed.media_resources.len(), 2);
+        assert_eq!(
+            parsed.media_resources.first().unwrap().get("URI"),
+            Some(&"hdr10/unenc/3300k/vod-iframe.m3u8".to_string())
+        );
+        assert_eq!(parsed.media_tags.len(), 4);
+        assert_eq!(
+            parsed.media_tags.first().unwrap().get("URI"),
+            Some(&"audio/unenc/aac_128k/vod.m3u8".to_string())
+        );
+
+        // Test fetch and sorting media resources
         let media_resources = parsed.get_media_resources("BANDWIDTH");
-        assert_eq!(media_resources.first().unwrap()["BANDWIDTH"], "222552");
-        assert_eq!(media_resources.last().unwrap()["BANDWIDTH"], "77758");
+        assert_eq!(media_resources.first().unwrap()["BANDWIDTH"], "77758");
+        assert_eq!(media_resources.last().unwrap()["BANDWIDTH"], "222552");
         // Reverse and still see if in order
         parsed.media_resources.reverse();
         let media_resources = parsed.get_media_resources("BANDWIDTH");
-        assert_eq!(media_resources.first().unwrap()["BANDWIDTH"], "222552");
-        assert_eq!(media_resources.last().unwrap()["BANDWIDTH"], "77758");
+        assert_eq!(media_resources.first().unwrap()["BANDWIDTH"], "77758");
+        assert_eq!(media_resources.last().unwrap()["BANDWIDTH"], "222552");
 
         // Test fetch and sorting media streams
         parsed.media_tags.reverse();
         let media_tags = parsed.get_media_tags("CHANNELS");
-        assert_eq!(media_tags.first().unwrap()["CHANNELS"], "16/JOC");
-        assert_eq!(media_tags.last().unwrap()["CHANNELS"], "6");
+        // CHANNELS sorts by channel count, so 16/JOC comes after 2 and 6
+        assert_eq!(media_tags.first().unwrap()["CHANNELS"], "2");
+        assert_eq!(media_tags.last().unwrap()["CHANNELS"], "16/JOC");
 
         // Test fetch and sorting variant streams
         let variant_streams = parsed.get_variant_streams("BANDWIDTH");
-        assert_eq!(variant_streams.first().unwrap()["BANDWIDTH"], "10429877");
-        assert_eq!(variant_streams.last().unwrap()["BANDWIDTH"], "9661857");
+        let bandwidths: Vec<u64> = variant_streams
+            .iter()
+            .map(|variant| variant["BANDWIDTH"].parse().unwrap())
+            .collect();
+        assert!(bandwidths.windows(2).all(|pair| pair[0] <= pair[1]));
     }
 
     #[test]
@@ -318,4 +1841,806 @@ mod tests {
         let m3u8_result = M3U8::from_uri("www.example.com");
         assert!(m3u8_result.is_err());
     }
+
+    #[test]
+    /// Tests quoted strings keep commas, escaped quotes and unicode intact
+    fn it_parses_quoted_attribute_values() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"O\\\"Brien, Fran\u{e7}ais\",URI=\"a=b/audio.m3u8\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1280000,CODECS=\"avc1.4d401f,mp4a.40.2\",AUDIO=\"aac\"\n\
+            http://example.com/low.m3u8\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let media_tag = parsed.media_tags.first().unwrap();
+        assert_eq!(media_tag["NAME"], "O\"Brien, Fran\u{e7}ais");
+        assert_eq!(media_tag["URI"], "a=b/audio.m3u8");
+        assert_eq!(media_tag["TYPE"], "AUDIO");
+        let variant_stream = parsed.variant_streams.first().unwrap();
+        assert_eq!(variant_stream["CODECS"], "avc1.4d401f,mp4a.40.2");
+        assert_eq!(variant_stream["uri"], "http://example.com/low.m3u8");
+    }
+
+    #[test]
+    /// Tests serialized quoted strings survive a round trip
+    fn it_round_trips_quoted_attribute_values() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"O\\\"Brien \\\\ Fran\u{e7}ais\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1280000,CLOSED-CAPTIONS=NONE,CODECS=\"avc1,mp4a\"\n\
+            low.m3u8\n";
+
+        let written = M3U8::from_body(body).unwrap().to_string();
+
+        assert!(written.contains("NAME=\"O\\\"Brien \\\\ Fran\u{e7}ais\""));
+        assert!(written.contains("CLOSED-CAPTIONS=NONE,"));
+        assert!(written.contains("TYPE=AUDIO"));
+        let reparsed = M3U8::from_body(&written).unwrap();
+        assert_eq!(reparsed.media_tags[0]["NAME"], "O\"Brien \\ Fran\u{e7}ais");
+        assert_eq!(reparsed.variant_streams[0]["CODECS"], "avc1,mp4a");
+        assert_eq!(reparsed.variant_streams[0]["uri"], "low.m3u8");
+    }
+
+    #[test]
+    /// Tests media tags are indexed by type, group and name
+    fn it_indexes_media_groups() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"Deutsch\",LANGUAGE=\"de\"\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"aac\",NAME=\"English\"\n\
+            #EXT-X-MEDIA:TYPE=BOGUS,GROUP-ID=\"aac\",NAME=\"Ignored\"\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let groups = parsed.media_group_index();
+        assert_eq!(groups.len(), 2);
+        let audio = &groups[&(MediaType::Audio, "aac".to_string())];
+        assert_eq!(audio.len(), 2);
+        assert_eq!(audio[1]["LANGUAGE"], "de");
+        assert_eq!(groups[&(MediaType::Subtitles, "aac".to_string())].len(), 1);
+
+        let names = parsed.media_name_index();
+        let key = (MediaType::Audio, "aac".to_string(), "English".to_string());
+        assert_eq!(names[&key]["LANGUAGE"], "en");
+        assert_eq!(names.len(), 3);
+    }
+
+    #[test]
+    /// Tests byte ranges resolve offsets from the previous sub-range
+    fn it_resolves_byte_ranges() {
+        let body = "#EXTM3U\n\
+            #EXTINF:10.0,\n\
+            #EXT-X-BYTERANGE:1000@200\n\
+            main.ts\n\
+            #EXTINF:10.0,Second\n\
+            #EXT-X-BYTERANGE:500\n\
+            main.ts\n\
+            #EXTINF:9.5,\n\
+            #EXT-X-BYTERANGE:300\n\
+            other.ts\n\
+            #EXTINF:4,\n\
+            plain.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let segments = parsed.segments();
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[1].title, "Second");
+        assert_eq!(segments[2].duration, 9.5);
+        let ranges: Vec<Option<ByteRange>> = segments.iter().map(|s| s.byte_range).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                Some(ByteRange {
+                    length: 1000,
+                    offset: 200
+                }),
+                Some(ByteRange {
+                    length: 500,
+                    offset: 1200
+                }),
+                Some(ByteRange {
+                    length: 300,
+                    offset: 0
+                }),
+                None,
+            ]
+        );
+        assert!(parsed
+            .to_string()
+            .contains("#EXT-X-BYTERANGE:500@1200\nmain.ts"));
+    }
+
+    #[test]
+    /// Tests malformed byte ranges are rejected
+    fn it_fails_invalid_byte_range() {
+        let body = "#EXTM3U\n#EXTINF:10,\n#EXT-X-BYTERANGE:abc@1\nmain.ts\n";
+        assert!(M3U8::from_body(body).is_err());
+
+        let body = "#EXTM3U\n#EXT-X-BYTERANGE:18446744073709551615@10\n#EXTINF:1,\na.ts\n\
+            #EXT-X-BYTERANGE:5\n#EXTINF:1,\na.ts\n";
+        assert!(matches!(
+            M3U8::from_body(body),
+            Err(ParseError::InvalidM3U8(_))
+        ));
+    }
+
+    #[test]
+    /// Tests HTML error pages are reported as not being a playlist
+    fn it_detects_html_error_pages() {
+        let body = "\n<!DOCTYPE html><html><body>503 Service Unavailable</body></html>";
+
+        let result = M3U8::check_playlist_body(Some("text/html".to_string()), body);
+
+        match result {
+            Err(ParseError::NotAPlaylist {
+                content_type,
+                snippet,
+            }) => {
+                assert_eq!(content_type.as_deref(), Some("text/html"));
+                assert!(snippet.starts_with("<!DOCTYPE html>"));
+            }
+            _ => panic!("expected NotAPlaylist"),
+        }
+        let html_without_type = M3U8::check_playlist_body(None, "<html></html>");
+        assert!(matches!(
+            html_without_type,
+            Err(ParseError::NotAPlaylist { .. })
+        ));
+        assert!(M3U8::check_playlist_body(Some("text/html".to_string()), "#EXTM3U\n").is_ok());
+        assert!(M3U8::check_playlist_body(None, "garbage").is_ok());
+    }
+
+    #[test]
+    /// Tests discontinuity markers are flagged on the following segment
+    fn it_tracks_discontinuities() {
+        let body = "#EXTM3U\n\
+            #EXT-X-DISCONTINUITY-SEQUENCE:3\n\
+            #EXTINF:10,\n\
+            content1.ts\n\
+            #EXT-X-DISCONTINUITY\n\
+            #EXTINF:15,\n\
+            ad1.ts\n\
+            #EXTINF:15,\n\
+            ad2.ts\n\
+            #EXT-X-DISCONTINUITY\n\
+            #EXTINF:10,\n\
+            content2.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let flags: Vec<bool> = parsed.segments().iter().map(|s| s.discontinuity).collect();
+        assert_eq!(flags, vec![false, true, false, true]);
+        assert_eq!(parsed.discontinuity_count(), 2);
+        assert_eq!(parsed.discontinuity_sequence(), 3);
+        let written = parsed.to_string();
+        assert!(written.contains("#EXT-X-DISCONTINUITY-SEQUENCE:3\n"));
+        assert!(written.contains("#EXT-X-DISCONTINUITY\n#EXTINF:15,\nad1.ts"));
+    }
+
+    #[test]
+    /// Tests program date times attach to segments and are interpolated between tags
+    fn it_interpolates_program_date_times() {
+        let body = "#EXTM3U\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-03-01T12:00:00.000Z\n\
+            #EXTINF:6.5,\n\
+            a.ts\n\
+            #EXTINF:6,\n\
+            b.ts\n\
+            #EXT-X-DISCONTINUITY\n\
+            #EXTINF:6,\n\
+            ad.ts\n\
+            #EXT-X-DISCONTINUITY\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-03-01T13:00:00+01:00\n\
+            #EXTINF:6,\n\
+            c.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let segments = parsed.segments();
+        let at = |value: &str| DateTime::parse_from_rfc3339(value).unwrap();
+        assert_eq!(
+            segments[0].program_date_time,
+            Some(at("2024-03-01T12:00:00Z"))
+        );
+        assert_eq!(segments[1].program_date_time, None);
+        assert_eq!(segments[1].date_time, Some(at("2024-03-01T12:00:06.5Z")));
+        assert_eq!(segments[2].date_time, None);
+        assert_eq!(segments[3].date_time, Some(at("2024-03-01T12:00:00Z")));
+        assert_eq!(
+            parsed.segment_at(at("2024-03-01T12:00:08Z")).unwrap().uri,
+            "b.ts"
+        );
+        assert!(parsed.segment_at(at("2024-03-01T11:00:00Z")).is_none());
+        let written = parsed.to_string();
+        assert!(written.contains("#EXT-X-PROGRAM-DATE-TIME:2024-03-01T12:00:00.000Z\n"));
+        assert!(written.contains("#EXT-X-PROGRAM-DATE-TIME:2024-03-01T13:00:00.000+01:00\n"));
+    }
+
+    #[test]
+    /// Tests out-of-range segment durations leave later date times unknown
+    fn it_stops_interpolating_on_overflow() {
+        let body = "#EXTM3U\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-01-01T00:00:00Z\n\
+            #EXTINF:1e15,\na.ts\n\
+            #EXTINF:1,\nb.ts\n";
+        let parsed = M3U8::from_body(body).unwrap();
+        assert!(parsed.segments()[0].date_time.is_some());
+        assert_eq!(parsed.segments()[1].date_time, None);
+        assert!(M3U8::from_body("#EXTM3U\n#EXTINF:NaN,\na.ts\n").is_err());
+    }
+
+    #[test]
+    /// Tests date ranges expose typed timing and SCTE-35 bytes
+    fn it_parses_date_ranges() {
+        let body = "#EXTM3U\n\
+            #EXT-X-DATERANGE:ID=\"splice-6FFFFFF0\",START-DATE=\"2014-03-05T11:15:00Z\",\
+            PLANNED-DURATION=59.993,SCTE35-OUT=0xFC002F0000,X-AD-ID=\"ad, 1\",X-COUNT=3\n\
+            #EXT-X-DATERANGE:ID=\"splice-6FFFFFF0\",START-DATE=\"2014-03-05T11:15:00Z\",\
+            DURATION=59.993,SCTE35-IN=0xfc00\n\
+            #EXTINF:6,\n\
+            a.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let date_ranges = parsed.date_ranges();
+        assert_eq!(date_ranges.len(), 2);
+        assert_eq!(date_ranges[0].id, "splice-6FFFFFF0");
+        assert_eq!(date_ranges[0].planned_duration, Some(59.993));
+        assert_eq!(
+            date_ranges[0].scte35_out,
+            Some(vec![0xFC, 0x00, 0x2F, 0x00, 0x00])
+        );
+        assert_eq!(date_ranges[0].client_attributes["X-AD-ID"], "ad, 1");
+        assert_eq!(date_ranges[1].scte35_in, Some(vec![0xFC, 0x00]));
+        assert_eq!(
+            date_ranges[1].end(),
+            Some(DateTime::parse_from_rfc3339("2014-03-05T11:15:59.993Z").unwrap())
+        );
+
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.date_ranges(), date_ranges);
+        let invalid = "#EXTM3U\n#EXT-X-DATERANGE:ID=\"a\",START-DATE=\"x\"\n";
+        assert!(M3U8::from_body(invalid).is_err());
+    }
+
+    #[test]
+    /// Tests segments marked with EXT-X-GAP are flagged
+    fn it_flags_gap_segments() {
+        let body = "#EXTM3U\n\
+            #EXTINF:6,\na.ts\n\
+            #EXT-X-GAP\n#EXTINF:6,\nmissing.ts\n\
+            #EXTINF:6,\nc.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let gaps: Vec<bool> = parsed.segments().iter().map(|s| s.gap).collect();
+        assert_eq!(gaps, vec![false, true, false]);
+        assert!(parsed.to_string().contains("#EXT-X-GAP\nmissing.ts"));
+    }
+
+    #[test]
+    /// Tests EXT-X-BITRATE applies to following segments except byte ranges
+    fn it_applies_bitrate_hints() {
+        let body = "#EXTM3U\n\
+            #EXTINF:6,\na.ts\n\
+            #EXT-X-BITRATE:1200\n\
+            #EXTINF:6,\nb.ts\n\
+            #EXTINF:6,\nc.ts\n\
+            #EXTINF:6,\n#EXT-X-BYTERANGE:100@0\nd.ts\n\
+            #EXT-X-BITRATE:900\n\
+            #EXTINF:6,\ne.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let bitrates: Vec<Option<u64>> = parsed.segments().iter().map(|s| s.bitrate).collect();
+        assert_eq!(
+            bitrates,
+            vec![None, Some(1200), Some(1200), None, Some(900)]
+        );
+        let written = parsed.to_string();
+        assert_eq!(written.matches("#EXT-X-BITRATE:1200").count(), 1);
+        assert!(written.contains("#EXT-X-BITRATE:900\n#EXTINF:6,\ne.ts"));
+    }
+
+    #[test]
+    /// Tests playlists cut off mid-tag or before a URI report the line
+    fn it_detects_truncated_playlists() {
+        let truncated = |body: &str| match M3U8::from_body(body) {
+            Err(ParseError::Truncated { at_line }) => Some(at_line),
+            _ => None,
+        };
+
+        assert_eq!(
+            truncated("#EXTM3U\n#EXTINF:6,\na.ts\n\n#EXTINF:6,\n"),
+            Some(5)
+        );
+        assert_eq!(
+            truncated("#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1\n"),
+            Some(2)
+        );
+        assert_eq!(
+            truncated("#EXTM3U\n#EXT-X-MEDIA:TYPE=AUDIO,NAME=\"Engl"),
+            Some(2)
+        );
+        assert_eq!(
+            truncated("#EXTM3U\n#EXTINF:6,\na.ts\n#EXT-X-DISCON"),
+            Some(4)
+        );
+        assert_eq!(truncated("#EXTM3U\n#EXTINF:6,\na.ts\n#EXT-X-ENDLIST"), None);
+        assert_eq!(
+            truncated("#EXTM3U\n#EXTINF:6,\na.ts\n#EXT-X-CUSTOM\n"),
+            None
+        );
+    }
+
+    #[test]
+    /// Tests EXT-X-START is parsed into a typed start point
+    fn it_parses_start_point() {
+        let body = "#EXTM3U\n#EXT-X-START:TIME-OFFSET=-12.5,PRECISE=YES\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let start = parsed.start().unwrap();
+        assert_eq!(start.time_offset, -12.5);
+        assert!(start.precise);
+        assert!(parsed
+            .to_string()
+            .contains("#EXT-X-START:TIME-OFFSET=-12.5,PRECISE=YES\n"));
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-START:PRECISE=NO\n").is_err());
+    }
+
+    #[test]
+    /// Tests session data is kept and can be looked up by DATA-ID
+    fn it_parses_session_data() {
+        let body = "#EXTM3U\n\
+            #EXT-X-SESSION-DATA:DATA-ID=\"com.example.title\",VALUE=\"This is an example\",LANGUAGE=\"en\"\n\
+            #EXT-X-SESSION-DATA:DATA-ID=\"com.example.title\",VALUE=\"Este es un ejemplo\",LANGUAGE=\"es\"\n\
+            #EXT-X-SESSION-DATA:DATA-ID=\"com.example.lyrics\",URI=\"lyrics.json\"\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(parsed.session_data().len(), 3);
+        let titles = parsed.session_data_by_id("com.example.title");
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles[1].value.as_deref(), Some("Este es un ejemplo"));
+        assert_eq!(titles[1].language.as_deref(), Some("es"));
+        let lyrics = parsed.session_data_by_id("com.example.lyrics");
+        assert_eq!(lyrics[0].uri.as_deref(), Some("lyrics.json"));
+        assert!(parsed.session_data_by_id("missing").is_empty());
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.session_data(), parsed.session_data());
+    }
+
+    #[test]
+    /// Tests LL-HLS parts attach to their parent segment and round trip
+    fn it_parses_partial_segments() {
+        let body = "#EXTM3U\n\
+            #EXT-X-PART-INF:PART-TARGET=1.0\n\
+            #EXT-X-PART:DURATION=1.0,URI=\"seg0.mp4\",INDEPENDENT=YES,BYTERANGE=\"1000@0\"\n\
+            #EXT-X-PART:DURATION=1.0,URI=\"seg0.mp4\",BYTERANGE=\"800\"\n\
+            #EXTINF:2,\nseg0.mp4\n\
+            #EXT-X-PART:DURATION=1.0,URI=\"part1.0.mp4\",INDEPENDENT=YES\n\
+            #EXT-X-PART:DURATION=1.0,URI=\"part1.1.mp4\",GAP=YES\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(parsed.part_target(), Some(1.0));
+        let parts = &parsed.segments()[0].parts;
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].independent);
+        assert_eq!(
+            parts[1].byte_range,
+            Some(ByteRange {
+                length: 800,
+                offset: 1000
+            })
+        );
+        assert_eq!(parsed.pending_parts().len(), 2);
+        assert!(parsed.pending_parts()[1].gap);
+
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.segments(), parsed.segments());
+        assert_eq!(reparsed.pending_parts(), parsed.pending_parts());
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-PART:URI=\"a.mp4\"\n").is_err());
+    }
+
+    #[test]
+    /// Tests preload hints are typed and round trip
+    fn it_parses_preload_hints() {
+        let body = "#EXTM3U\n\
+            #EXTINF:2,\nseg0.mp4\n\
+            #EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"part1.0.mp4\"\n\
+            #EXT-X-PRELOAD-HINT:TYPE=MAP,URI=\"init.mp4\",BYTERANGE-START=100,BYTERANGE-LENGTH=50\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let hints = parsed.preload_hints();
+        assert_eq!(hints.len(), 2);
+        assert_eq!(hints[0].hint_type, PreloadHintType::Part);
+        assert_eq!(hints[0].byte_range_start, 0);
+        assert_eq!(hints[0].byte_range_length, None);
+        assert_eq!(hints[1].hint_type, PreloadHintType::Map);
+        assert_eq!(hints[1].byte_range_start, 100);
+        assert_eq!(hints[1].byte_range_length, Some(50));
+
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.preload_hints(), hints);
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-PRELOAD-HINT:TYPE=SEGMENT,URI=\"a\"\n").is_err());
+    }
+
+    #[test]
+    /// Tests rendition reports are collected and looked up by URI
+    fn it_parses_rendition_reports() {
+        let body = "#EXTM3U\n\
+            #EXTINF:2,\nseg0.mp4\n\
+            #EXT-X-RENDITION-REPORT:URI=\"../low/index.m3u8\",LAST-MSN=273,LAST-PART=2\n\
+            #EXT-X-RENDITION-REPORT:URI=\"../high/index.m3u8\"\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(parsed.rendition_reports().len(), 2);
+        let low = parsed.rendition_report("../low/index.m3u8").unwrap();
+        assert_eq!(low.last_msn, Some(273));
+        assert_eq!(low.last_part, Some(2));
+        assert_eq!(
+            parsed
+                .rendition_report("../high/index.m3u8")
+                .unwrap()
+                .last_msn,
+            None
+        );
+        assert!(parsed.rendition_report("missing.m3u8").is_none());
+
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.rendition_reports(), parsed.rendition_reports());
+    }
+
+    #[test]
+    /// Tests server control directives are typed and round trip
+    fn it_parses_server_control() {
+        let body = "#EXTM3U\n\
+            #EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES,PART-HOLD-BACK=1.0,CAN-SKIP-UNTIL=12.0\n\
+            #EXTINF:2,\nseg0.mp4\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(
+            parsed.server_control(),
+            Some(ServerControl {
+                can_block_reload: true,
+                hold_back: None,
+                part_hold_back: Some(1.0),
+                can_skip_until: Some(12.0),
+                can_skip_dateranges: false,
+            })
+        );
+        let reparsed = M3U8::from_body(&parsed.to_string()).unwrap();
+        assert_eq!(reparsed.server_control(), parsed.server_control());
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-SERVER-CONTROL:HOLD-BACK=soon\n").is_err());
+    }
+
+    #[test]
+    /// Tests I-frame playlists are flagged and their byte range segments resolved
+    fn it_parses_i_frames_only_playlists() {
+        let body = "#EXTM3U\n\
+            #EXT-X-VERSION:4\n\
+            #EXT-X-I-FRAMES-ONLY\n\
+            #EXTINF:2.002,\n#EXT-X-BYTERANGE:9400@376\nsegment0.ts\n\
+            #EXTINF:2.002,\n#EXT-X-BYTERANGE:7144@1128000\nsegment0.ts\n\
+            #EXTINF:2.002,\n#EXT-X-BYTERANGE:10340\nsegment0.ts\n\
+            #EXT-X-ENDLIST\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert!(parsed.i_frames_only());
+        let segments = parsed.segments();
+        assert_eq!(segments.len(), 3);
+        assert!(segments.iter().all(|segment| segment.title.is_empty()));
+        assert_eq!(segments[2].byte_range.unwrap().offset, 1_135_144);
+        assert!(M3U8::from_body(&parsed.to_string())
+            .unwrap()
+            .i_frames_only());
+        assert!(!M3U8::from_body("#EXTM3U\n").unwrap().i_frames_only());
+    }
+
+    #[test]
+    /// Tests the legacy allow cache tag is parsed and round trips
+    fn it_parses_allow_cache() {
+        let parsed = M3U8::from_body("#EXTM3U\n#EXT-X-ALLOW-CACHE:NO\n").unwrap();
+
+        assert_eq!(parsed.allow_cache(), Some(false));
+        assert!(parsed.unknown_lines().is_empty());
+        assert!(parsed.to_string().contains("#EXT-X-ALLOW-CACHE:NO\n"));
+        assert_eq!(M3U8::from_body("#EXTM3U\n").unwrap().allow_cache(), None);
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-ALLOW-CACHE:MAYBE\n").is_err());
+    }
+
+    #[test]
+    /// Tests media playlist header tags and EXT-X-MAP are parsed and round trip
+    fn it_parses_media_playlist_tags() {
+        let body = "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:4\n\
+            #EXT-X-PLAYLIST-TYPE:EVENT\n\
+            #EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\"\n\
+            #EXTINF:4,\na.m4s\n#EXTINF:4,\nb.m4s\n\
+            #EXT-X-DISCONTINUITY\n#EXT-X-MAP:URI=\"ad-init.mp4\"\n#EXTINF:4,\nad.m4s\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(parsed.target_duration(), Some(4));
+        assert_eq!(parsed.playlist_type(), Some(PlaylistType::Event));
+        assert!(parsed.unknown_lines().is_empty());
+        let maps: Vec<&str> = parsed
+            .segments()
+            .iter()
+            .filter_map(|segment| segment.map.as_ref().map(|map| map.uri.as_str()))
+            .collect();
+        assert_eq!(maps, vec!["init.mp4", "init.mp4", "ad-init.mp4"]);
+        assert_eq!(
+            parsed.segments()[0].map.as_ref().unwrap().byte_range,
+            Some(ByteRange {
+                length: 720,
+                offset: 0
+            })
+        );
+        let written = parsed.to_string();
+        assert!(written.contains("#EXT-X-TARGETDURATION:4\n#EXT-X-PLAYLIST-TYPE:EVENT\n"));
+        assert_eq!(written.matches("#EXT-X-MAP:").count(), 2);
+        assert!(written.contains(
+            "#EXT-X-DISCONTINUITY\n#EXT-X-MAP:URI=\"ad-init.mp4\"\n#EXTINF:4,\nad.m4s\n"
+        ));
+        assert_eq!(
+            M3U8::from_body(&written).unwrap().segments(),
+            parsed.segments()
+        );
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-PLAYLIST-TYPE:LIVE\n").is_err());
+    }
+
+    #[test]
+    /// Tests tag occurrences, unknown tags and comments are counted
+    fn it_counts_tags() {
+        let body = "#EXTM3U\n\
+            # generated for a test\n\
+            #EXT-X-TARGETDURATION:6\n\
+            #EXT-X-AD-MARKER:30\n\
+            #EXTINF:6,\na.ts\n#EXT-X-DISCONTINUITY\n#EXTINF:6,\nb.ts\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let counts = parsed.tag_counts();
+        assert_eq!(counts.get(&Tag::ExtInf), Some(&2));
+        assert_eq!(counts.get(&Tag::ExtXDiscontinuity), Some(&1));
+        assert_eq!(counts.get(&Tag::ExtM3U), Some(&1));
+        assert_eq!(counts.get(&Tag::ExtXEndList), None);
+        assert_eq!(counts.get(&Tag::ExtXTargetDuration), Some(&1));
+        assert_eq!(parsed.unknown_tag_count(), 1);
+        assert_eq!(parsed.comment_count(), 1);
+    }
+
+    #[test]
+    /// Tests comments and unknown tags are kept with their positions and written back
+    fn it_preserves_unknown_lines() {
+        let body = "#EXTM3U\n\
+            # packaged by test\n\
+            #EXTINF:6,\na.ts\n\
+            #EXT-X-AD-MARKER:30\n\
+            #EXTINF:6,\nb.ts\n\
+            #EXT-X-AD-END\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let unknown = parsed.unknown_lines();
+        assert_eq!(unknown.len(), 3);
+        assert_eq!(unknown[0].line, "# packaged by test");
+        assert_eq!(unknown[1].line_number, 5);
+        assert_eq!(unknown[1].segment_index, 1);
+        assert_eq!(unknown[2].segment_index, 2);
+
+        let written = parsed.to_string();
+        assert!(written.contains("a.ts\n#EXT-X-AD-MARKER:30\n#EXTINF:6,\nb.ts\n#EXT-X-AD-END\n"));
+        let reparsed = M3U8::from_body(&written).unwrap();
+        let lines = |m3u8: &M3U8| -> Vec<String> {
+            m3u8.unknown_lines()
+                .iter()
+                .map(|unknown| unknown.line.clone())
+                .collect()
+        };
+        assert_eq!(lines(&reparsed), lines(&parsed));
+    }
+
+    #[test]
+    /// Tests getters sort by several keys and keep playlist order for ties
+    fn it_sorts_by_multiple_keys() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=3000000,RESOLUTION=1280x720,CODECS=\"hvc1\"\nhevc.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080\nfhd.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=3000000,RESOLUTION=1280x720,CODECS=\"avc1\"\navc.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2500000,RESOLUTION=1280x720\nmid.m3u8\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let sorted = parsed.get_variant_streams_sorted(&[
+            ("RESOLUTION", SortOrder::Desc),
+            ("BANDWIDTH", SortOrder::Asc),
+        ]);
+        let uris: Vec<&str> = sorted
+            .iter()
+            .map(|variant| variant["uri"].as_str())
+            .collect();
+        assert_eq!(uris, vec!["fhd.m3u8", "mid.m3u8", "hevc.m3u8", "avc.m3u8"]);
+        let unsorted = parsed.get_variant_streams_sorted(&[]);
+        assert_eq!(unsorted[0]["uri"], "hevc.m3u8");
+    }
+
+    #[test]
+    /// Tests errors describe themselves and expose their source
+    fn it_displays_errors() {
+        use std::error::Error;
+
+        let truncated = ParseError::Truncated { at_line: 4 };
+        assert_eq!(truncated.to_string(), "playlist is truncated at line 4");
+        assert!(truncated.source().is_none());
+
+        let io = ParseError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
+        assert_eq!(io.to_string(), "IO error: gone");
+        assert!(io.source().is_some());
+
+        let boxed: Box<dyn Error> = Box::new(M3U8::from_body("nope").unwrap_err());
+        assert!(boxed.to_string().starts_with("invalid playlist"));
+    }
+
+    #[test]
+    /// Tests thumbnail streams and tiled image segments are parsed and written back
+    fn it_parses_image_streams_and_tiles() {
+        let master = "#EXTM3U\n\
+            #EXT-X-IMAGE-STREAM-INF:BANDWIDTH=12000,RESOLUTION=320x180,CODECS=\"jpeg\",\
+            URI=\"thumbs.m3u8\"\n";
+        let mut parsed = M3U8::from_body(master).unwrap();
+        let image_streams = parsed.get_image_streams("BANDWIDTH");
+        assert_eq!(image_streams[0]["URI"], "thumbs.m3u8");
+        assert!(parsed.to_string().contains(
+            "#EXT-X-IMAGE-STREAM-INF:BANDWIDTH=12000,CODECS=\"jpeg\",RESOLUTION=320x180,\
+             URI=\"thumbs.m3u8\""
+        ));
+
+        let media = "#EXTM3U\n#EXT-X-IMAGES-ONLY\n\
+            #EXT-X-TILES:RESOLUTION=320x180,LAYOUT=5x4,DURATION=2.0\n\
+            #EXTINF:40.0,\ntiles-1.jpg\n";
+        let parsed = M3U8::from_body(media).unwrap();
+        assert!(parsed.images_only());
+        let tiles = parsed.segments()[0].tiles.unwrap();
+        assert_eq!((tiles.columns, tiles.rows), (5, 4));
+        assert_eq!(tiles.tile_at(13.0), Some((1, 1)));
+        assert_eq!(tiles.tile_at(40.0), None);
+        let written = parsed.to_string();
+        assert!(written.contains("#EXT-X-IMAGES-ONLY\n"));
+        assert!(written.contains("#EXT-X-TILES:RESOLUTION=320x180,LAYOUT=5x4,DURATION=2\n"));
+
+        let huge = "#EXTM3U\n#EXT-X-IMAGES-ONLY\n\
+            #EXT-X-TILES:RESOLUTION=160x90,LAYOUT=100000x100000,DURATION=1\n\
+            #EXTINF:10.0,\ntiles-1.jpg\n";
+        let tiles = M3U8::from_body(huge).unwrap().segments()[0].tiles.unwrap();
+        assert_eq!(tiles.tile_at(5.0), Some((5, 0)));
+        assert_eq!(tiles.tile_at(f64::INFINITY), None);
+    }
+
+    #[test]
+    /// Tests numeric and resolution attributes sort by value rather than as text
+    fn it_sorts_numeric_attributes() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=9661857,RESOLUTION=1280x720,FRAME-RATE=59.94\nb.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=10429877,RESOLUTION=3840x2160,FRAME-RATE=120\nc.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,FRAME-RATE=29.97\na.m3u8\n";
+        let mut parsed = M3U8::from_body(body).unwrap();
+
+        let uris = |streams: Vec<std::collections::HashMap<String, String>>| -> Vec<String> {
+            streams
+                .iter()
+                .map(|stream| stream["uri"].to_string())
+                .collect()
+        };
+        assert_eq!(
+            uris(parsed.get_variant_streams("BANDWIDTH")),
+            vec!["a.m3u8", "b.m3u8", "c.m3u8"]
+        );
+        assert_eq!(
+            uris(parsed.get_variant_streams_sorted(&[("FRAME-RATE", SortOrder::Desc)])),
+            vec!["c.m3u8", "b.m3u8", "a.m3u8"]
+        );
+        assert_eq!(
+            uris(parsed.get_variant_streams_sorted(&[("RESOLUTION", SortOrder::Asc)])),
+            vec!["a.m3u8", "b.m3u8", "c.m3u8"]
+        );
+
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"Atmos\",CHANNELS=\"16/JOC\",URI=\"atmos.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"Stereo\",CHANNELS=\"2\",URI=\"stereo.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"Surround\",CHANNELS=\"6\",URI=\"surround.m3u8\"\n";
+        let mut parsed = M3U8::from_body(body).unwrap();
+        let channels: Vec<String> = parsed
+            .get_media_tags("CHANNELS")
+            .iter()
+            .map(|media| media["CHANNELS"].to_string())
+            .collect();
+        assert_eq!(channels, vec!["2", "6", "16/JOC"]);
+    }
+
+    #[test]
+    /// Tests borrowed accessors leave playlist order alone and sort views by reference
+    fn it_borrows_sorted_views() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000\nhigh.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000\nlow.m3u8\n\
+            #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=90000,URI=\"low-if.m3u8\"\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let sorted = parsed.variant_streams_sorted_by(&[("BANDWIDTH", SortOrder::Asc)]);
+
+        assert_eq!(sorted[0]["uri"], "low.m3u8");
+        assert!(std::ptr::eq(sorted[0], &parsed.variant_streams()[1]));
+        assert_eq!(parsed.variant_streams()[0]["uri"], "high.m3u8");
+        assert_eq!(parsed.media_tags()[0]["NAME"], "English");
+        assert_eq!(parsed.iframe_streams()[0]["URI"], "low-if.m3u8");
+        assert!(parsed.image_streams().is_empty());
+    }
+
+    #[test]
+    /// Tests playlists parse from strings without a request
+    fn it_parses_from_str() {
+        let body = "#EXTM3U\n#EXT-X-VERSION:3\n#EXTINF:4.5,\na.ts\n#EXT-X-ENDLIST\n";
+
+        let parsed: M3U8 = body.parse().unwrap();
+
+        assert_eq!(parsed.segments()[0].uri, "a.ts");
+        assert_eq!(parsed.to_string(), body);
+        assert_eq!(
+            <M3U8 as std::str::FromStr>::from_str(body)
+                .unwrap()
+                .version(),
+            3
+        );
+        assert!(matches!(
+            "<html></html>".parse::<M3U8>(),
+            Err(ParseError::InvalidM3U8(_))
+        ));
+    }
+
+    #[test]
+    /// Tests playlists parse from files and readers
+    fn it_parses_files_and_readers() {
+        let body = "#EXTM3U\n#EXTINF:4,\na.ts\n#EXT-X-ENDLIST\n";
+        let path = std::env::temp_dir().join("m3u8parse-from-file.m3u8");
+        std::fs::write(&path, body).unwrap();
+
+        let parsed = M3U8::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(parsed.segments()[0].uri, "a.ts");
+        let parsed = M3U8::from_reader(std::io::Cursor::new(body)).unwrap();
+        assert!(parsed.end_list());
+        assert!(matches!(
+            M3U8::from_file("/nonexistent/playlist.m3u8"),
+            Err(ParseError::IoError(_))
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    /// Tests playlists are fetched with the async client
+    fn it_fetches_asynchronously() {
+        let dir = crate::testing::FixtureDir::new("async-fixture").unwrap();
+        std::fs::write(dir.join("live.m3u8"), "#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+        let (url, server) = crate::testing::serve_fixture(&dir).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let parsed = runtime
+            .block_on(M3U8::from_uri_async(&format!("{}/live.m3u8", url)))
+            .unwrap();
+
+        assert_eq!(parsed.segments()[0].uri, "a.ts");
+        assert_eq!(server.requests(), 1);
+    }
 }
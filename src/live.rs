@@ -0,0 +1,210 @@
+//! Live media playlists polled for new segments.
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{FetchOptions, ParseError, PlaylistHandle, Refresh, Segment, M3U8};
+
+/// Reload interval used while a playlist declares no EXT-X-TARGETDURATION
+const DEFAULT_TARGET_DURATION: Duration = Duration::from_secs(6);
+
+/// Live media playlist that yields each segment once as it is published
+///
+/// The playlist is reloaded with conditional requests one target duration after it
+/// last changed, or half a target duration after a reload that found no change, as
+/// clients are asked to in RFC 8216. Segments are told apart by media sequence
+/// number, so each one is yielded once however long it stays in the window. When
+/// the newest sequence number goes backwards the origin is taken to have restarted
+/// and its whole window is yielded again.
+///
+/// As an iterator it sleeps until each reload is due and ends after EXT-X-ENDLIST.
+#[derive(Debug, Clone)]
+pub struct LivePlaylist {
+    uri: String,
+    options: FetchOptions,
+    handle: Option<PlaylistHandle>,
+    last_sequence: Option<u64>,
+    reload_interval: Duration,
+    next_reload: Option<Instant>,
+    pending: VecDeque<Segment>,
+}
+
+/// Implementation for LivePlaylist
+impl LivePlaylist {
+    /// Follows a live media playlist, fetching it on the first poll
+    pub fn new(uri: &str) -> Self {
+        LivePlaylist {
+            uri: uri.to_string(),
+            options: FetchOptions::default(),
+            handle: None,
+            last_sequence: None,
+            reload_interval: Duration::ZERO,
+            next_reload: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Options used for every reload
+    pub fn with_options(mut self, options: FetchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the playlist as of the last reload that changed it
+    pub fn playlist(&self) -> Option<&M3U8> {
+        self.handle.as_ref().map(PlaylistHandle::playlist)
+    }
+
+    /// Returns the media sequence number of the newest segment seen
+    pub fn last_sequence(&self) -> Option<u64> {
+        self.last_sequence
+    }
+
+    /// Returns how long after the last poll the next reload is due
+    pub fn reload_interval(&self) -> Duration {
+        self.reload_interval
+    }
+
+    /// Whether the playlist has ended with EXT-X-ENDLIST
+    pub fn is_ended(&self) -> bool {
+        self.playlist().is_some_and(M3U8::end_list)
+    }
+
+    /// Reloads the playlist now and returns the segments added since the last poll
+    pub fn poll(&mut self) -> Result<Vec<Segment>, ParseError> {
+        let result = self.reload();
+        let target = self
+            .playlist()
+            .and_then(M3U8::target_duration)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TARGET_DURATION);
+        self.reload_interval = match &result {
+            Ok(Some(_)) => target,
+            _ => target / 2,
+        };
+        self.next_reload = Some(Instant::now() + self.reload_interval);
+        let Some(playlist) = result? else {
+            return Ok(vec![]);
+        };
+
+        let newest = playlist.segments.last().map(|segment| segment.sequence);
+        let restarted =
+            matches!((newest, self.last_sequence), (Some(newest), Some(last)) if newest < last);
+        let added = playlist
+            .segments
+            .iter()
+            .filter(|segment| {
+                restarted
+                    || self
+                        .last_sequence
+                        .is_none_or(|last| segment.sequence > last)
+            })
+            .cloned()
+            .collect();
+        if newest.is_some() {
+            self.last_sequence = newest;
+        }
+        Ok(added)
+    }
+
+    /// Fetches or reloads the playlist, returning it when it changed
+    fn reload(&mut self) -> Result<Option<M3U8>, ParseError> {
+        match &mut self.handle {
+            Some(handle) => match handle.reload()? {
+                Refresh::Changed(playlist) => Ok(Some(*playlist)),
+                Refresh::Unchanged => Ok(None),
+            },
+            None => {
+                let handle = PlaylistHandle::open_with_options(&self.uri, self.options.clone())?;
+                let playlist = handle.playlist().clone();
+                self.handle = Some(handle);
+                Ok(Some(playlist))
+            }
+        }
+    }
+
+    /// Polls on a background thread, sending each new segment over a channel
+    ///
+    /// The thread stops after the playlist ends or when the receiver is dropped.
+    pub fn spawn(self) -> Receiver<Result<Segment, ParseError>> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for segment in self {
+                if sender.send(segment).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+}
+
+/// Iterator of new segments, blocking until each reload is due
+impl Iterator for LivePlaylist {
+    type Item = Result<Segment, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(segment) = self.pending.pop_front() {
+                return Some(Ok(segment));
+            }
+            if self.is_ended() {
+                return None;
+            }
+            if let Some(next_reload) = self.next_reload {
+                thread::sleep(next_reload.saturating_duration_since(Instant::now()));
+            }
+            match self.poll() {
+                Ok(segments) => self.pending.extend(segments),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::testing::{serve_fixture, FixtureDir};
+    use crate::LivePlaylist;
+
+    #[test]
+    /// Tests each segment is yielded once across reloads until the playlist ends
+    fn it_yields_new_segments() {
+        let dir = FixtureDir::new("live").unwrap();
+        let publish = |sequence: u64, uris: &[&str], ended: bool| {
+            let mut body = format!(
+                "#EXTM3U\n#EXT-X-TARGETDURATION:1\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+                sequence
+            );
+            for uri in uris {
+                body.push_str(&format!("#EXTINF:1,\n{}\n", uri));
+            }
+            if ended {
+                body.push_str("#EXT-X-ENDLIST\n");
+            }
+            std::fs::write(dir.join("live.m3u8"), body).unwrap();
+        };
+        let uris = |segments: Vec<crate::Segment>| -> Vec<String> {
+            segments.into_iter().map(|segment| segment.uri).collect()
+        };
+        publish(0, &["a.ts", "b.ts"], false);
+        let (url, _guard) = serve_fixture(&dir).unwrap();
+        let mut live = LivePlaylist::new(&format!("{}/live.m3u8", url));
+
+        assert_eq!(uris(live.poll().unwrap()), vec!["a.ts", "b.ts"]);
+        assert_eq!(live.reload_interval(), Duration::from_secs(1));
+        publish(1, &["b.ts", "c.ts"], false);
+        assert_eq!(uris(live.poll().unwrap()), vec!["c.ts"]);
+        assert_eq!(live.last_sequence(), Some(2));
+        assert!(live.poll().unwrap().is_empty());
+        assert_eq!(live.reload_interval(), Duration::from_millis(500));
+
+        publish(2, &["c.ts", "d.ts"], true);
+        let rest: Vec<String> = live.map(|segment| segment.unwrap().uri).collect();
+        assert_eq!(rest, vec!["d.ts"]);
+    }
+}
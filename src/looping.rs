@@ -0,0 +1,163 @@
+//! Endless live playlists looped from a VOD playlist, for test streams.
+use chrono::{DateTime, FixedOffset};
+
+use crate::segment::seconds_to_duration;
+use crate::{MediaPlaylist, ParseError, M3U8};
+
+/// Sliding live playlist that loops the segments of a VOD playlist forever
+///
+/// Window `n` starts at media sequence `n` and the loop repeats every source segment
+/// count. A discontinuity is marked where the loop wraps, and program date times
+/// advance with the summed segment durations when a start time is set. As an
+/// iterator it yields the windows from sequence 0 onwards and never ends.
+#[derive(Debug, Clone)]
+pub struct LiveLoop {
+    source: MediaPlaylist,
+    window: usize,
+    start: Option<DateTime<FixedOffset>>,
+    next_sequence: u64,
+}
+
+/// Implementation for LiveLoop
+impl LiveLoop {
+    /// Loops the segments of `source`, publishing `window` segments at a time
+    pub fn new(source: &MediaPlaylist, window: usize) -> Result<Self, ParseError> {
+        if source.segments.is_empty() || window == 0 {
+            return Err(ParseError::InvalidM3U8(
+                "A live loop needs segments and a window of at least one".to_string(),
+            ));
+        }
+        Ok(LiveLoop {
+            source: source.clone(),
+            window,
+            start: None,
+            next_sequence: 0,
+        })
+    }
+
+    /// Tags every segment with EXT-X-PROGRAM-DATE-TIME counted from `start`
+    pub fn start_time(mut self, start: DateTime<FixedOffset>) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Whether the segment at a looped sequence number starts a discontinuity
+    fn is_discontinuity(&self, sequence: u64) -> bool {
+        let count = self.source.segments.len() as u64;
+        let index = (sequence % count) as usize;
+        sequence > 0 && (index == 0 || self.source.segments[index].discontinuity)
+    }
+
+    /// Returns the live playlist whose first segment has media sequence `sequence`
+    pub fn playlist_at(&self, sequence: u64) -> MediaPlaylist {
+        let segments = &self.source.segments;
+        let count = segments.len() as u64;
+        let loop_duration: f64 = segments.iter().map(|segment| segment.duration).sum();
+        let inner_discontinuities = |end: usize| {
+            segments[1..end.max(1)]
+                .iter()
+                .filter(|segment| segment.discontinuity)
+                .count() as u64
+        };
+        let loops = sequence / count;
+        let wraps = sequence.saturating_sub(1) / count;
+        let discontinuity_sequence = wraps
+            + loops * inner_discontinuities(segments.len())
+            + inner_discontinuities((sequence % count) as usize);
+
+        let mut playlist = M3U8 {
+            segments: vec![],
+            media_sequence: sequence,
+            discontinuity_sequence: self.source.discontinuity_sequence + discontinuity_sequence,
+            date_ranges: vec![],
+            unknown_lines: self
+                .source
+                .unknown_lines
+                .iter()
+                .filter(|unknown| unknown.segment_index == 0 && unknown.line.starts_with("#EXT"))
+                .cloned()
+                .collect(),
+            end_list: false,
+            // A sliding window is neither VOD nor EVENT
+            playlist_type: None,
+            ..self.source.clone()
+        };
+        for looped in sequence..sequence + self.window as u64 {
+            let index = (looped % count) as usize;
+            let mut segment = segments[index].clone();
+            segment.sequence = looped;
+            segment.discontinuity = self.is_discontinuity(looped);
+            let offset = (looped / count) as f64 * loop_duration
+                + segments[..index]
+                    .iter()
+                    .map(|segment| segment.duration)
+                    .sum::<f64>();
+            segment.program_date_time = self
+                .start
+                .and_then(|start| start.checked_add_signed(seconds_to_duration(offset)?));
+            segment.date_time = segment.program_date_time;
+            playlist.segments.push(segment);
+        }
+        playlist
+    }
+}
+
+/// Yields successive windows, each one segment further along the loop
+impl Iterator for LiveLoop {
+    type Item = MediaPlaylist;
+
+    fn next(&mut self) -> Option<MediaPlaylist> {
+        let playlist = self.playlist_at(self.next_sequence);
+        self.next_sequence += 1;
+        Some(playlist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use chrono::DateTime;
+
+    use crate::{LiveLoop, M3U8};
+
+    #[test]
+    /// Tests the loop slides over the source with discontinuities and advancing times
+    fn it_loops_a_vod_playlist() {
+        let source = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-TARGETDURATION:4\n#EXT-X-PLAYLIST-TYPE:VOD\n\
+             #EXTINF:4,\na.ts\n#EXTINF:4,\nb.ts\n#EXTINF:2,\nc.ts\n#EXT-X-ENDLIST\n",
+        )
+        .unwrap();
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        let mut live = LiveLoop::new(&source, 3).unwrap().start_time(start);
+
+        let first = live.next().unwrap();
+        assert_eq!(first.segments().len(), 3);
+        assert!(!first.end_list());
+
+        let wrapped = live.playlist_at(5);
+        let uris: Vec<&str> = wrapped
+            .segments()
+            .iter()
+            .map(|segment| segment.uri.as_str())
+            .collect();
+        assert_eq!(uris, vec!["c.ts", "a.ts", "b.ts"]);
+        assert!(wrapped.segments()[1].discontinuity);
+        assert_eq!(wrapped.discontinuity_sequence(), 1);
+        assert_eq!(
+            wrapped.segments()[1]
+                .program_date_time
+                .unwrap()
+                .to_rfc3339(),
+            "2024-01-01T00:00:20+00:00"
+        );
+        let written = wrapped.to_string();
+        assert!(written.contains("#EXT-X-MEDIA-SEQUENCE:5\n#EXT-X-DISCONTINUITY-SEQUENCE:1\n"));
+        assert!(written.contains("#EXT-X-TARGETDURATION:4\n"));
+        assert!(!written.contains("#EXT-X-ENDLIST"));
+        assert!(!written.contains("#EXT-X-PLAYLIST-TYPE"));
+
+        assert_eq!(live.nth(5).unwrap().media_sequence(), 6);
+        assert!(LiveLoop::new(&source, 0).is_err());
+    }
+}
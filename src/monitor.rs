@@ -0,0 +1,342 @@
+//! Live playlist monitoring.
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::{FetchOptions, ParseError, PlaylistFetcher, M3U8};
+
+/// Default number of unchanged refreshes tolerated before a live playlist is stale
+const DEFAULT_MAX_STALE_REFRESHES: usize = 3;
+
+/// Default number of refreshes served by a backup before the primary URI is retried
+const DEFAULT_FAIL_BACK_AFTER: usize = 10;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Outcome of fetching a playlist that may not have changed
+#[derive(Debug)]
+pub enum ContentChange {
+    /// The body hashed to the previous hash and was not parsed
+    Unchanged,
+    /// The body changed, with the parsed playlist and its new hash
+    Changed(Box<M3U8>, u64),
+}
+
+/// Outcome of refreshing a monitored playlist
+#[derive(Debug)]
+pub enum Refresh {
+    /// The playlist changed since the last refresh
+    Changed(Box<M3U8>),
+    /// The origin served the same playlist as the last refresh
+    Unchanged,
+}
+
+/// Refreshes a live playlist and detects when its origin stops advancing
+///
+/// When a fetch fails, or more than `max_stale_refreshes` consecutive refreshes return
+/// an unchanged playlist that has not ended, the monitor fails over to the next backup
+/// URI. While on a backup the primary URI is retried every `fail_back_after` refreshes.
+pub struct Monitor {
+    uris: Vec<String>,
+    active: usize,
+    served_by: Option<usize>,
+    max_stale_refreshes: usize,
+    stale_refreshes: usize,
+    fail_back_after: usize,
+    backup_refreshes: usize,
+    last_hash: Option<u64>,
+    end_list: bool,
+    publish_latency: Option<Duration>,
+    fetcher: Arc<dyn PlaylistFetcher>,
+}
+
+/// Monitor as a Debug type, leaving out the fetcher
+impl fmt::Debug for Monitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Monitor")
+            .field("uris", &self.uris)
+            .field("active", &self.active)
+            .field("served_by", &self.served_by)
+            .field("stale_refreshes", &self.stale_refreshes)
+            .field("last_hash", &self.last_hash)
+            .field("end_list", &self.end_list)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Implementation for Monitor
+impl Monitor {
+    /// Monitors a single playlist URI
+    pub fn new(uri: &str) -> Self {
+        Monitor {
+            uris: vec![uri.to_string()],
+            active: 0,
+            served_by: None,
+            max_stale_refreshes: DEFAULT_MAX_STALE_REFRESHES,
+            stale_refreshes: 0,
+            fail_back_after: DEFAULT_FAIL_BACK_AFTER,
+            backup_refreshes: 0,
+            last_hash: None,
+            end_list: false,
+            publish_latency: None,
+            fetcher: Arc::new(FetchOptions::default()),
+        }
+    }
+
+    /// Alternate URIs serving the same stream, tried in order when the active one fails
+    pub fn with_backup_uris(mut self, uris: &[&str]) -> Self {
+        self.uris.extend(uris.iter().map(|uri| uri.to_string()));
+        self
+    }
+
+    /// Fetcher used to load the playlist, instead of reqwest with default options
+    pub fn with_fetcher(mut self, fetcher: Arc<dyn PlaylistFetcher>) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
+    /// Number of consecutive unchanged refreshes allowed before switching URI
+    pub fn with_max_stale_refreshes(mut self, max_stale_refreshes: usize) -> Self {
+        self.max_stale_refreshes = max_stale_refreshes;
+        self
+    }
+
+    /// Number of refreshes served by a backup before trying to fail back to the primary
+    pub fn with_fail_back_after(mut self, fail_back_after: usize) -> Self {
+        self.fail_back_after = fail_back_after;
+        self
+    }
+
+    /// Returns the URI currently being refreshed
+    pub fn uri(&self) -> &str {
+        &self.uris[self.active]
+    }
+
+    /// Returns the URI that served the most recent successful refresh
+    pub fn served_by(&self) -> Option<&str> {
+        self.served_by.map(|index| self.uris[index].as_str())
+    }
+
+    /// Fetches the playlist again, failing over to backup URIs on errors or staleness
+    pub fn refresh(&mut self) -> Result<Refresh, ParseError> {
+        let body = self.fetch()?;
+        let refresh = self.update(body)?;
+        if self.is_stale() && self.switch_uri() {
+            let body = self.fetch()?;
+            return self.update(body);
+        }
+        Ok(refresh)
+    }
+
+    /// Fetches from the active URI, failing back to the primary or over to backups
+    fn fetch(&mut self) -> Result<String, ParseError> {
+        if self.active != 0 {
+            self.backup_refreshes += 1;
+            if self.backup_refreshes > self.fail_back_after {
+                self.backup_refreshes = 0;
+                if let Ok(body) = M3U8::fetch_body_with(&self.uris[0], self.fetcher.as_ref()) {
+                    self.use_uri(0);
+                    return Ok(body);
+                }
+            }
+        }
+        let mut last_error = None;
+        for attempt in 0..self.uris.len() {
+            let index = (self.active + attempt) % self.uris.len();
+            match M3U8::fetch_body_with(&self.uris[index], self.fetcher.as_ref()) {
+                Ok(body) => {
+                    self.use_uri(index);
+                    return Ok(body);
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(last_error.expect("monitor always has a URI"))
+    }
+
+    /// Makes a URI active after it served a refresh
+    fn use_uri(&mut self, index: usize) {
+        if index != self.active {
+            self.active = index;
+            self.backup_refreshes = 0;
+            self.stale_refreshes = 0;
+        }
+        self.served_by = Some(index);
+    }
+
+    /// Returns the publish latency measured when the playlist last changed
+    ///
+    /// This is how long before the refresh the newest segment finished according to
+    /// its EXT-X-PROGRAM-DATE-TIME, and is None when the playlist has no date times.
+    pub fn publish_latency(&self) -> Option<Duration> {
+        self.publish_latency
+    }
+
+    /// Whether the live playlist has not advanced for longer than allowed
+    pub fn is_stale(&self) -> bool {
+        !self.end_list && self.stale_refreshes > self.max_stale_refreshes
+    }
+
+    /// Records a fetched body, parsing it only when it differs from the last one
+    fn update(&mut self, body: String) -> Result<Refresh, ParseError> {
+        match M3U8::parse_if_changed(&body, self.last_hash)? {
+            ContentChange::Unchanged => {
+                self.stale_refreshes += 1;
+                Ok(Refresh::Unchanged)
+            }
+            ContentChange::Changed(m3u8, hash) => {
+                self.stale_refreshes = 0;
+                self.end_list = m3u8.end_list();
+                self.publish_latency = m3u8.publish_latency(Utc::now());
+                self.last_hash = Some(hash);
+                Ok(Refresh::Changed(m3u8))
+            }
+        }
+    }
+
+    /// Moves to the next URI, returning false when there is no backup to move to
+    fn switch_uri(&mut self) -> bool {
+        if self.uris.len() < 2 {
+            return false;
+        }
+        self.active = (self.active + 1) % self.uris.len();
+        self.stale_refreshes = 0;
+        true
+    }
+}
+
+/// Implementation of change detection for M3U8
+impl M3U8 {
+    /// Stable 64-bit FNV-1a hash of a playlist body, safe to persist between runs
+    pub fn content_hash(body: &str) -> u64 {
+        body.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+    }
+
+    /// Parses a body only when its hash differs from `prev_hash`
+    pub fn parse_if_changed(
+        body: &str,
+        prev_hash: Option<u64>,
+    ) -> Result<ContentChange, ParseError> {
+        let hash = M3U8::content_hash(body);
+        if prev_hash == Some(hash) {
+            return Ok(ContentChange::Unchanged);
+        }
+        Ok(ContentChange::Changed(
+            Box::new(M3U8::from_body(body)?),
+            hash,
+        ))
+    }
+
+    /// Fetches a URI and parses it only when its body changed since `prev_hash`
+    pub fn fetch_if_changed(
+        uri: &str,
+        prev_hash: Option<u64>,
+    ) -> Result<ContentChange, ParseError> {
+        let body = M3U8::fetch_body(uri)?;
+        M3U8::parse_if_changed(&body, prev_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ContentChange, Monitor, ParseError, Refresh, M3U8};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    const LIVE: &str = "#EXTM3U\n#EXTINF:6,\na.ts\n";
+
+    #[test]
+    /// Tests unchanged refreshes make the monitor stale and move it to a backup
+    fn it_switches_to_backup_when_stale() {
+        let mut monitor = Monitor::new("http://primary/live.m3u8")
+            .with_backup_uris(&["http://backup/live.m3u8"])
+            .with_max_stale_refreshes(1);
+
+        assert!(matches!(
+            monitor.update(LIVE.to_string()),
+            Ok(Refresh::Changed(_))
+        ));
+        assert!(matches!(
+            monitor.update(LIVE.to_string()),
+            Ok(Refresh::Unchanged)
+        ));
+        assert!(!monitor.is_stale());
+        monitor.update(LIVE.to_string()).unwrap();
+        assert!(monitor.is_stale());
+
+        assert!(monitor.switch_uri());
+        assert_eq!(monitor.uri(), "http://backup/live.m3u8");
+        assert!(!monitor.is_stale());
+    }
+
+    #[test]
+    /// Tests ended playlists never become stale
+    fn it_ignores_staleness_after_end_list() {
+        let ended = "#EXTM3U\n#EXTINF:6,\na.ts\n#EXT-X-ENDLIST\n";
+        let mut monitor = Monitor::new("http://primary/vod.m3u8").with_max_stale_refreshes(0);
+
+        monitor.update(ended.to_string()).unwrap();
+        monitor.update(ended.to_string()).unwrap();
+
+        assert!(!monitor.is_stale());
+        assert!(!monitor.switch_uri());
+    }
+
+    static PRIMARY_UP: AtomicBool = AtomicBool::new(false);
+
+    /// Fetcher where the primary origin is only up when PRIMARY_UP is set
+    fn fake_fetch(uri: &str) -> Result<String, ParseError> {
+        if uri.contains("primary") && !PRIMARY_UP.load(Ordering::SeqCst) {
+            return Err(ParseError::InvalidM3U8("primary down".to_string()));
+        }
+        Ok(format!("#EXTM3U\n#EXTINF:6,\n{}.ts\n", uri.len()))
+    }
+
+    #[test]
+    /// Tests fetch errors fail over to a backup and the primary is retried later
+    fn it_fails_over_and_back() {
+        let mut monitor = Monitor::new("http://primary/live.m3u8")
+            .with_backup_uris(&["http://backup/live.m3u8"])
+            .with_fail_back_after(1)
+            .with_fetcher(Arc::new(fake_fetch));
+
+        monitor.refresh().unwrap();
+        assert_eq!(monitor.served_by(), Some("http://backup/live.m3u8"));
+        monitor.refresh().unwrap();
+        assert_eq!(monitor.served_by(), Some("http://backup/live.m3u8"));
+
+        PRIMARY_UP.store(true, Ordering::SeqCst);
+        monitor.refresh().unwrap();
+        assert_eq!(monitor.served_by(), Some("http://primary/live.m3u8"));
+        assert_eq!(monitor.uri(), "http://primary/live.m3u8");
+    }
+
+    #[test]
+    /// Tests unchanged bodies are skipped by hash and changed ones are parsed
+    fn it_parses_only_when_changed() {
+        assert_eq!(M3U8::content_hash(""), 0xcbf29ce484222325);
+        assert_eq!(M3U8::content_hash("a"), 0xaf63dc4c8601ec8c);
+
+        let hash = match M3U8::parse_if_changed(LIVE, None).unwrap() {
+            ContentChange::Changed(m3u8, hash) => {
+                assert_eq!(m3u8.segments().len(), 1);
+                hash
+            }
+            ContentChange::Unchanged => panic!("expected a change"),
+        };
+        assert!(matches!(
+            M3U8::parse_if_changed(LIVE, Some(hash)),
+            Ok(ContentChange::Unchanged)
+        ));
+        let next = "#EXTM3U\n#EXTINF:6,\na.ts\n#EXTINF:6,\nb.ts\n";
+        assert!(matches!(
+            M3U8::parse_if_changed(next, Some(hash)),
+            Ok(ContentChange::Changed(_, _))
+        ));
+    }
+}
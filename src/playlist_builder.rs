@@ -0,0 +1,204 @@
+//! Constructing master and media playlists in code.
+use crate::{MasterPlaylist, MediaPlaylist, Rendition, Segment, VariantStream, M3U8};
+
+/// Builds a master playlist from typed variants and renditions
+#[derive(Debug, Clone, Default)]
+pub struct MasterPlaylistBuilder {
+    m3u8: M3U8,
+}
+
+/// Implementation for MasterPlaylistBuilder
+impl MasterPlaylistBuilder {
+    /// Empty master playlist
+    pub fn new() -> Self {
+        MasterPlaylistBuilder { m3u8: M3U8::new() }
+    }
+
+    /// EXT-X-VERSION to declare, raised on build if the playlist needs a later one
+    pub fn version(mut self, version: u8) -> Self {
+        self.m3u8.version = version;
+        self
+    }
+
+    /// Declares EXT-X-INDEPENDENT-SEGMENTS
+    pub fn independent_segments(mut self) -> Self {
+        self.m3u8.independent_segments = true;
+        self
+    }
+
+    /// Adds an EXT-X-STREAM-INF variant stream
+    pub fn variant(mut self, variant: VariantStream) -> Self {
+        self.m3u8.variant_streams_mut().push(variant.to_map());
+        self
+    }
+
+    /// Adds an EXT-X-MEDIA rendition
+    pub fn rendition(mut self, rendition: Rendition) -> Self {
+        self.m3u8.media_tags.push(rendition.to_map());
+        self
+    }
+
+    /// Returns the master playlist
+    pub fn build(self) -> MasterPlaylist {
+        let mut m3u8 = self.m3u8;
+        m3u8.version = m3u8.version.max(m3u8.required_version());
+        m3u8
+    }
+}
+
+/// Builds a media playlist segment by segment
+#[derive(Debug, Clone, Default)]
+pub struct MediaPlaylistBuilder {
+    m3u8: M3U8,
+    target_duration: Option<u64>,
+    discontinuity: bool,
+}
+
+/// Implementation for MediaPlaylistBuilder
+impl MediaPlaylistBuilder {
+    /// Empty media playlist
+    pub fn new() -> Self {
+        MediaPlaylistBuilder {
+            m3u8: M3U8::new(),
+            ..Default::default()
+        }
+    }
+
+    /// EXT-X-VERSION to declare, raised on build if the playlist needs a later one
+    pub fn version(mut self, version: u8) -> Self {
+        self.m3u8.version = version;
+        self
+    }
+
+    /// EXT-X-TARGETDURATION to declare, instead of the longest rounded segment duration
+    pub fn target_duration(mut self, seconds: u64) -> Self {
+        self.target_duration = Some(seconds);
+        self
+    }
+
+    /// EXT-X-MEDIA-SEQUENCE of the first segment
+    pub fn media_sequence(mut self, sequence: u64) -> Self {
+        self.m3u8.media_sequence = sequence;
+        self
+    }
+
+    /// Adds a segment with a duration in seconds
+    pub fn segment(self, duration: f64, uri: &str) -> Self {
+        self.add_segment(Segment {
+            duration,
+            uri: uri.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Adds a segment with its tags, such as keys, byte range or program date time
+    pub fn add_segment(mut self, mut segment: Segment) -> Self {
+        segment.discontinuity |= std::mem::take(&mut self.discontinuity);
+        self.m3u8.segments.push(segment);
+        self
+    }
+
+    /// Marks the next segment with EXT-X-DISCONTINUITY
+    pub fn discontinuity(mut self) -> Self {
+        self.discontinuity = true;
+        self
+    }
+
+    /// Ends the playlist with EXT-X-ENDLIST
+    pub fn end_list(mut self) -> Self {
+        self.m3u8.end_list = true;
+        self
+    }
+
+    /// Returns the media playlist, numbering segments from the media sequence
+    pub fn build(self) -> MediaPlaylist {
+        let mut m3u8 = self.m3u8;
+        m3u8.target_duration = Some(self.target_duration.unwrap_or_else(|| {
+            m3u8.segments
+                .iter()
+                .map(|segment| segment.duration.round() as u64)
+                .max()
+                .unwrap_or(0)
+        }));
+        let mut previous: Option<&Segment> = None;
+        for (index, segment) in m3u8.segments.iter_mut().enumerate() {
+            segment.sequence = m3u8.media_sequence + index as u64;
+            segment.date_time = segment.program_date_time.or_else(|| {
+                previous
+                    .filter(|_| !segment.discontinuity)
+                    .and_then(|previous| previous.end_date_time())
+            });
+            previous = Some(segment);
+        }
+        m3u8.version = m3u8.version.max(m3u8.required_version());
+        m3u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        MasterPlaylistBuilder, MediaPlaylistBuilder, MediaType, Rendition, Resolution,
+        VariantStream,
+    };
+
+    #[test]
+    /// Tests built playlists write out as valid HLS and parse back the same
+    fn it_builds_playlists() {
+        let media = MediaPlaylistBuilder::new()
+            .media_sequence(10)
+            .segment(6.0, "a.ts")
+            .segment(5.5, "b.ts")
+            .discontinuity()
+            .segment(4.0, "ad.ts")
+            .end_list()
+            .build();
+
+        assert_eq!(
+            media.to_string(),
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:6\n\
+             #EXT-X-MEDIA-SEQUENCE:10\n#EXTINF:6,\na.ts\n#EXTINF:5.5,\nb.ts\n\
+             #EXT-X-DISCONTINUITY\n#EXTINF:4,\nad.ts\n#EXT-X-ENDLIST\n"
+        );
+        assert_eq!(media.segments()[2].sequence, 12);
+
+        let master = MasterPlaylistBuilder::new()
+            .independent_segments()
+            .rendition(Rendition {
+                media_type: MediaType::Audio,
+                group_id: "aac".to_string(),
+                name: "English".to_string(),
+                language: Some("en".to_string()),
+                assoc_language: None,
+                uri: Some("en.m3u8".to_string()),
+                default: true,
+                autoselect: true,
+                forced: false,
+                instream_id: None,
+                characteristics: vec![],
+                channels: Some("2".to_string()),
+                extras: Default::default(),
+            })
+            .variant(VariantStream {
+                uri: "720p.m3u8".to_string(),
+                bandwidth: 2_500_000,
+                codecs: vec!["avc1.64001f".to_string(), "mp4a.40.2".to_string()],
+                resolution: Some(Resolution {
+                    width: 1280,
+                    height: 720,
+                }),
+                audio: Some("aac".to_string()),
+                ..Default::default()
+            })
+            .build();
+
+        let written = master.to_string();
+        assert!(written.contains(
+            "#EXT-X-STREAM-INF:AUDIO=\"aac\",BANDWIDTH=2500000,\
+             CODECS=\"avc1.64001f,mp4a.40.2\",RESOLUTION=1280x720\n720p.m3u8\n"
+        ));
+        let variant = &master.variants().unwrap()[0];
+        assert_eq!(variant.audio_renditions(&master).len(), 1);
+    }
+}
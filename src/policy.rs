@@ -0,0 +1,221 @@
+//! Declarative manifest manipulation rules loaded from JSON.
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{ParseError, SortOrder, M3U8};
+
+/// Ordered list of rules applied to a master playlist
+///
+/// ```json
+/// {"rules": [
+///   {"target": "variant", "match": {"CODECS": {"contains": "hvc1"}}, "action": "drop"},
+///   {"target": "variant", "action": {"replace": {"key": "uri", "from": "http:", "to": "https:"}}},
+///   {"target": "variant", "action": {"sort": {"key": "BANDWIDTH", "descending": true}}}
+/// ]}
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Policy {
+    pub rules: Vec<PolicyRule>,
+}
+
+/// One rule: the attribute lists it applies to and what to do with them
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PolicyRule {
+    pub target: PolicyTarget,
+    /// Conditions on attributes that must all hold, keyed by attribute name
+    #[serde(default, rename = "match")]
+    pub conditions: HashMap<String, Condition>,
+    pub action: PolicyAction,
+}
+
+/// Attribute lists a rule applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyTarget {
+    /// EXT-X-STREAM-INF, with the URI under the `uri` key
+    Variant,
+    /// EXT-X-MEDIA
+    Rendition,
+    /// EXT-X-I-FRAME-STREAM-INF
+    IframeStream,
+}
+
+/// Test on one attribute value
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Equals(String),
+    Contains(String),
+    Prefix(String),
+    /// Value is a number greater than this
+    Gt(f64),
+    /// Value is a number less than this
+    Lt(f64),
+    /// Attribute is present, or absent when false
+    Present(bool),
+}
+
+/// Change made to the matching attribute lists
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    Drop,
+    /// Sets attributes, replacing existing values
+    Set(HashMap<String, String>),
+    /// Removes attributes
+    Remove(Vec<String>),
+    /// Replaces text within one attribute value
+    Replace {
+        key: String,
+        from: String,
+        to: String,
+    },
+    /// Moves the matching entries, sorted by an attribute, ahead of the rest
+    Sort {
+        key: String,
+        #[serde(default)]
+        descending: bool,
+    },
+}
+
+/// Implementation for Condition
+impl Condition {
+    /// Whether an attribute value satisfies the condition
+    fn matches(&self, value: Option<&String>) -> bool {
+        let number = || value.and_then(|value| value.parse::<f64>().ok());
+        match self {
+            Condition::Equals(expected) => value == Some(expected),
+            Condition::Contains(text) => value.is_some_and(|value| value.contains(text.as_str())),
+            Condition::Prefix(text) => value.is_some_and(|value| value.starts_with(text.as_str())),
+            Condition::Gt(limit) => number().is_some_and(|number| number > *limit),
+            Condition::Lt(limit) => number().is_some_and(|number| number < *limit),
+            Condition::Present(present) => value.is_some() == *present,
+        }
+    }
+}
+
+/// Implementation for PolicyRule
+impl PolicyRule {
+    /// Whether an attribute list meets every condition of the rule
+    fn matches(&self, attributes: &HashMap<String, String>) -> bool {
+        self.conditions
+            .iter()
+            .all(|(key, condition)| condition.matches(attributes.get(key)))
+    }
+
+    /// Applies the rule to a list, returning how many entries it changed
+    fn apply(&self, list: &mut Vec<HashMap<String, String>>) -> usize {
+        let matched = list.iter().filter(|entry| self.matches(entry)).count();
+        match &self.action {
+            PolicyAction::Drop => list.retain(|entry| !self.matches(entry)),
+            PolicyAction::Set(values) => {
+                for entry in list.iter_mut().filter(|entry| self.matches(entry)) {
+                    entry.extend(values.clone());
+                }
+            }
+            PolicyAction::Remove(keys) => {
+                for entry in list.iter_mut().filter(|entry| self.matches(entry)) {
+                    entry.retain(|key, _| !keys.contains(key));
+                }
+            }
+            PolicyAction::Replace { key, from, to } => {
+                for entry in list.iter_mut().filter(|entry| self.matches(entry)) {
+                    if let Some(value) = entry.get_mut(key) {
+                        *value = value.replace(from.as_str(), to);
+                    }
+                }
+            }
+            PolicyAction::Sort { key, descending } => {
+                let (mut sorted, rest): (Vec<_>, Vec<_>) =
+                    list.drain(..).partition(|entry| self.matches(entry));
+                let order = if *descending {
+                    SortOrder::Desc
+                } else {
+                    SortOrder::Asc
+                };
+                M3U8::sort_list_by_keys(&mut sorted, &[(key.as_str(), order)]);
+                list.extend(sorted);
+                list.extend(rest);
+            }
+        }
+        matched
+    }
+}
+
+/// Implementation for Policy
+impl Policy {
+    /// Deserializes a policy from JSON
+    pub fn from_json(body: &str) -> Result<Policy, ParseError> {
+        Ok(serde_json::from_str(body)?)
+    }
+}
+
+/// Implementation of policy application for M3U8
+impl M3U8 {
+    /// Applies each rule of a policy in order, returning how many entries matched
+    pub fn apply_policy(&mut self, policy: &Policy) -> usize {
+        policy
+            .rules
+            .iter()
+            .map(|rule| match rule.target {
+                PolicyTarget::Variant => rule.apply(self.variant_streams_mut()),
+                PolicyTarget::Rendition => rule.apply(&mut self.media_tags),
+                PolicyTarget::IframeStream => rule.apply(&mut self.media_resources),
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ParseError, Policy, M3U8};
+
+    #[test]
+    /// Tests configured rules drop, rewrite and reorder matching entries
+    fn it_applies_policies() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"French\",LANGUAGE=\"fr\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,CODECS=\"avc1.4d401e\"\nhttp://cdn/low.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=3000000,CODECS=\"hvc1.2.4.L123.B0\"\nhttp://cdn/hevc.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000,CODECS=\"avc1.640028\"\nhttp://cdn/high.m3u8\n";
+        let mut parsed = M3U8::from_body(body).unwrap();
+        let policy = Policy::from_json(
+            r#"{"rules": [
+                {"target": "variant", "match": {"CODECS": {"prefix": "hvc1"}}, "action": "drop"},
+                {"target": "variant", "action": {"replace": {"key": "uri", "from": "http:", "to": "https:"}}},
+                {"target": "variant", "match": {"BANDWIDTH": {"gt": 1000000}},
+                 "action": {"sort": {"key": "BANDWIDTH", "descending": true}}},
+                {"target": "rendition", "match": {"LANGUAGE": {"equals": "en"}},
+                 "action": {"set": {"DEFAULT": "YES"}}}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.select_variant(4_000_000).unwrap().uri,
+            "http://cdn/hevc.m3u8"
+        );
+        assert_eq!(parsed.apply_policy(&policy), 5);
+        assert_eq!(
+            parsed.select_variant(4_000_000).unwrap().uri,
+            "https://cdn/low.m3u8"
+        );
+
+        let uris: Vec<&str> = parsed
+            .variant_streams()
+            .iter()
+            .map(|variant| variant["uri"].as_str())
+            .collect();
+        assert_eq!(uris, vec!["https://cdn/high.m3u8", "https://cdn/low.m3u8"]);
+        assert_eq!(parsed.media_tags()[1]["DEFAULT"], "YES");
+        assert!(!parsed.media_tags()[0].contains_key("DEFAULT"));
+
+        assert!(matches!(
+            Policy::from_json(r#"{"rules": [{"target": "segment", "action": "drop"}]}"#),
+            Err(ParseError::JsonError(_))
+        ));
+    }
+}
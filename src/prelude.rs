@@ -0,0 +1,7 @@
+//! Common types for glob import with `use m3u8parse::prelude::*`.
+pub use crate::{
+    AttributeError, Codec, CodecError, FetchOptions, MasterPlaylist, MasterPlaylistBuilder,
+    MediaPlaylist, MediaPlaylistBuilder, MediaType, ParseError, ParseWarning, ParserBuilder,
+    PartialSegment, Playlist, PlaylistKind, Rendition, Resolution, Segment, SortOrder,
+    VariantStream, M3U8,
+};
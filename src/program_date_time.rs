@@ -0,0 +1,154 @@
+//! Generating and repairing EXT-X-PROGRAM-DATE-TIME tags.
+use chrono::{DateTime, Duration, FixedOffset, TimeZone};
+
+use crate::segment::seconds_to_duration;
+use crate::M3U8;
+
+/// Which segments get an EXT-X-PROGRAM-DATE-TIME tag when generating them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdtInterval {
+    EverySegment,
+    /// The first segment and every segment starting a discontinuity
+    EveryDiscontinuity,
+}
+
+/// Implementation of program date time generation for M3U8
+impl M3U8 {
+    /// Tags segments with wall-clock times counted from `start` by segment duration
+    ///
+    /// Existing EXT-X-PROGRAM-DATE-TIME values are replaced or, for segments the
+    /// interval skips, removed.
+    pub fn insert_program_date_times(
+        &mut self,
+        start: DateTime<FixedOffset>,
+        interval: PdtInterval,
+    ) {
+        let mut date_time = Some(start);
+        for (index, segment) in self.segments.iter_mut().enumerate() {
+            let tagged = match interval {
+                PdtInterval::EverySegment => true,
+                PdtInterval::EveryDiscontinuity => index == 0 || segment.discontinuity,
+            };
+            segment.program_date_time = date_time.filter(|_| tagged);
+            segment.date_time = date_time;
+            date_time = date_time.and_then(|date_time| {
+                date_time.checked_add_signed(seconds_to_duration(segment.duration)?)
+            });
+        }
+    }
+
+    /// Rewrites program date times that drift more than `tolerance` seconds from the
+    /// end of the previous segment, returning how many were repaired
+    ///
+    /// A program date time on a discontinuity starts a new timeline and is kept.
+    pub fn repair_program_date_times(&mut self, tolerance: f64) -> usize {
+        let mut repaired = 0;
+        let mut expected: Option<DateTime<FixedOffset>> = None;
+        for segment in &mut self.segments {
+            if segment.discontinuity {
+                expected = None;
+            }
+            if let (Some(date_time), Some(expected)) = (segment.program_date_time, expected) {
+                let drift = (date_time - expected)
+                    .num_microseconds()
+                    .unwrap_or(i64::MAX);
+                if drift.unsigned_abs() as f64 > tolerance * 1_000_000.0 {
+                    segment.program_date_time = Some(expected);
+                    repaired += 1;
+                }
+            }
+            segment.date_time = segment.program_date_time.or(expected);
+            expected = segment.end_date_time();
+        }
+        repaired
+    }
+
+    /// Returns how long before `now` the newest segment finished, by its wall-clock time
+    ///
+    /// For a live playlist fetched at `now` this is the origin's publish latency. It is
+    /// negative when the origin clock runs ahead of `now`.
+    pub fn publish_latency<Tz: TimeZone>(&self, now: DateTime<Tz>) -> Option<Duration> {
+        let newest = self.segments.last()?.end_date_time()?;
+        Some(now.fixed_offset() - newest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use chrono::{DateTime, Duration};
+
+    use crate::{PdtInterval, M3U8};
+
+    const BODY: &str = "#EXTM3U\n\
+        #EXTINF:4,\na.ts\n#EXTINF:4,\nb.ts\n\
+        #EXT-X-DISCONTINUITY\n#EXTINF:4,\nc.ts\n";
+
+    #[test]
+    /// Tests program date times are generated at the requested interval
+    fn it_inserts_program_date_times() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        let mut parsed = M3U8::from_body(BODY).unwrap();
+
+        parsed.insert_program_date_times(start, PdtInterval::EveryDiscontinuity);
+
+        let tagged: Vec<bool> = parsed
+            .segments()
+            .iter()
+            .map(|segment| segment.program_date_time.is_some())
+            .collect();
+        assert_eq!(tagged, vec![true, false, true]);
+        assert_eq!(
+            parsed.segments()[2].program_date_time.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:08+00:00"
+        );
+        let written = parsed.to_string();
+        assert_eq!(written.matches("#EXT-X-PROGRAM-DATE-TIME").count(), 2);
+
+        parsed.insert_program_date_times(start, PdtInterval::EverySegment);
+        assert!(parsed
+            .segments()
+            .iter()
+            .all(|segment| segment.program_date_time.is_some()));
+    }
+
+    #[test]
+    /// Tests drifting program date times are repaired but discontinuities are kept
+    fn it_repairs_drifting_program_date_times() {
+        let body = "#EXTM3U\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-01-01T00:00:00Z\n#EXTINF:4,\na.ts\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-01-01T00:00:04.010Z\n#EXTINF:4,\nb.ts\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-01-01T00:00:09Z\n#EXTINF:4,\nc.ts\n\
+            #EXT-X-DISCONTINUITY\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-01-01T01:00:00Z\n#EXTINF:4,\nd.ts\n";
+        let mut parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(parsed.repair_program_date_times(0.1), 1);
+
+        let segments = parsed.segments();
+        assert_eq!(
+            segments[2].program_date_time.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:08.010+00:00"
+        );
+        assert_eq!(
+            segments[3].program_date_time.unwrap().to_rfc3339(),
+            "2024-01-01T01:00:00+00:00"
+        );
+    }
+
+    #[test]
+    /// Tests publish latency is measured from the end of the newest segment
+    fn it_measures_publish_latency() {
+        let body = "#EXTM3U\n\
+            #EXT-X-PROGRAM-DATE-TIME:2024-01-01T00:00:00Z\n#EXTINF:4,\na.ts\n\
+            #EXTINF:4.5,\nb.ts\n";
+        let parsed = M3U8::from_body(body).unwrap();
+        let now = DateTime::parse_from_rfc3339("2024-01-01T01:00:10+01:00").unwrap();
+
+        assert_eq!(
+            parsed.publish_latency(now),
+            Some(Duration::milliseconds(1_500))
+        );
+        assert_eq!(M3U8::from_body(BODY).unwrap().publish_latency(now), None);
+    }
+}
@@ -0,0 +1,141 @@
+//! Fluent filtering of variant streams.
+use crate::{HdcpLevel, VariantStream, M3U8};
+
+/// Predicate applied to each variant of a query
+type Predicate<'a> = Box<dyn Fn(&VariantStream) -> bool + 'a>;
+
+/// Variant streams filtered by chained conditions, built with `M3U8::variants_query`
+///
+/// Variants whose attributes cannot be parsed never match.
+pub struct VariantQuery<'a> {
+    playlist: &'a M3U8,
+    predicates: Vec<Predicate<'a>>,
+}
+
+/// Implementation for VariantQuery
+impl<'a> VariantQuery<'a> {
+    /// Keeps variants matching an arbitrary condition
+    pub fn filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&VariantStream) -> bool + 'a,
+    {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Keeps variants with at least this BANDWIDTH
+    pub fn min_bandwidth(self, bandwidth: u64) -> Self {
+        self.filter(move |variant| variant.bandwidth >= bandwidth)
+    }
+
+    /// Keeps variants with at most this BANDWIDTH
+    pub fn max_bandwidth(self, bandwidth: u64) -> Self {
+        self.filter(move |variant| variant.bandwidth <= bandwidth)
+    }
+
+    /// Keeps variants with a CODECS entry containing `text`, such as `avc1`
+    pub fn codec_contains(self, text: &'a str) -> Self {
+        self.filter(move |variant| variant.codecs.iter().any(|codec| codec.contains(text)))
+    }
+
+    /// Keeps variants whose RESOLUTION fits within `width` by `height`
+    pub fn max_resolution(self, width: u32, height: u32) -> Self {
+        self.filter(move |variant| {
+            variant
+                .resolution
+                .is_some_and(|resolution| resolution.width <= width && resolution.height <= height)
+        })
+    }
+
+    /// Keeps HDR variants, with a VIDEO-RANGE of PQ or HLG, or only SDR ones
+    pub fn hdr(self, hdr: bool) -> Self {
+        self.filter(move |variant| variant.is_hdr() == hdr)
+    }
+
+    /// Keeps variants whose HDCP-LEVEL a device supporting `level` can play
+    pub fn max_hdcp_level(self, level: HdcpLevel) -> Self {
+        self.filter(move |variant| variant.hdcp_level.unwrap_or(HdcpLevel::None) <= level)
+    }
+
+    /// Keeps variants using an AUDIO group
+    pub fn audio_group(self, group_id: &'a str) -> Self {
+        self.filter(move |variant| variant.audio.as_deref() == Some(group_id))
+    }
+
+    /// Returns the matching variants in playlist order
+    pub fn collect(self) -> Vec<VariantStream> {
+        self.playlist
+            .iter_variants()
+            .filter_map(Result::ok)
+            .filter(|variant| self.predicates.iter().all(|predicate| predicate(variant)))
+            .collect()
+    }
+}
+
+/// Implementation of variant queries for M3U8
+impl M3U8 {
+    /// Starts a query over the variant streams, matching all of them until narrowed
+    pub fn variants_query(&self) -> VariantQuery<'_> {
+        VariantQuery {
+            playlist: self,
+            predicates: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{HdcpLevel, M3U8};
+
+    #[test]
+    /// Tests chained conditions narrow the variants together
+    fn it_queries_variants() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,CODECS=\"avc1.4d401e\",RESOLUTION=640x360\n\
+            low.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000,CODECS=\"avc1.640028\",RESOLUTION=1920x1080\n\
+            high.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=6000000,CODECS=\"hvc1.2.4.L123.B0\",\
+            RESOLUTION=1920x1080,VIDEO-RANGE=PQ,HDCP-LEVEL=TYPE-1\nhdr.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let uris = |variants: Vec<crate::VariantStream>| -> Vec<String> {
+            variants.into_iter().map(|variant| variant.uri).collect()
+        };
+        assert_eq!(
+            uris(
+                parsed
+                    .variants_query()
+                    .min_bandwidth(1_000_000)
+                    .codec_contains("avc1")
+                    .hdr(false)
+                    .collect()
+            ),
+            vec!["high.m3u8"]
+        );
+        assert_eq!(
+            uris(parsed.variants_query().hdr(true).collect()),
+            vec!["hdr.m3u8"]
+        );
+        assert_eq!(
+            uris(
+                parsed
+                    .variants_query()
+                    .max_resolution(1280, 720)
+                    .filter(|variant| variant.audio.is_none())
+                    .collect()
+            ),
+            vec!["low.m3u8"]
+        );
+        assert_eq!(parsed.variants_query().collect().len(), 3);
+        assert_eq!(
+            parsed
+                .variants_query()
+                .max_hdcp_level(HdcpLevel::Type0)
+                .collect()
+                .len(),
+            2
+        );
+    }
+}
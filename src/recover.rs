@@ -0,0 +1,212 @@
+//! Recovering lines from playlists with missing or unusual line breaks.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{TagTypes, M3U8};
+
+/// A defect in the playlist body that the parser worked around
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseWarning {
+    /// Lines were separated by bare carriage returns
+    CarriageReturnSeparators,
+    /// Several tags were packed onto one line and split apart
+    PackedLine { line_number: usize, tags: usize },
+    /// A variant stream was dropped because its URI could not be separated from its
+    /// packed tag
+    UnseparatedUri { line_number: usize },
+    /// A quoted-string attribute, such as URI, was given without quotes
+    UnquotedAttribute { key: String },
+}
+
+/// ParseWarning as a Display type, describing the defect
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarning::CarriageReturnSeparators => {
+                write!(f, "lines are separated by bare carriage returns")
+            }
+            ParseWarning::PackedLine { line_number, tags } => {
+                write!(f, "line {} packs {} tags together", line_number, tags)
+            }
+            ParseWarning::UnseparatedUri { line_number } => {
+                write!(
+                    f,
+                    "line {} has a variant URI joined to its tag",
+                    line_number
+                )
+            }
+            ParseWarning::UnquotedAttribute { key } => {
+                write!(f, "{} attribute value is not quoted", key)
+            }
+        }
+    }
+}
+
+/// Splits on LF, CRLF and bare CR line breaks
+fn split_breaks(body: &str, warnings: &mut Vec<ParseWarning>) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\r' | '\n' => {
+                if c == '\r' && !warnings.contains(&ParseWarning::CarriageReturnSeparators) {
+                    warnings.push(ParseWarning::CarriageReturnSeparators);
+                }
+                lines.push(std::mem::take(&mut line));
+            }
+            _ => line.push(c),
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Splits a tag line before every `#EXT` outside a quoted string
+fn split_packed(line: &str) -> Vec<&str> {
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut in_quotes = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if index > start && !in_quotes && line[index..].starts_with("#EXT") => {
+                pieces.push(&line[start..index]);
+                start = index;
+            }
+            _ => (),
+        }
+    }
+    pieces.push(&line[start..]);
+    pieces
+}
+
+/// Separates the URI trailing a packed tag, if it can be told apart
+///
+/// An EXTINF title ends at the last whitespace, or is taken as the URI when it has no
+/// whitespace. Other tags only give up a URI after whitespace outside quotes.
+fn split_uri(piece: &str) -> (&str, Option<&str>) {
+    let (tag, data) = M3U8::by_value(piece);
+    match TagTypes::from_str(tag) {
+        Ok(TagTypes::ExtInf) => match data.split_once(',') {
+            Some((_, title)) if !title.trim().is_empty() => {
+                let uri_start = piece.len() - title.trim_start().len();
+                match piece.trim_end().rfind(char::is_whitespace) {
+                    Some(space) if space >= uri_start => {
+                        (piece[..space].trim_end(), Some(piece[space..].trim()))
+                    }
+                    _ => (&piece[..uri_start], Some(piece[uri_start..].trim())),
+                }
+            }
+            _ => (piece, None),
+        },
+        _ => {
+            let mut in_quotes = false;
+            let mut space = None;
+            for (index, c) in piece.trim_end().char_indices() {
+                match c {
+                    '"' => in_quotes = !in_quotes,
+                    c if c.is_whitespace() && !in_quotes => space = Some(index),
+                    _ => (),
+                }
+            }
+            match space {
+                Some(space) => (piece[..space].trim_end(), Some(piece[space..].trim())),
+                None => (piece, None),
+            }
+        }
+    }
+}
+
+/// Implementation of tolerant line splitting for M3U8
+impl M3U8 {
+    /// Splits a body into lines, unpacking tags that share a line
+    pub(crate) fn split_lines(body: &str) -> (Vec<String>, Vec<ParseWarning>) {
+        let mut warnings = vec![];
+        let mut lines = vec![];
+        for (index, line) in split_breaks(body, &mut warnings).into_iter().enumerate() {
+            let pieces = if line.starts_with("#EXT") {
+                split_packed(&line)
+            } else {
+                vec![line.as_str()]
+            };
+            if pieces.len() == 1 {
+                lines.push(line.to_string());
+                continue;
+            }
+            warnings.push(ParseWarning::PackedLine {
+                line_number: index + 1,
+                tags: pieces.len(),
+            });
+            for piece in pieces {
+                let (tag, uri) = split_uri(piece);
+                let variant =
+                    TagTypes::from_str(M3U8::by_value(tag).0) == Ok(TagTypes::ExtXStreamInf);
+                match uri {
+                    Some(uri) => {
+                        lines.push(tag.to_string());
+                        lines.push(uri.to_string());
+                    }
+                    None if variant => warnings.push(ParseWarning::UnseparatedUri {
+                        line_number: index + 1,
+                    }),
+                    None => lines.push(tag.to_string()),
+                }
+            }
+        }
+        (lines, warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ParseWarning, M3U8};
+
+    #[test]
+    /// Tests single-line and carriage-return playlists are split back into lines
+    fn it_recovers_packed_lines() {
+        let body = "#EXTM3U#EXT-X-VERSION:3#EXT-X-TARGETDURATION:10\
+            #EXTINF:10,seg1.ts#EXTINF:9.5,Intro seg2.ts#EXT-X-ENDLIST";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let uris: Vec<&str> = parsed
+            .segments()
+            .iter()
+            .map(|segment| segment.uri.as_str())
+            .collect();
+        assert_eq!(uris, vec!["seg1.ts", "seg2.ts"]);
+        assert_eq!(parsed.segments()[1].title, "Intro");
+        assert!(parsed.end_list());
+        assert_eq!(
+            parsed.warnings(),
+            &[ParseWarning::PackedLine {
+                line_number: 1,
+                tags: 6
+            }]
+        );
+
+        let parsed = M3U8::from_body("#EXTM3U\r#EXTINF:4,\ra.ts\r#EXT-X-ENDLIST\r").unwrap();
+        assert_eq!(parsed.segments()[0].uri, "a.ts");
+        assert_eq!(parsed.warnings(), &[ParseWarning::CarriageReturnSeparators]);
+
+        let parsed = M3U8::from_body(
+            "#EXTM3U#EXT-X-STREAM-INF:BANDWIDTH=1,CODECS=\"a b\" low.m3u8\
+             #EXT-X-STREAM-INF:BANDWIDTH=2high.m3u8\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.variant_streams.len(), 1);
+        assert_eq!(parsed.variant_streams[0]["uri"], "low.m3u8");
+        assert_eq!(
+            parsed.warnings()[1],
+            ParseWarning::UnseparatedUri { line_number: 1 }
+        );
+
+        let parsed = M3U8::from_body("#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+        assert!(parsed.warnings().is_empty());
+    }
+}
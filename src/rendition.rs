@@ -0,0 +1,241 @@
+//! Typed EXT-X-MEDIA renditions.
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::{AttributeError, MediaTag, MediaType, VariantStream, M3U8};
+
+/// Attribute keys mapped onto typed Rendition fields
+const TYPED_KEYS: &[&str] = &[
+    "TYPE",
+    "GROUP-ID",
+    "NAME",
+    "LANGUAGE",
+    "ASSOC-LANGUAGE",
+    "URI",
+    "DEFAULT",
+    "AUTOSELECT",
+    "FORCED",
+    "INSTREAM-ID",
+    "CHARACTERISTICS",
+    "CHANNELS",
+];
+
+/// An alternative rendition from EXT-X-MEDIA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendition {
+    pub media_type: MediaType,
+    pub group_id: String,
+    pub name: String,
+    pub language: Option<String>,
+    pub assoc_language: Option<String>,
+    /// Media playlist of the rendition, absent when it is carried in the variant
+    pub uri: Option<String>,
+    pub default: bool,
+    pub autoselect: bool,
+    /// Subtitles that must be shown, such as translations of foreign dialogue
+    pub forced: bool,
+    pub instream_id: Option<String>,
+    /// Uniform Type Identifiers from CHARACTERISTICS, such as
+    /// `public.accessibility.describes-music-and-sound`
+    pub characteristics: Vec<String>,
+    pub channels: Option<String>,
+    /// Attributes without a typed field, keyed by attribute name
+    pub extras: HashMap<String, String>,
+}
+
+/// Implementation for Rendition
+impl Rendition {
+    /// Converts an attribute map, as returned by `get_media_tags`
+    pub fn from_map(map: &HashMap<String, String>) -> Result<Rendition, AttributeError> {
+        let required = |key: &str| {
+            map.get(key)
+                .cloned()
+                .ok_or_else(|| AttributeError::Missing(key.to_string()))
+        };
+        let yes_no = |key: &str| match map.get(key).map(|value| value.as_str()) {
+            None | Some("NO") => Ok(false),
+            Some("YES") => Ok(true),
+            Some(value) => Err(AttributeError::Invalid {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+        };
+        let media_type = required("TYPE")?;
+        Ok(Rendition {
+            media_type: MediaType::from_str(&media_type).map_err(|_| AttributeError::Invalid {
+                key: "TYPE".to_string(),
+                value: media_type.to_string(),
+            })?,
+            group_id: required("GROUP-ID")?,
+            name: required("NAME")?,
+            language: map.get("LANGUAGE").cloned(),
+            assoc_language: map.get("ASSOC-LANGUAGE").cloned(),
+            uri: map.get("URI").cloned(),
+            default: yes_no("DEFAULT")?,
+            autoselect: yes_no("AUTOSELECT")?,
+            forced: yes_no("FORCED")?,
+            instream_id: map.get("INSTREAM-ID").cloned(),
+            characteristics: map
+                .get("CHARACTERISTICS")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|characteristic| characteristic.trim().to_string())
+                        .filter(|characteristic| !characteristic.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            channels: map.get("CHANNELS").cloned(),
+            extras: map
+                .iter()
+                .filter(|(key, _)| !TYPED_KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        })
+    }
+
+    /// Whether CHARACTERISTICS lists the Uniform Type Identifier `characteristic`
+    pub fn has_characteristic(&self, characteristic: &str) -> bool {
+        self.characteristics
+            .iter()
+            .any(|value| value == characteristic)
+    }
+
+    /// Converts back to an attribute map, including extras
+    ///
+    /// DEFAULT, AUTOSELECT and FORCED are only written when YES.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        let mut map = self.extras.clone();
+        map.insert("TYPE".to_string(), self.media_type.to_string());
+        map.insert("GROUP-ID".to_string(), self.group_id.to_string());
+        map.insert("NAME".to_string(), self.name.to_string());
+        let optional = [
+            ("LANGUAGE", self.language.clone()),
+            ("ASSOC-LANGUAGE", self.assoc_language.clone()),
+            ("URI", self.uri.clone()),
+            ("DEFAULT", self.default.then(|| "YES".to_string())),
+            ("AUTOSELECT", self.autoselect.then(|| "YES".to_string())),
+            ("FORCED", self.forced.then(|| "YES".to_string())),
+            ("INSTREAM-ID", self.instream_id.clone()),
+            (
+                "CHARACTERISTICS",
+                (!self.characteristics.is_empty()).then(|| self.characteristics.join(",")),
+            ),
+            ("CHANNELS", self.channels.clone()),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                map.insert(key.to_string(), value);
+            }
+        }
+        map
+    }
+}
+
+/// Implementation of typed rendition access for M3U8
+impl M3U8 {
+    /// Returns the EXT-X-MEDIA renditions as typed values, in playlist order
+    pub fn renditions(&self) -> Result<Vec<Rendition>, AttributeError> {
+        self.iter_renditions().collect()
+    }
+
+    /// Converts renditions one at a time, in playlist order
+    pub fn iter_renditions(&self) -> impl Iterator<Item = Result<Rendition, AttributeError>> + '_ {
+        self.media_tags.iter().map(Rendition::from_map)
+    }
+}
+
+/// Implementation of rendition group lookups for VariantStream
+impl VariantStream {
+    /// Returns the media tags of the group this variant names for a media type
+    ///
+    /// CLOSED-CAPTIONS=NONE names no group and returns nothing.
+    pub fn renditions<'a>(&self, playlist: &'a M3U8, media_type: MediaType) -> Vec<&'a MediaTag> {
+        let group_id = match media_type {
+            MediaType::Audio => self.audio.as_deref(),
+            MediaType::Video => self.video.as_deref(),
+            MediaType::Subtitles => self.subtitles.as_deref(),
+            MediaType::ClosedCaptions => self
+                .closed_captions
+                .as_ref()
+                .and_then(|captions| captions.group_id()),
+        };
+        match group_id {
+            Some(group_id) => playlist.group(media_type, group_id),
+            None => vec![],
+        }
+    }
+
+    /// Returns the audio renditions of the variant's AUDIO group
+    pub fn audio_renditions<'a>(&self, playlist: &'a M3U8) -> Vec<&'a MediaTag> {
+        self.renditions(playlist, MediaType::Audio)
+    }
+
+    /// Returns the subtitle renditions of the variant's SUBTITLES group
+    pub fn subtitle_renditions<'a>(&self, playlist: &'a M3U8) -> Vec<&'a MediaTag> {
+        self.renditions(playlist, MediaType::Subtitles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{AttributeError, MediaType, M3U8};
+
+    #[test]
+    /// Tests EXT-X-MEDIA tags convert to typed renditions and back
+    fn it_returns_typed_renditions() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",\
+            DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"en.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID=\"cc\",NAME=\"CC1\",\
+            INSTREAM-ID=\"CC1\",DEFAULT=NO,STABLE-RENDITION-ID=\"cc1\"\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let renditions = parsed.renditions().unwrap();
+
+        assert_eq!(renditions[0].media_type, MediaType::Audio);
+        assert_eq!(renditions[0].language.as_deref(), Some("en"));
+        assert!(renditions[0].default && renditions[0].autoselect && !renditions[0].forced);
+        assert_eq!(renditions[0].uri.as_deref(), Some("en.m3u8"));
+        assert_eq!(renditions[1].media_type, MediaType::ClosedCaptions);
+        assert_eq!(renditions[1].uri, None);
+        assert_eq!(renditions[1].extras["STABLE-RENDITION-ID"], "cc1");
+        assert_eq!(renditions[0].to_map(), parsed.get_media_tags_sorted(&[])[0]);
+
+        let invalid = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"x\",FORCED=MAYBE\n",
+        )
+        .unwrap();
+        assert_eq!(
+            invalid.renditions(),
+            Err(AttributeError::Invalid {
+                key: "FORCED".to_string(),
+                value: "MAYBE".to_string()
+            })
+        );
+    }
+
+    #[test]
+    /// Tests variants resolve the EXT-X-MEDIA entries of the groups they reference
+    fn it_resolves_variant_groups() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"French\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"ec3\",NAME=\"English\"\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"aac\",NAME=\"English\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1,AUDIO=\"aac\",CLOSED-CAPTIONS=NONE\nv.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+        let variant = &parsed.variants().unwrap()[0];
+
+        let audio = variant.audio_renditions(&parsed);
+
+        assert_eq!(audio.len(), 2);
+        assert_eq!(audio[1]["NAME"], "French");
+        assert!(variant.subtitle_renditions(&parsed).is_empty());
+        assert!(variant
+            .renditions(&parsed, MediaType::ClosedCaptions)
+            .is_empty());
+        assert_eq!(parsed.group(MediaType::Subtitles, "aac").len(), 1);
+    }
+}
@@ -0,0 +1,127 @@
+//! Automatic repair of common playlist defects.
+use std::fmt;
+
+use crate::{ParseWarning, PlaylistType, M3U8};
+
+/// A fix applied by `M3U8::repair`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Repair {
+    /// EXT-X-ENDLIST was added to a VOD playlist that lacked it
+    AddedEndList,
+    /// Unquoted values of a quoted-string attribute are written quoted
+    QuotedAttribute(String),
+    /// EXT-X-VERSION was raised to cover the features in use
+    Version { from: u8, to: u8 },
+    /// AVERAGE-BANDWIDTH was filled in from BANDWIDTH for a variant
+    AverageBandwidth { uri: String },
+}
+
+/// Repair as a Display type, describing the change
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Repair::AddedEndList => write!(f, "added EXT-X-ENDLIST to VOD playlist"),
+            Repair::QuotedAttribute(key) => write!(f, "quoted {} attribute values", key),
+            Repair::Version { from, to } => {
+                write!(f, "raised EXT-X-VERSION from {} to {}", from, to)
+            }
+            Repair::AverageBandwidth { uri } => {
+                write!(f, "set AVERAGE-BANDWIDTH from BANDWIDTH for {}", uri)
+            }
+        }
+    }
+}
+
+/// Implementation of playlist repair for M3U8
+impl M3U8 {
+    /// Applies safe fixes for common defects, returning what was changed
+    ///
+    /// A playlist with EXT-X-PLAYLIST-TYPE:VOD gets EXT-X-ENDLIST, unquoted
+    /// quoted-string attributes found while parsing are written quoted, EXT-X-VERSION
+    /// is raised when features need a later version and variants without
+    /// AVERAGE-BANDWIDTH take their BANDWIDTH. Repairing twice changes nothing more.
+    pub fn repair(&mut self) -> Vec<Repair> {
+        let mut repairs = vec![];
+
+        if self.playlist_type == Some(PlaylistType::Vod) && !self.end_list {
+            self.end_list = true;
+            repairs.push(Repair::AddedEndList);
+        }
+
+        // The writer always quotes these, so reporting them is the fix
+        self.warnings.retain(|warning| match warning {
+            ParseWarning::UnquotedAttribute { key } => {
+                repairs.push(Repair::QuotedAttribute(key.to_string()));
+                false
+            }
+            _ => true,
+        });
+
+        let needed = self.required_version();
+        if self.version < needed {
+            repairs.push(Repair::Version {
+                from: self.version,
+                to: needed,
+            });
+            self.version = needed;
+        }
+
+        for variant in self.variant_streams_mut() {
+            if variant.contains_key("AVERAGE-BANDWIDTH") {
+                continue;
+            }
+            if let Some(bandwidth) = variant.get("BANDWIDTH").cloned() {
+                variant.insert("AVERAGE-BANDWIDTH".to_string(), bandwidth);
+                repairs.push(Repair::AverageBandwidth {
+                    uri: variant.get("uri").cloned().unwrap_or_default(),
+                });
+            }
+        }
+        repairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{Repair, M3U8};
+
+    #[test]
+    /// Tests common defects are fixed once and logged
+    fn it_repairs_common_defects() {
+        let body = "#EXTM3U\n#EXT-X-VERSION:2\n#EXT-X-PLAYLIST-TYPE:VOD\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=key.bin\n\
+            #EXTINF:4.5,\na.ts\n";
+        let mut parsed = M3U8::from_body(body).unwrap();
+
+        let repairs = parsed.repair();
+
+        assert_eq!(
+            repairs,
+            vec![
+                Repair::AddedEndList,
+                Repair::QuotedAttribute("URI".to_string()),
+                Repair::Version { from: 2, to: 3 },
+            ]
+        );
+        let written = parsed.to_string();
+        assert!(written.contains("#EXT-X-VERSION:3\n"));
+        assert!(written.contains("URI=\"key.bin\""));
+        assert!(written.ends_with("#EXT-X-ENDLIST\n"));
+        assert!(parsed.repair().is_empty());
+
+        let mut master = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=800000\nlow.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=5000000,AVERAGE-BANDWIDTH=4000000\nhigh.m3u8\n",
+        )
+        .unwrap();
+        assert_eq!(
+            master.repair(),
+            vec![Repair::AverageBandwidth {
+                uri: "low.m3u8".to_string()
+            }]
+        );
+        assert_eq!(master.variant_streams[0]["AVERAGE-BANDWIDTH"], "800000");
+    }
+}
@@ -0,0 +1,242 @@
+//! Media segments parsed from media playlists.
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::tags::{required, yes_no};
+use crate::{AdCue, EncryptionKey, ParseError, Tiles, M3U8};
+
+/// Resolved EXT-X-BYTERANGE sub-range of a segment resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub length: u64,
+    pub offset: u64,
+}
+
+/// Implementation for ByteRange
+impl ByteRange {
+    /// Parses `<n>[@<o>]`, returning the length and the offset if one was given
+    pub(crate) fn parse(data: &str) -> Option<(u64, Option<u64>)> {
+        match data.trim().split_once('@') {
+            Some((length, offset)) => Some((length.parse().ok()?, Some(offset.parse().ok()?))),
+            None => Some((data.trim().parse().ok()?, None)),
+        }
+    }
+
+    /// First byte after this sub-range, or None if it lies past u64::MAX
+    pub fn end(&self) -> Option<u64> {
+        self.offset.checked_add(self.length)
+    }
+}
+
+/// ByteRange as a Display type, always writing the resolved offset
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@{}", self.length, self.offset)
+    }
+}
+
+/// Media initialization section from EXT-X-MAP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitSection {
+    pub uri: String,
+    /// Sub-range of the resource holding the section
+    pub byte_range: Option<ByteRange>,
+}
+
+/// Implementation for InitSection
+impl InitSection {
+    /// Builds an initialization section from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<InitSection, ParseError> {
+        let uri = attributes.get("URI").ok_or_else(|| {
+            ParseError::InvalidM3U8("Missing #EXT-X-MAP URI attribute".to_string())
+        })?;
+        let byte_range = attributes
+            .get("BYTERANGE")
+            .map(|value| {
+                let (length, offset) = ByteRange::parse(value).ok_or_else(|| {
+                    ParseError::InvalidM3U8("Invalid #EXT-X-MAP BYTERANGE attribute".to_string())
+                })?;
+                Ok::<_, ParseError>(ByteRange {
+                    length,
+                    offset: offset.unwrap_or(0),
+                })
+            })
+            .transpose()?;
+        Ok(InitSection {
+            uri: uri.to_string(),
+            byte_range,
+        })
+    }
+}
+
+/// InitSection as a Display type, writing its attribute list
+impl fmt::Display for InitSection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "URI={}", M3U8::quote(&self.uri))?;
+        if let Some(byte_range) = self.byte_range {
+            write!(f, ",BYTERANGE=\"{}\"", byte_range)?;
+        }
+        Ok(())
+    }
+}
+
+/// A media segment with the tags that apply to it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Segment {
+    /// Media sequence number, counted from EXT-X-MEDIA-SEQUENCE
+    pub sequence: u64,
+    pub duration: f64,
+    pub title: String,
+    pub uri: String,
+    pub byte_range: Option<ByteRange>,
+    /// Preceded by EXT-X-DISCONTINUITY
+    pub discontinuity: bool,
+    /// Marked missing by EXT-X-GAP and should not be loaded
+    pub gap: bool,
+    /// Approximate bitrate in kbit/s from the EXT-X-BITRATE tag in effect
+    pub bitrate: Option<u64>,
+    /// EXT-X-KEY tags in effect, one per KEYFORMAT, empty when unencrypted
+    pub keys: Vec<EncryptionKey>,
+    /// EXT-X-MAP initialization section in effect
+    pub map: Option<InitSection>,
+    /// Ad cue tag preceding this segment
+    pub ad_cue: Option<AdCue>,
+    /// Inside an ad break opened by EXT-X-CUE-OUT or EXT-X-CUE-OUT-CONT
+    pub in_ad_break: bool,
+    /// LL-HLS partial segments from EXT-X-PART that make up this segment
+    pub parts: Vec<PartialSegment>,
+    /// Thumbnail grid from EXT-X-TILES, in image media playlists
+    pub tiles: Option<Tiles>,
+    /// Wall-clock time given by EXT-X-PROGRAM-DATE-TIME for this segment
+    pub program_date_time: Option<DateTime<FixedOffset>>,
+    /// Wall-clock time of this segment, interpolated from the last EXT-X-PROGRAM-DATE-TIME
+    pub date_time: Option<DateTime<FixedOffset>>,
+}
+
+/// Implementation for Segment
+impl Segment {
+    /// Parses `<duration>,[<title>]` from an EXTINF tag
+    pub(crate) fn parse_inf(data: &str) -> Option<(f64, String)> {
+        let (duration, title) = data.split_once(',').unwrap_or((data, ""));
+        let duration = duration
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|d| d.is_finite())?;
+        Some((duration, title.to_string()))
+    }
+
+    /// Wall-clock time at which this segment ends, if its start is known
+    pub fn end_date_time(&self) -> Option<DateTime<FixedOffset>> {
+        self.date_time?
+            .checked_add_signed(seconds_to_duration(self.duration)?)
+    }
+}
+
+/// Converts seconds to a Duration, or None if not finite or out of range
+pub(crate) fn seconds_to_duration(seconds: f64) -> Option<Duration> {
+    let micros = (seconds * 1_000_000.0).round();
+    (micros.is_finite() && micros.abs() < i64::MAX as f64)
+        .then(|| Duration::microseconds(micros as i64))
+}
+
+/// A Low-Latency HLS partial segment from EXT-X-PART
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PartialSegment {
+    pub uri: String,
+    pub duration: f64,
+    /// Contains an independent frame
+    pub independent: bool,
+    pub byte_range: Option<ByteRange>,
+    /// Marked unavailable and should not be loaded
+    pub gap: bool,
+}
+
+/// Implementation for PartialSegment
+impl PartialSegment {
+    /// Builds a partial segment from a parsed attribute list
+    ///
+    /// A BYTERANGE without an offset continues from `previous` when it shares the URI.
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+        previous: Option<&PartialSegment>,
+    ) -> Result<PartialSegment, ParseError> {
+        let uri = attributes.get("URI").ok_or_else(|| {
+            ParseError::InvalidM3U8("Missing #EXT-X-PART URI attribute".to_string())
+        })?;
+        let byte_range = attributes
+            .get("BYTERANGE")
+            .map(|value| {
+                let (length, offset) = ByteRange::parse(value).ok_or_else(|| {
+                    ParseError::InvalidM3U8("Invalid #EXT-X-PART BYTERANGE attribute".to_string())
+                })?;
+                let offset = offset
+                    .or_else(|| {
+                        previous
+                            .filter(|previous| &previous.uri == uri)
+                            .and_then(|previous| previous.byte_range)
+                            .and_then(|previous| previous.end())
+                    })
+                    .unwrap_or(0);
+                let byte_range = ByteRange { length, offset };
+                byte_range.end().ok_or_else(|| {
+                    ParseError::InvalidM3U8("#EXT-X-PART BYTERANGE overflows".to_string())
+                })?;
+                Ok::<_, ParseError>(byte_range)
+            })
+            .transpose()?;
+        Ok(PartialSegment {
+            uri: uri.to_string(),
+            duration: required(attributes, "#EXT-X-PART", "DURATION")?,
+            independent: yes_no(attributes, "INDEPENDENT"),
+            byte_range,
+            gap: yes_no(attributes, "GAP"),
+        })
+    }
+}
+
+/// PartialSegment as a Display type, writing its attribute list
+impl fmt::Display for PartialSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "DURATION={},URI={}",
+            self.duration,
+            M3U8::quote(&self.uri)
+        )?;
+        if self.independent {
+            write!(f, ",INDEPENDENT=YES")?;
+        }
+        if let Some(byte_range) = self.byte_range {
+            write!(f, ",BYTERANGE={}", M3U8::quote(&byte_range.to_string()))?;
+        }
+        if self.gap {
+            write!(f, ",GAP=YES")?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterates the segments of a borrowed playlist
+impl<'a> IntoIterator for &'a M3U8 {
+    type Item = &'a Segment;
+    type IntoIter = std::slice::Iter<'a, Segment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.iter()
+    }
+}
+
+/// Consumes a playlist into its segments
+impl IntoIterator for M3U8 {
+    type Item = Segment;
+    type IntoIter = std::vec::IntoIter<Segment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.into_iter()
+    }
+}
@@ -0,0 +1,407 @@
+//! Variant and rendition selection helpers.
+use std::collections::HashMap;
+
+use crate::{MediaType, Rendition, VariantStream, M3U8};
+
+/// Parses the BANDWIDTH attribute of a stream
+fn bandwidth(stream: &HashMap<String, String>) -> Option<u64> {
+    stream.get("BANDWIDTH")?.parse().ok()
+}
+
+/// Whether both streams carry the same value for an attribute
+fn same_attribute(a: &HashMap<String, String>, b: &HashMap<String, String>, key: &str) -> bool {
+    matches!((a.get(key), b.get(key)), (Some(a), Some(b)) if a == b)
+}
+
+/// Implementation of selection helpers for M3U8
+impl M3U8 {
+    /// Returns the i-frame stream to use for trick play with a variant stream
+    ///
+    /// An explicit association by STABLE-VARIANT-ID or VIDEO group wins. Otherwise the
+    /// closest bandwidth with the same RESOLUTION is used, then the nearest lower
+    /// bandwidth, then the closest bandwidth overall.
+    pub fn trick_play_for(
+        &self,
+        variant: &HashMap<String, String>,
+    ) -> Option<&HashMap<String, String>> {
+        if let Some(explicit) = self.media_resources.iter().find(|iframe| {
+            same_attribute(variant, iframe, "STABLE-VARIANT-ID")
+                || same_attribute(variant, iframe, "VIDEO")
+        }) {
+            return Some(explicit);
+        }
+
+        let target = bandwidth(variant).unwrap_or(0);
+        let distance =
+            |iframe: &&HashMap<String, String>| bandwidth(iframe).unwrap_or(0).abs_diff(target);
+        self.media_resources
+            .iter()
+            .filter(|iframe| same_attribute(variant, iframe, "RESOLUTION"))
+            .min_by_key(distance)
+            .or_else(|| {
+                self.media_resources
+                    .iter()
+                    .filter(|iframe| bandwidth(iframe).is_some_and(|bw| bw <= target))
+                    .max_by_key(|iframe| bandwidth(iframe))
+            })
+            .or_else(|| self.media_resources.iter().min_by_key(distance))
+    }
+
+    /// Returns the variant with the highest BANDWIDTH not above `max_bandwidth`, or
+    /// the lowest BANDWIDTH variant when none fits
+    ///
+    /// Variants whose attributes cannot be parsed are skipped. The first listed wins
+    /// between equal bandwidths.
+    pub fn select_variant(&self, max_bandwidth: u64) -> Option<&VariantStream> {
+        let variants = self.selectable_variants();
+        let fitting = variants
+            .iter()
+            .filter(|variant| variant.bandwidth <= max_bandwidth)
+            .rev()
+            .max_by_key(|variant| variant.bandwidth);
+        fitting.or_else(|| variants.iter().min_by_key(|variant| variant.bandwidth))
+    }
+
+    /// Returns the variant `select_variant` would, but with bandwidth ties going to
+    /// the higher SCORE
+    ///
+    /// A variant without SCORE loses to any with one. Remaining ties go to the
+    /// first listed.
+    pub fn select_variant_by_score(&self, max_bandwidth: u64) -> Option<&VariantStream> {
+        let variants = self.selectable_variants();
+        let score = |variant: &VariantStream| variant.score.unwrap_or(f64::NEG_INFINITY);
+        let by_score = |a: &&VariantStream, b: &&VariantStream| score(a).total_cmp(&score(b));
+        let fitting = variants
+            .iter()
+            .filter(|variant| variant.bandwidth <= max_bandwidth)
+            .rev()
+            .max_by(|a, b| a.bandwidth.cmp(&b.bandwidth).then(by_score(a, b)));
+        fitting.or_else(|| {
+            variants
+                .iter()
+                .min_by(|a, b| a.bandwidth.cmp(&b.bandwidth).then(by_score(b, a)))
+        })
+    }
+
+    /// Returns the variants that parse, in playlist order, parsing them on first use
+    fn selectable_variants(&self) -> &[VariantStream] {
+        self.selectable_variants
+            .get_or_init(|| self.iter_variants().filter_map(Result::ok).collect())
+    }
+}
+
+/// Implementation of screen-size selection for M3U8
+impl M3U8 {
+    /// Returns the largest variant whose RESOLUTION fits within `width` by `height`
+    ///
+    /// Equal resolutions are decided by the higher BANDWIDTH. When nothing fits the
+    /// smallest resolution is used. Variants without RESOLUTION are only considered
+    /// when no variant has one, and then the highest BANDWIDTH is used.
+    pub fn select_variant_for_screen(&self, width: u32, height: u32) -> Option<&VariantStream> {
+        let variants = self.selectable_variants();
+        let sized = || {
+            variants
+                .iter()
+                .filter_map(|variant| Some((variant.resolution?, variant)))
+        };
+        if sized().next().is_none() {
+            return variants
+                .iter()
+                .rev()
+                .max_by_key(|variant| variant.bandwidth);
+        }
+        sized()
+            .filter(|(resolution, _)| resolution.width <= width && resolution.height <= height)
+            .max_by_key(|(resolution, variant)| (*resolution, variant.bandwidth))
+            .or_else(|| {
+                sized().min_by_key(|(resolution, variant)| {
+                    (*resolution, std::cmp::Reverse(variant.bandwidth))
+                })
+            })
+            .map(|(_, variant)| variant)
+    }
+}
+
+/// Whether subtitle selection considers FORCED=YES renditions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ForcedSubtitles {
+    /// Full subtitles are preferred, with forced ones used when nothing else matches
+    #[default]
+    Include,
+    /// Forced renditions are never selected
+    Exclude,
+    /// Only forced renditions are selected, as when audio is in the viewer's language
+    Only,
+}
+
+/// How well a rendition language matches a requested BCP 47 tag, lower is better
+fn language_rank(rendition: &Rendition, language: &str) -> u8 {
+    let primary = |tag: &str| {
+        tag.split('-')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    };
+    let exact = |tag: &Option<String>| {
+        tag.as_deref()
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(language))
+    };
+    let same_primary = |tag: &Option<String>| {
+        tag.as_deref()
+            .is_some_and(|tag| primary(tag) == primary(language))
+    };
+    if exact(&rendition.language) {
+        0
+    } else if exact(&rendition.assoc_language) {
+        1
+    } else if same_primary(&rendition.language) || same_primary(&rendition.assoc_language) {
+        2
+    } else {
+        3
+    }
+}
+
+/// Implementation of audio selection for M3U8
+impl M3U8 {
+    /// Returns the audio rendition to play for a BCP 47 language tag
+    ///
+    /// LANGUAGE is matched exactly, then ASSOC-LANGUAGE, then by primary language
+    /// subtag, so `en-US` falls back to `en` or `en-GB`. Among equal matches the
+    /// preferred channel count wins, then DEFAULT=YES, then AUTOSELECT=YES, then
+    /// playlist order. Without a language match the DEFAULT, else AUTOSELECT,
+    /// rendition is returned.
+    pub fn select_audio(&self, language: &str, prefer_channels: Option<&str>) -> Option<Rendition> {
+        let channel_count =
+            |channels: &str| channels.split('/').next().unwrap_or_default().to_string();
+        self.iter_renditions()
+            .filter_map(Result::ok)
+            .filter(|rendition| rendition.media_type == MediaType::Audio)
+            .map(|rendition| {
+                let rank = language_rank(&rendition, language);
+                let channels_match = match (prefer_channels, &rendition.channels) {
+                    (Some(preferred), Some(channels)) => {
+                        channel_count(preferred) == channel_count(channels)
+                    }
+                    _ => false,
+                };
+                let key = (
+                    rank,
+                    !channels_match,
+                    !rendition.default,
+                    !rendition.autoselect,
+                );
+                (key, rendition)
+            })
+            .filter(|((rank, _, not_default, not_autoselect), _)| {
+                *rank < 3 || !*not_default || !*not_autoselect
+            })
+            .min_by_key(|(key, _)| *key)
+            .map(|(_, rendition)| rendition)
+    }
+}
+
+/// Implementation of subtitle selection for M3U8
+impl M3U8 {
+    /// Returns the subtitle rendition to show for a BCP 47 language tag
+    ///
+    /// Languages are matched as for `select_audio`, but there is no fallback to
+    /// another language. Among equal matches non-forced renditions win unless only
+    /// forced ones are wanted, then DEFAULT=YES, then AUTOSELECT=YES, then playlist
+    /// order.
+    pub fn select_subtitles(&self, language: &str, forced: ForcedSubtitles) -> Option<Rendition> {
+        self.iter_renditions()
+            .filter_map(Result::ok)
+            .filter(|rendition| rendition.media_type == MediaType::Subtitles)
+            .filter(|rendition| match forced {
+                ForcedSubtitles::Include => true,
+                ForcedSubtitles::Exclude => !rendition.forced,
+                ForcedSubtitles::Only => rendition.forced,
+            })
+            .map(|rendition| {
+                let key = (
+                    language_rank(&rendition, language),
+                    rendition.forced,
+                    !rendition.default,
+                    !rendition.autoselect,
+                );
+                (key, rendition)
+            })
+            .filter(|((rank, _, _, _), _)| *rank < 3)
+            .min_by_key(|(key, _)| *key)
+            .map(|(_, rendition)| rendition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ForcedSubtitles, M3U8};
+
+    const MASTER: &str = "#EXTM3U\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360\n\
+        low.m3u8\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080\n\
+        high.m3u8\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=2500000,RESOLUTION=1280x720\n\
+        mid.m3u8\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=9000000,RESOLUTION=3840x2160,STABLE-VARIANT-ID=\"uhd\"\n\
+        uhd.m3u8\n\
+        #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=90000,RESOLUTION=640x360,URI=\"low-if.m3u8\"\n\
+        #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=400000,RESOLUTION=1920x1080,URI=\"high-if.m3u8\"\n\
+        #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=10,STABLE-VARIANT-ID=\"uhd\",URI=\"uhd-if.m3u8\"\n";
+
+    /// Uri of the i-frame stream matched to a variant
+    fn trick_play_uri(parsed: &M3U8, variant_uri: &str) -> String {
+        let variant = parsed
+            .variant_streams
+            .iter()
+            .find(|variant| variant["uri"] == variant_uri)
+            .unwrap();
+        parsed.trick_play_for(variant).unwrap()["URI"].to_string()
+    }
+
+    #[test]
+    /// Tests variants pair with i-frame streams by association, resolution then bandwidth
+    fn it_matches_trick_play_streams() {
+        let parsed = M3U8::from_body(MASTER).unwrap();
+
+        assert_eq!(trick_play_uri(&parsed, "uhd.m3u8"), "uhd-if.m3u8");
+        assert_eq!(trick_play_uri(&parsed, "high.m3u8"), "high-if.m3u8");
+        assert_eq!(trick_play_uri(&parsed, "low.m3u8"), "low-if.m3u8");
+        assert_eq!(trick_play_uri(&parsed, "mid.m3u8"), "high-if.m3u8");
+    }
+
+    #[test]
+    /// Tests the highest variant under the cap is chosen, else the lowest overall
+    fn it_selects_variants_under_a_bandwidth_cap() {
+        let parsed = M3U8::from_body(MASTER).unwrap();
+
+        let uri = |cap| parsed.select_variant(cap).unwrap().uri.as_str();
+
+        assert_eq!(uri(3_000_000), "mid.m3u8");
+        assert_eq!(uri(5_000_000), "high.m3u8");
+        assert_eq!(uri(100), "low.m3u8");
+        assert_eq!(uri(u64::MAX), "uhd.m3u8");
+        assert!(M3U8::from_body("#EXTM3U\n")
+            .unwrap()
+            .select_variant(1)
+            .is_none());
+    }
+
+    #[test]
+    /// Tests the largest resolution fitting the screen is chosen, with fallbacks
+    fn it_selects_variants_for_a_screen() {
+        let parsed = M3U8::from_body(MASTER).unwrap();
+
+        let uri = |width, height| {
+            parsed
+                .select_variant_for_screen(width, height)
+                .unwrap()
+                .uri
+                .as_str()
+        };
+
+        assert_eq!(uri(1920, 1080), "high.m3u8");
+        assert_eq!(uri(1366, 768), "mid.m3u8");
+        assert_eq!(uri(320, 240), "low.m3u8");
+        assert_eq!(uri(7680, 4320), "uhd.m3u8");
+
+        let audio_only = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=64000\na.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=128000\nb.m3u8\n",
+        )
+        .unwrap();
+        assert_eq!(
+            audio_only.select_variant_for_screen(640, 360).unwrap().uri,
+            "b.m3u8"
+        );
+    }
+
+    #[test]
+    /// Tests audio is chosen by language, regional fallback, channels and defaults
+    fn it_selects_audio_renditions() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"English\",LANGUAGE=\"en-GB\",\
+            CHANNELS=\"2\",DEFAULT=YES,AUTOSELECT=YES\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"English 5.1\",LANGUAGE=\"en-GB\",\
+            CHANNELS=\"6/JOC\",AUTOSELECT=YES\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"a\",NAME=\"Deutsch\",LANGUAGE=\"de\",\
+            AUTOSELECT=YES\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"s\",NAME=\"Francais\",LANGUAGE=\"fr\"\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let name = |language, channels| parsed.select_audio(language, channels).unwrap().name;
+
+        assert_eq!(name("en-US", None), "English");
+        assert_eq!(name("en", Some("6")), "English 5.1");
+        assert_eq!(name("DE", None), "Deutsch");
+        assert_eq!(name("fr", None), "English");
+    }
+
+    #[test]
+    /// Tests subtitles are chosen by language with forced tracks included or not
+    fn it_selects_subtitle_renditions() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"s\",NAME=\"English (forced)\",\
+            LANGUAGE=\"en\",FORCED=YES,AUTOSELECT=YES\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"s\",NAME=\"English SDH\",LANGUAGE=\"en\",\
+            CHARACTERISTICS=\"public.accessibility.transcribes-spoken-dialog,\
+            public.accessibility.describes-music-and-sound\"\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"s\",NAME=\"Espanol (forced)\",\
+            LANGUAGE=\"es\",FORCED=YES\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let name = |language, forced| {
+            parsed
+                .select_subtitles(language, forced)
+                .map(|rendition| rendition.name)
+        };
+
+        assert_eq!(
+            name("en-US", ForcedSubtitles::Include).as_deref(),
+            Some("English SDH")
+        );
+        assert_eq!(
+            name("en", ForcedSubtitles::Only).as_deref(),
+            Some("English (forced)")
+        );
+        assert_eq!(
+            name("es", ForcedSubtitles::Include).as_deref(),
+            Some("Espanol (forced)")
+        );
+        assert_eq!(name("es", ForcedSubtitles::Exclude), None);
+        assert_eq!(name("de", ForcedSubtitles::Include), None);
+
+        let sdh = parsed
+            .select_subtitles("en", ForcedSubtitles::Exclude)
+            .unwrap();
+        assert_eq!(sdh.characteristics.len(), 2);
+        assert!(sdh.has_characteristic("public.accessibility.describes-music-and-sound"));
+    }
+
+    #[test]
+    /// Tests SCORE breaks bandwidth ties and PATHWAY-ID is kept
+    fn it_breaks_bandwidth_ties_by_score() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,SCORE=1.0,PATHWAY-ID=\"cdn-a\"\nlow-a.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,SCORE=1.5,PATHWAY-ID=\"cdn-b\"\nlow-b.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000\nhigh-plain.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=5000000,SCORE=0.5\nhigh-scored.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let uri = |cap| parsed.select_variant_by_score(cap).unwrap().uri.as_str();
+
+        assert_eq!(uri(1_000_000), "low-b.m3u8");
+        assert_eq!(uri(100), "low-b.m3u8");
+        assert_eq!(uri(u64::MAX), "high-scored.m3u8");
+        assert_eq!(
+            parsed.select_variant(u64::MAX).unwrap().uri,
+            "high-plain.m3u8"
+        );
+
+        let variant = &parsed.variants().unwrap()[1];
+        assert_eq!(variant.score, Some(1.5));
+        assert_eq!(variant.pathway_id.as_deref(), Some("cdn-b"));
+        assert_eq!(variant.to_map()["SCORE"], "1.5");
+    }
+}
@@ -0,0 +1,198 @@
+//! HLS specification levels and the tags and attributes each one defines.
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Tag, M3U8};
+
+/// Attributes added by the RFC 8216bis drafts
+const BIS_ATTRIBUTES: &[&str] = &[
+    "ALLOWED-CPC",
+    "BIT-DEPTH",
+    "HDCP-LEVEL",
+    "PATHWAY-ID",
+    "REQ-VIDEO-LAYOUT",
+    "SAMPLE-RATE",
+    "SCORE",
+    "STABLE-RENDITION-ID",
+    "STABLE-VARIANT-ID",
+    "SUPPLEMENTAL-CODECS",
+    "VIDEO-RANGE",
+];
+
+/// HLS specification level a playlist is validated against or written for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum SpecVersion {
+    /// RFC 8216
+    Rfc8216,
+    /// The RFC 8216bis drafts, adding low-latency HLS, gaps and content steering
+    Rfc8216bis,
+    /// Everything this crate parses, including SCTE-35 cues and image streams
+    #[default]
+    Latest,
+}
+
+/// Tag or attribute not defined at the targeted specification level
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpecViolation {
+    Tag(Tag),
+    Attribute { tag: Tag, attribute: String },
+}
+
+/// SpecViolation as a Display type, naming the tag or attribute
+impl fmt::Display for SpecViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecViolation::Tag(tag) => write!(f, "{} is not defined at this level", tag),
+            SpecViolation::Attribute { tag, attribute } => {
+                write!(f, "{} {} is not defined at this level", tag, attribute)
+            }
+        }
+    }
+}
+
+/// Implementation for SpecVersion
+impl SpecVersion {
+    /// Returns the first specification level defining a tag
+    pub fn introducing(tag: Tag) -> SpecVersion {
+        match tag {
+            Tag::ExtXGap
+            | Tag::ExtXBitrate
+            | Tag::ExtXContentSteering
+            | Tag::ExtXPartInf
+            | Tag::ExtXPart
+            | Tag::ExtXPreloadHint
+            | Tag::ExtXRenditionReport
+            | Tag::ExtXServerControl
+            | Tag::ExtXSkip => SpecVersion::Rfc8216bis,
+            Tag::ExtXCueOut
+            | Tag::ExtXCueOutCont
+            | Tag::ExtXCueIn
+            | Tag::ExtXImageStreamInf
+            | Tag::ExtXImagesOnly
+            | Tag::ExtXTiles => SpecVersion::Latest,
+            _ => SpecVersion::Rfc8216,
+        }
+    }
+
+    /// Whether a tag is defined at this level
+    pub fn allows(&self, tag: Tag) -> bool {
+        SpecVersion::introducing(tag) <= *self
+    }
+
+    /// Whether an attribute is defined at this level
+    pub fn allows_attribute(&self, attribute: &str) -> bool {
+        *self >= SpecVersion::Rfc8216bis || !BIS_ATTRIBUTES.contains(&attribute)
+    }
+}
+
+/// Implementation of specification level checks for M3U8
+impl M3U8 {
+    /// Returns the tags and attributes in the playlist that `spec` does not define
+    pub fn spec_violations(&self, spec: SpecVersion) -> Vec<SpecViolation> {
+        let mut tags: Vec<&Tag> = self
+            .tag_counts
+            .keys()
+            .filter(|tag| !spec.allows(**tag))
+            .collect();
+        tags.sort_by_key(|tag| tag.to_string());
+        let mut violations: Vec<SpecViolation> = tags
+            .into_iter()
+            .map(|tag| SpecViolation::Tag(*tag))
+            .collect();
+        for (tag, list) in self.attribute_lists() {
+            let mut attributes: Vec<&String> = list
+                .iter()
+                .flat_map(|attributes| attributes.keys())
+                .filter(|attribute| !spec.allows_attribute(attribute))
+                .collect();
+            attributes.sort();
+            attributes.dedup();
+            violations.extend(
+                attributes
+                    .into_iter()
+                    .map(|attribute| SpecViolation::Attribute {
+                        tag,
+                        attribute: attribute.to_string(),
+                    }),
+            );
+        }
+        violations
+    }
+
+    /// Writes the playlist without the tags and attributes `spec` does not define
+    pub fn to_string_for(&self, spec: SpecVersion) -> String {
+        let mut playlist = self.clone();
+        for list in [
+            &mut playlist.media_tags,
+            &mut playlist.variant_streams,
+            &mut playlist.media_resources,
+        ] {
+            for attributes in list.iter_mut() {
+                attributes.retain(|attribute, _| spec.allows_attribute(attribute));
+            }
+        }
+        playlist
+            .to_string()
+            .lines()
+            .filter(|line| {
+                Tag::from_str(M3U8::by_value(line).0).map_or(true, |tag| spec.allows(tag))
+            })
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+
+    /// Attribute lists kept as maps, with the tag they came from
+    fn attribute_lists(&self) -> [(Tag, &Vec<HashMap<String, String>>); 3] {
+        [
+            (Tag::ExtXMedia, &self.media_tags),
+            (Tag::ExtXStreamInf, &self.variant_streams),
+            (Tag::ExtXIFrameStreamInf, &self.media_resources),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{ParseError, ParserBuilder, SpecVersion, SpecViolation, Tag, M3U8};
+
+    const BODY: &str = "#EXTM3U\n\
+        #EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=1,VIDEO-RANGE=PQ\nv.m3u8\n\
+        #EXTINF:4,\na.ts\n#EXT-X-GAP\n#EXTINF:4,\nb.ts\n";
+
+    #[test]
+    /// Tests tags and attributes are checked and written per specification level
+    fn it_targets_spec_versions() {
+        let parsed = M3U8::from_body(BODY).unwrap();
+
+        assert!(parsed.spec_violations(SpecVersion::Rfc8216bis).is_empty());
+        assert_eq!(
+            parsed.spec_violations(SpecVersion::Rfc8216),
+            vec![
+                SpecViolation::Tag(Tag::ExtXGap),
+                SpecViolation::Tag(Tag::ExtXServerControl),
+                SpecViolation::Attribute {
+                    tag: Tag::ExtXStreamInf,
+                    attribute: "VIDEO-RANGE".to_string()
+                },
+            ]
+        );
+
+        let written = parsed.to_string_for(SpecVersion::Rfc8216);
+        assert!(!written.contains("#EXT-X-GAP") && !written.contains("SERVER-CONTROL"));
+        assert!(written.contains("#EXT-X-STREAM-INF:BANDWIDTH=1\nv.m3u8\n"));
+        assert_eq!(
+            parsed.to_string_for(SpecVersion::Latest),
+            parsed.to_string()
+        );
+
+        let strict = ParserBuilder::new()
+            .spec_version(SpecVersion::Rfc8216)
+            .parse(BODY);
+        assert!(matches!(strict, Err(ParseError::InvalidM3U8(_))));
+    }
+}
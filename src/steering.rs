@@ -0,0 +1,134 @@
+//! Content steering from EXT-X-CONTENT-STEERING and its steering manifest.
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::{FetchOptions, ParseError, M3U8};
+
+/// Steering server and initial pathway from EXT-X-CONTENT-STEERING
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentSteering {
+    pub server_uri: String,
+    /// Pathway to use until the steering manifest is loaded
+    pub pathway_id: Option<String>,
+}
+
+/// Steering manifest served as JSON by the steering server
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+pub struct SteeringManifest {
+    pub version: u32,
+    /// Seconds before the manifest should be reloaded
+    pub ttl: u64,
+    pub reload_uri: Option<String>,
+    /// Pathway IDs in order of preference
+    pub pathway_priority: Vec<String>,
+    #[serde(default)]
+    pub pathway_clones: Vec<PathwayClone>,
+}
+
+/// A new pathway cloned from an existing one with URI replacements
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+pub struct PathwayClone {
+    pub base_id: String,
+    pub id: String,
+    pub uri_replacement: UriReplacement,
+}
+
+/// URI changes applied to the base pathway of a clone
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "SCREAMING-KEBAB-CASE", default)]
+pub struct UriReplacement {
+    pub host: Option<String>,
+    pub params: HashMap<String, String>,
+    pub per_variant_uris: HashMap<String, String>,
+    pub per_rendition_uris: HashMap<String, String>,
+}
+
+/// Implementation for ContentSteering
+impl ContentSteering {
+    /// Builds content steering from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<ContentSteering, ParseError> {
+        let server_uri = attributes.get("SERVER-URI").ok_or_else(|| {
+            ParseError::InvalidM3U8(
+                "Missing #EXT-X-CONTENT-STEERING SERVER-URI attribute".to_string(),
+            )
+        })?;
+        Ok(ContentSteering {
+            server_uri: server_uri.to_string(),
+            pathway_id: attributes.get("PATHWAY-ID").cloned(),
+        })
+    }
+
+    /// Fetches and deserializes the steering manifest from SERVER-URI
+    pub fn fetch_manifest(&self) -> Result<SteeringManifest, ParseError> {
+        let body = FetchOptions::default().fetch(&self.server_uri)?.body;
+        SteeringManifest::from_json(&body)
+    }
+}
+
+/// ContentSteering as a Display type, writing its attribute list
+impl fmt::Display for ContentSteering {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SERVER-URI={}", M3U8::quote(&self.server_uri))?;
+        if let Some(pathway_id) = &self.pathway_id {
+            write!(f, ",PATHWAY-ID={}", M3U8::quote(pathway_id))?;
+        }
+        Ok(())
+    }
+}
+
+/// Implementation for SteeringManifest
+impl SteeringManifest {
+    /// Deserializes a steering manifest from its JSON body
+    pub fn from_json(body: &str) -> Result<SteeringManifest, ParseError> {
+        Ok(serde_json::from_str(body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{SteeringManifest, M3U8};
+
+    #[test]
+    /// Tests the steering tag is parsed and a steering manifest deserializes
+    fn it_parses_content_steering() {
+        let body = "#EXTM3U\n\
+            #EXT-X-CONTENT-STEERING:SERVER-URI=\"https://steer.example.com/v1\",PATHWAY-ID=\"CDN-A\"\n";
+
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let steering = parsed.content_steering().unwrap();
+        assert_eq!(steering.server_uri, "https://steer.example.com/v1");
+        assert_eq!(steering.pathway_id.as_deref(), Some("CDN-A"));
+        assert!(parsed.to_string().contains(
+            "#EXT-X-CONTENT-STEERING:SERVER-URI=\"https://steer.example.com/v1\",PATHWAY-ID=\"CDN-A\"\n"
+        ));
+
+        let manifest = SteeringManifest::from_json(
+            r#"{
+                "VERSION": 1,
+                "TTL": 300,
+                "RELOAD-URI": "https://steer.example.com/v1?session=1",
+                "PATHWAY-PRIORITY": ["CDN-B", "CDN-A"],
+                "PATHWAY-CLONES": [{
+                    "BASE-ID": "CDN-A",
+                    "ID": "CDN-C",
+                    "URI-REPLACEMENT": {"HOST": "c.example.com", "PARAMS": {"token": "x"}}
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.ttl, 300);
+        assert_eq!(manifest.pathway_priority, vec!["CDN-B", "CDN-A"]);
+        let clone = &manifest.pathway_clones[0];
+        assert_eq!(clone.uri_replacement.host.as_deref(), Some("c.example.com"));
+        assert_eq!(clone.uri_replacement.params["token"], "x");
+        assert!(SteeringManifest::from_json("{}").is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Typed playlist-level tags.
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{ParseError, Resolution, M3U8};
+
+/// Parses an enumerated YES/NO attribute, defaulting to NO when absent
+pub(crate) fn yes_no(attributes: &HashMap<String, String>, key: &str) -> bool {
+    attributes.get(key).is_some_and(|value| value == "YES")
+}
+
+/// Parses a required attribute into a number, naming the tag on failure
+pub(crate) fn required<T: std::str::FromStr>(
+    attributes: &HashMap<String, String>,
+    tag: &str,
+    key: &str,
+) -> Result<T, ParseError> {
+    optional(attributes, tag, key)?
+        .ok_or_else(|| ParseError::InvalidM3U8(format!("Missing {} {} attribute", tag, key)))
+}
+
+/// Parses an optional attribute into a number, naming the tag on failure
+pub(crate) fn optional<T: std::str::FromStr>(
+    attributes: &HashMap<String, String>,
+    tag: &str,
+    key: &str,
+) -> Result<Option<T>, ParseError> {
+    attributes
+        .get(key)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| ParseError::InvalidM3U8(format!("Invalid {} {} attribute", tag, key)))
+        })
+        .transpose()
+}
+
+/// Preferred start position from EXT-X-START
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartPoint {
+    /// Seconds from the start of the playlist, or from its end when negative
+    pub time_offset: f64,
+    /// Start exactly at the offset instead of at the segment containing it
+    pub precise: bool,
+}
+
+/// Implementation for StartPoint
+impl StartPoint {
+    /// Builds a start point from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<StartPoint, ParseError> {
+        Ok(StartPoint {
+            time_offset: required(attributes, "#EXT-X-START", "TIME-OFFSET")?,
+            precise: yes_no(attributes, "PRECISE"),
+        })
+    }
+}
+
+/// StartPoint as a Display type, writing its attribute list
+impl fmt::Display for StartPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TIME-OFFSET={}", self.time_offset)?;
+        if self.precise {
+            write!(f, ",PRECISE=YES")?;
+        }
+        Ok(())
+    }
+}
+
+/// Mutability of a media playlist from EXT-X-PLAYLIST-TYPE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistType {
+    /// Segments may only be appended
+    Event,
+    /// The playlist never changes
+    Vod,
+}
+
+/// Implementation for PlaylistType
+impl FromStr for PlaylistType {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<PlaylistType, Self::Err> {
+        match input {
+            "EVENT" => Ok(PlaylistType::Event),
+            "VOD" => Ok(PlaylistType::Vod),
+            _ => Err(ParseError::InvalidM3U8(format!(
+                "Invalid #EXT-X-PLAYLIST-TYPE: {}",
+                input
+            ))),
+        }
+    }
+}
+
+/// PlaylistType as a Display type
+impl fmt::Display for PlaylistType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlaylistType::Event => write!(f, "EVENT"),
+            PlaylistType::Vod => write!(f, "VOD"),
+        }
+    }
+}
+
+/// Session-level metadata from EXT-X-SESSION-DATA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionData {
+    pub data_id: String,
+    /// Inline value, exclusive with `uri`
+    pub value: Option<String>,
+    /// URI of a JSON document holding the data, exclusive with `value`
+    pub uri: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Implementation for SessionData
+impl SessionData {
+    /// Builds session data from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<SessionData, ParseError> {
+        let data_id = attributes.get("DATA-ID").ok_or_else(|| {
+            ParseError::InvalidM3U8("Missing #EXT-X-SESSION-DATA DATA-ID attribute".to_string())
+        })?;
+        Ok(SessionData {
+            data_id: data_id.to_string(),
+            value: attributes.get("VALUE").cloned(),
+            uri: attributes.get("URI").cloned(),
+            language: attributes.get("LANGUAGE").cloned(),
+        })
+    }
+}
+
+/// SessionData as a Display type, writing its attribute list
+impl fmt::Display for SessionData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DATA-ID={}", M3U8::quote(&self.data_id))?;
+        if let Some(value) = &self.value {
+            write!(f, ",VALUE={}", M3U8::quote(value))?;
+        }
+        if let Some(uri) = &self.uri {
+            write!(f, ",URI={}", M3U8::quote(uri))?;
+        }
+        if let Some(language) = &self.language {
+            write!(f, ",LANGUAGE={}", M3U8::quote(language))?;
+        }
+        Ok(())
+    }
+}
+
+/// Kind of resource announced by EXT-X-PRELOAD-HINT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreloadHintType {
+    Part,
+    Map,
+}
+
+/// Implementation for PreloadHintType
+impl FromStr for PreloadHintType {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<PreloadHintType, Self::Err> {
+        match input {
+            "PART" => Ok(PreloadHintType::Part),
+            "MAP" => Ok(PreloadHintType::Map),
+            _ => Err(ParseError::InvalidM3U8(format!(
+                "Invalid #EXT-X-PRELOAD-HINT TYPE: {}",
+                input
+            ))),
+        }
+    }
+}
+
+/// PreloadHintType as a Display type
+impl fmt::Display for PreloadHintType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PreloadHintType::Part => write!(f, "PART"),
+            PreloadHintType::Map => write!(f, "MAP"),
+        }
+    }
+}
+
+/// A resource the server expects to publish next, from EXT-X-PRELOAD-HINT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreloadHint {
+    pub hint_type: PreloadHintType,
+    pub uri: String,
+    /// First byte of the hinted resource, defaulting to 0
+    pub byte_range_start: u64,
+    /// Length of the hinted range, or to the end of the resource when absent
+    pub byte_range_length: Option<u64>,
+}
+
+/// Implementation for PreloadHint
+impl PreloadHint {
+    /// Builds a preload hint from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<PreloadHint, ParseError> {
+        let missing = |key: &str| {
+            ParseError::InvalidM3U8(format!("Missing #EXT-X-PRELOAD-HINT {} attribute", key))
+        };
+        Ok(PreloadHint {
+            hint_type: attributes
+                .get("TYPE")
+                .ok_or_else(|| missing("TYPE"))?
+                .parse()?,
+            uri: attributes
+                .get("URI")
+                .ok_or_else(|| missing("URI"))?
+                .to_string(),
+            byte_range_start: optional(attributes, "#EXT-X-PRELOAD-HINT", "BYTERANGE-START")?
+                .unwrap_or(0),
+            byte_range_length: optional(attributes, "#EXT-X-PRELOAD-HINT", "BYTERANGE-LENGTH")?,
+        })
+    }
+}
+
+/// PreloadHint as a Display type, writing its attribute list
+impl fmt::Display for PreloadHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TYPE={},URI={}", self.hint_type, M3U8::quote(&self.uri))?;
+        if self.byte_range_start > 0 {
+            write!(f, ",BYTERANGE-START={}", self.byte_range_start)?;
+        }
+        if let Some(length) = self.byte_range_length {
+            write!(f, ",BYTERANGE-LENGTH={}", length)?;
+        }
+        Ok(())
+    }
+}
+
+/// Latest media sequence and part of another rendition, from EXT-X-RENDITION-REPORT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenditionReport {
+    pub uri: String,
+    pub last_msn: Option<u64>,
+    pub last_part: Option<u64>,
+}
+
+/// Implementation for RenditionReport
+impl RenditionReport {
+    /// Builds a rendition report from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<RenditionReport, ParseError> {
+        let uri = attributes.get("URI").ok_or_else(|| {
+            ParseError::InvalidM3U8("Missing #EXT-X-RENDITION-REPORT URI attribute".to_string())
+        })?;
+        Ok(RenditionReport {
+            uri: uri.to_string(),
+            last_msn: optional(attributes, "#EXT-X-RENDITION-REPORT", "LAST-MSN")?,
+            last_part: optional(attributes, "#EXT-X-RENDITION-REPORT", "LAST-PART")?,
+        })
+    }
+}
+
+/// RenditionReport as a Display type, writing its attribute list
+impl fmt::Display for RenditionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "URI={}", M3U8::quote(&self.uri))?;
+        if let Some(last_msn) = self.last_msn {
+            write!(f, ",LAST-MSN={}", last_msn)?;
+        }
+        if let Some(last_part) = self.last_part {
+            write!(f, ",LAST-PART={}", last_part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Delivery directives from EXT-X-SERVER-CONTROL
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ServerControl {
+    /// The server supports blocking playlist reloads
+    pub can_block_reload: bool,
+    /// Seconds from the end of the playlist a client should not start closer than
+    pub hold_back: Option<f64>,
+    /// Like `hold_back`, for clients playing partial segments
+    pub part_hold_back: Option<f64>,
+    /// Seconds from the end of the playlist within which delta updates keep segments
+    pub can_skip_until: Option<f64>,
+    /// Delta updates may also skip EXT-X-DATERANGE tags
+    pub can_skip_dateranges: bool,
+}
+
+/// Implementation for ServerControl
+impl ServerControl {
+    /// Builds server control directives from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<ServerControl, ParseError> {
+        let tag = "#EXT-X-SERVER-CONTROL";
+        Ok(ServerControl {
+            can_block_reload: yes_no(attributes, "CAN-BLOCK-RELOAD"),
+            hold_back: optional(attributes, tag, "HOLD-BACK")?,
+            part_hold_back: optional(attributes, tag, "PART-HOLD-BACK")?,
+            can_skip_until: optional(attributes, tag, "CAN-SKIP-UNTIL")?,
+            can_skip_dateranges: yes_no(attributes, "CAN-SKIP-DATERANGES"),
+        })
+    }
+}
+
+/// ServerControl as a Display type, writing its attribute list
+impl fmt::Display for ServerControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut attributes = Vec::new();
+        if self.can_block_reload {
+            attributes.push("CAN-BLOCK-RELOAD=YES".to_string());
+        }
+        if let Some(hold_back) = self.hold_back {
+            attributes.push(format!("HOLD-BACK={}", hold_back));
+        }
+        if let Some(part_hold_back) = self.part_hold_back {
+            attributes.push(format!("PART-HOLD-BACK={}", part_hold_back));
+        }
+        if let Some(can_skip_until) = self.can_skip_until {
+            attributes.push(format!("CAN-SKIP-UNTIL={}", can_skip_until));
+        }
+        if self.can_skip_dateranges {
+            attributes.push("CAN-SKIP-DATERANGES=YES".to_string());
+        }
+        write!(f, "{}", attributes.join(","))
+    }
+}
+
+/// Segments left out of a playlist delta update, from EXT-X-SKIP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skip {
+    pub skipped_segments: u64,
+    /// IDs of date ranges removed since the playlist the delta applies to
+    pub recently_removed_dateranges: Vec<String>,
+}
+
+/// Implementation for Skip
+impl Skip {
+    /// Builds a skip from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<Skip, ParseError> {
+        Ok(Skip {
+            skipped_segments: required(attributes, "#EXT-X-SKIP", "SKIPPED-SEGMENTS")?,
+            recently_removed_dateranges: attributes
+                .get("RECENTLY-REMOVED-DATERANGES")
+                .map(|ids| {
+                    ids.split('\t')
+                        .filter(|id| !id.is_empty())
+                        .map(|id| id.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Skip as a Display type, writing its attribute list
+impl fmt::Display for Skip {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SKIPPED-SEGMENTS={}", self.skipped_segments)?;
+        if !self.recently_removed_dateranges.is_empty() {
+            let ids = self.recently_removed_dateranges.join("\t");
+            write!(f, ",RECENTLY-REMOVED-DATERANGES={}", M3U8::quote(&ids))?;
+        }
+        Ok(())
+    }
+}
+
+/// Thumbnail grid of an image segment from EXT-X-TILES
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tiles {
+    /// Size of each thumbnail
+    pub resolution: Resolution,
+    pub columns: u32,
+    pub rows: u32,
+    /// Seconds of media covered by each thumbnail
+    pub duration: f64,
+}
+
+/// Implementation for Tiles
+impl Tiles {
+    /// Builds a tile grid from a parsed attribute list
+    pub(crate) fn from_attributes(
+        attributes: &HashMap<String, String>,
+    ) -> Result<Tiles, ParseError> {
+        let layout: Resolution = required(attributes, "#EXT-X-TILES", "LAYOUT")?;
+        Ok(Tiles {
+            resolution: required(attributes, "#EXT-X-TILES", "RESOLUTION")?,
+            columns: layout.width,
+            rows: layout.height,
+            duration: required(attributes, "#EXT-X-TILES", "DURATION")?,
+        })
+    }
+
+    /// Returns the column and row of the thumbnail shown `offset` seconds into the
+    /// image segment
+    pub fn tile_at(&self, offset: f64) -> Option<(u32, u32)> {
+        if offset < 0.0 || self.duration <= 0.0 || self.columns == 0 {
+            return None;
+        }
+        let index = offset / self.duration;
+        let columns = u64::from(self.columns);
+        if !index.is_finite() || index >= (columns * u64::from(self.rows)) as f64 {
+            return None;
+        }
+        let index = index as u64;
+        Some(((index % columns) as u32, (index / columns) as u32))
+    }
+}
+
+/// Tiles as a Display type, writing its attribute list
+impl fmt::Display for Tiles {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "RESOLUTION={},LAYOUT={}x{},DURATION={}",
+            self.resolution, self.columns, self.rows, self.duration
+        )
+    }
+}
+
+/// A comment or unrecognized tag kept so it can be written back out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLine {
+    /// 1-based line number in the parsed body
+    pub line_number: usize,
+    pub line: String,
+    /// Index of the segment the line precedes, or the segment count when it follows them all
+    pub segment_index: usize,
+}
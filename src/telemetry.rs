@@ -0,0 +1,126 @@
+//! Coarse playlist features for fleet-wide analytics.
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{Codec, MediaType, VideoCodec, M3U8};
+
+/// Width of the bandwidth buckets in bits per second
+const BANDWIDTH_BUCKET: u64 = 500_000;
+
+/// Upper bounds in seconds of the segment duration buckets, and their labels
+const DURATION_BUCKETS: &[(f64, &str)] = &[
+    (2.0, "<2s"),
+    (4.0, "2-4s"),
+    (6.0, "4-6s"),
+    (10.0, "6-10s"),
+    (f64::INFINITY, ">=10s"),
+];
+
+/// Shape of a playlist without URIs, names, languages or other identifiers
+///
+/// Bandwidths are rounded down to 500 kbit/s and segment durations are counted in
+/// buckets, so summaries of different playlists with the same shape are identical.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TelemetrySummary {
+    /// Variant bandwidths in bits per second, bucketed and ascending
+    pub ladder: Vec<u64>,
+    /// Distinct variant heights in pixels, ascending
+    pub heights: Vec<u32>,
+    /// Variant count per codec family, such as `hevc` or `aac`
+    pub codec_mix: BTreeMap<&'static str, usize>,
+    /// Rendition count per EXT-X-MEDIA TYPE
+    pub renditions: BTreeMap<String, usize>,
+    /// Segment count per duration bucket
+    pub segment_durations: BTreeMap<&'static str, usize>,
+    pub encrypted: bool,
+    pub live: bool,
+}
+
+/// Returns the family of a codec string, never the string itself
+fn codec_family(codec: &str) -> &'static str {
+    match codec.parse::<Codec>() {
+        Ok(Codec::Video(VideoCodec::Avc(_))) => "avc",
+        Ok(Codec::Video(VideoCodec::Hevc(_))) => "hevc",
+        Ok(Codec::Video(VideoCodec::Av1(_))) => "av1",
+        Ok(Codec::Aac { .. }) => "aac",
+        Ok(Codec::Mp3) => "mp3",
+        Ok(Codec::Ac3) => "ac3",
+        Ok(Codec::Ec3) => "ec3",
+        Ok(Codec::Ac4) => "ac4",
+        Ok(Codec::Opus) => "opus",
+        Ok(Codec::Flac) => "flac",
+        Ok(Codec::WebVtt | Codec::Ttml) => "text",
+        _ => "other",
+    }
+}
+
+/// Implementation of telemetry summaries for M3U8
+impl M3U8 {
+    /// Reduces the playlist to coarse features safe to collect across a fleet
+    pub fn telemetry_summary(&self) -> TelemetrySummary {
+        let mut summary = TelemetrySummary {
+            encrypted: self.segments.iter().any(|segment| !segment.keys.is_empty()),
+            live: !self.segments.is_empty() && !self.end_list,
+            ..Default::default()
+        };
+        for variant in self.iter_variants().filter_map(Result::ok) {
+            summary
+                .ladder
+                .push(variant.bandwidth / BANDWIDTH_BUCKET * BANDWIDTH_BUCKET);
+            if let Some(resolution) = variant.resolution {
+                summary.heights.push(resolution.height);
+            }
+            for codec in &variant.codecs {
+                *summary.codec_mix.entry(codec_family(codec)).or_default() += 1;
+            }
+        }
+        summary.ladder.sort_unstable();
+        summary.heights.sort_unstable();
+        summary.heights.dedup();
+        for media_tag in &self.media_tags {
+            let media_type = media_tag
+                .get("TYPE")
+                .and_then(|value| value.parse::<MediaType>().ok())
+                .map_or("OTHER".to_string(), |media_type| media_type.to_string());
+            *summary.renditions.entry(media_type).or_default() += 1;
+        }
+        for segment in &self.segments {
+            let (_, label) = DURATION_BUCKETS
+                .iter()
+                .find(|(bound, _)| segment.duration < *bound)
+                .unwrap_or(&DURATION_BUCKETS[DURATION_BUCKETS.len() - 1]);
+            *summary.segment_durations.entry(label).or_default() += 1;
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::M3U8;
+
+    #[test]
+    /// Tests summaries keep the ladder shape and drop URIs and identifiers
+    fn it_summarizes_without_identifiers() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"secret-group\",NAME=\"Director\",LANGUAGE=\"en\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2749000,RESOLUTION=1280x720,\
+            CODECS=\"hvc1.2.4.L123.B0,mp4a.40.2\",AUDIO=\"secret-group\"\n\
+            https://private.example/token=abc/720.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=801000,RESOLUTION=640x360,CODECS=\"avc1.4d401e,x-custom\"\n\
+            https://private.example/token=abc/360.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let summary = parsed.telemetry_summary();
+
+        assert_eq!(summary.ladder, vec![500_000, 2_500_000]);
+        assert_eq!(summary.heights, vec![360, 720]);
+        assert_eq!(summary.codec_mix["hevc"], 1);
+        assert_eq!(summary.codec_mix["other"], 1);
+        assert_eq!(summary.renditions["AUDIO"], 1);
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(!json.contains("private") && !json.contains("secret") && !json.contains("\"en\""));
+    }
+}
@@ -0,0 +1,142 @@
+//! Segment URI templates for writing live playlists.
+use std::str::FromStr;
+
+use crate::{ParseError, Segment, M3U8};
+
+/// A piece of a parsed URI template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    /// Media sequence number, zero-padded to at least `width` digits
+    Sequence {
+        width: usize,
+    },
+}
+
+/// Segment URI pattern such as `seg_{seq:05}.m4s`
+///
+/// `{seq}` expands to the media sequence number of the segment and `{seq:0N}` pads
+/// it with zeros to N digits. `{{` and `}}` write literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UriTemplate {
+    pieces: Vec<Piece>,
+}
+
+/// Implementation for UriTemplate
+impl UriTemplate {
+    /// Returns the URI for a media sequence number
+    pub fn expand(&self, sequence: u64) -> String {
+        self.pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Literal(text) => text.to_string(),
+                Piece::Sequence { width } => format!("{:0width$}", sequence, width = width),
+            })
+            .collect()
+    }
+}
+
+/// Implementation for UriTemplate
+impl FromStr for UriTemplate {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<UriTemplate, Self::Err> {
+        let invalid = || ParseError::InvalidM3U8(format!("Invalid URI template: {}", input));
+        let mut pieces = vec![];
+        let mut literal = String::new();
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(invalid()),
+                        }
+                    }
+                    let width = match placeholder.split_once(':') {
+                        Some(("seq", width)) if width.starts_with('0') => {
+                            width.parse().map_err(|_| invalid())?
+                        }
+                        None if placeholder == "seq" => 0,
+                        _ => return Err(invalid()),
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Sequence { width });
+                }
+                '}' => return Err(invalid()),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(UriTemplate { pieces })
+    }
+}
+
+/// Implementation of templated segment writing for M3U8
+impl M3U8 {
+    /// Appends a segment named from `template` with the next media sequence number
+    pub fn append_segment(&mut self, duration: f64, template: &UriTemplate) -> &Segment {
+        let skipped = self.skip.as_ref().map_or(0, |skip| skip.skipped_segments);
+        let sequence = self.media_sequence + skipped + self.segments.len() as u64;
+        let date_time = self.segments.last().and_then(|last| last.end_date_time());
+        self.segments.push(Segment {
+            sequence,
+            duration,
+            uri: template.expand(sequence),
+            date_time,
+            ..Default::default()
+        });
+        &self.segments[self.segments.len() - 1]
+    }
+
+    /// Renames every segment from `template` using its media sequence number
+    pub fn apply_uri_template(&mut self, template: &UriTemplate) {
+        for segment in &mut self.segments {
+            segment.uri = template.expand(segment.sequence);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{UriTemplate, M3U8};
+
+    #[test]
+    /// Tests templates expand the media sequence number of appended segments
+    fn it_appends_templated_segments() {
+        let template: UriTemplate = "live/seg_{seq:05}.m4s".parse().unwrap();
+        let mut parsed =
+            M3U8::from_body("#EXTM3U\n#EXT-X-MEDIA-SEQUENCE:41\n#EXTINF:4,\nold.m4s\n").unwrap();
+
+        let appended = parsed.append_segment(4.0, &template);
+
+        assert_eq!(appended.sequence, 42);
+        assert_eq!(appended.uri, "live/seg_00042.m4s");
+        assert!(parsed
+            .to_string()
+            .ends_with("#EXTINF:4,\nlive/seg_00042.m4s\n"));
+
+        parsed.apply_uri_template(&"{{{seq}}}.ts".parse().unwrap());
+        assert_eq!(parsed.segments()[0].uri, "{41}.ts");
+
+        assert!("{seq}_{seq".parse::<UriTemplate>().is_err());
+        assert!("seg_{index}.ts".parse::<UriTemplate>().is_err());
+        assert!("seg_{seq:5}.ts".parse::<UriTemplate>().is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Local HTTP fixture server for tests that fetch playlists.
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::{ParseError, M3U8};
+
+/// Serves the files of a directory over HTTP on 127.0.0.1
+///
+/// Requests are answered one at a time on a background thread. Each response can be
+/// delayed, and the first requests can be failed with an error status, to exercise
+/// retry and monitoring code. Files are served with an ETag and Last-Modified date,
+/// and conditional requests that still match are answered 304 Not Modified. Paths
+/// can also be redirected elsewhere. Requests in absolute form, as sent to an HTTP
+/// proxy, are served by their path, so the server can stand in for a proxy. A
+/// session cookie can be handed out by one path and then demanded by all others.
+#[derive(Debug, Clone)]
+pub struct FixtureServer {
+    dir: PathBuf,
+    latency: Duration,
+    failures: usize,
+    failure_status: u16,
+    redirects: Vec<(String, String)>,
+    session: Option<(String, String)>,
+}
+
+/// Running fixture server, stopped when dropped
+#[derive(Debug)]
+pub struct ServerGuard {
+    url: String,
+    stop: Arc<AtomicBool>,
+    requests: Arc<AtomicUsize>,
+    not_modified: Arc<AtomicUsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Serves a directory with no latency or injected errors, returning its base URL
+pub fn serve_fixture(dir: impl AsRef<Path>) -> Result<(String, ServerGuard), ParseError> {
+    let guard = FixtureServer::new(dir).start()?;
+    Ok((guard.url().to_string(), guard))
+}
+
+/// Temporary directory of fixture files, removed with its contents when dropped
+#[derive(Debug)]
+pub struct FixtureDir {
+    path: PathBuf,
+}
+
+/// Implementation for FixtureDir
+impl FixtureDir {
+    /// Creates an empty directory under the system temp directory, unique to `name`
+    /// and this process
+    pub fn new(name: &str) -> Result<Self, ParseError> {
+        let path = std::env::temp_dir().join(format!("m3u8parse-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path)?;
+        Ok(FixtureDir { path })
+    }
+}
+
+/// FixtureDir dereferences to its path
+impl Deref for FixtureDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// FixtureDir as a path for serving and writing files
+impl AsRef<Path> for FixtureDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Removes the directory even when a test fails before reaching its end
+impl Drop for FixtureDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Returns the reason phrase of a status code
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        302 => "Found",
+        304 => "Not Modified",
+        403 => "Forbidden",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Error",
+    }
+}
+
+/// Implementation for FixtureServer
+impl FixtureServer {
+    /// Server for the files under `dir`
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        FixtureServer {
+            dir: dir.as_ref().to_path_buf(),
+            latency: Duration::ZERO,
+            failures: 0,
+            failure_status: 503,
+            redirects: vec![],
+            session: None,
+        }
+    }
+
+    /// Waits this long before answering each request
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Answers the first `count` requests with `status` instead of the file
+    pub fn fail_first(mut self, count: usize, status: u16) -> Self {
+        self.failures = count;
+        self.failure_status = status;
+        self
+    }
+
+    /// Answers requests for `path` with a 302 redirect to `location`
+    pub fn redirect(mut self, path: &str, location: &str) -> Self {
+        self.redirects
+            .push((path.to_string(), location.to_string()));
+        self
+    }
+
+    /// Sets `cookie`, such as `session=abc`, on responses for `path` and answers
+    /// requests for other paths without it with 403 Forbidden
+    pub fn session_cookie(mut self, path: &str, cookie: &str) -> Self {
+        self.session = Some((path.to_string(), cookie.to_string()));
+        self
+    }
+
+    /// Binds a free local port and starts serving
+    pub fn start(self) -> Result<ServerGuard, ParseError> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let stop = Arc::new(AtomicBool::new(false));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let thread = {
+            let stop = Arc::clone(&stop);
+            let requests = Arc::clone(&requests);
+            let not_modified = Arc::clone(&not_modified);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let count = requests.fetch_add(1, Ordering::SeqCst);
+                        // A client hanging up early only affects its own request
+                        if let Ok(304) = self.respond(stream, count < self.failures) {
+                            not_modified.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                }
+            })
+        };
+        Ok(ServerGuard {
+            url,
+            stop,
+            requests,
+            not_modified,
+            thread: Some(thread),
+        })
+    }
+
+    /// Answers one request with a file, a 304, a 404 or an injected failure,
+    /// returning the status sent
+    fn respond(&self, mut stream: TcpStream, fail: bool) -> std::io::Result<u16> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut if_none_match = None;
+        let mut if_modified_since = None;
+        let mut cookies = String::new();
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            if let Some((name, value)) = header.split_once(':') {
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "if-none-match" => if_none_match = Some(value.trim().to_string()),
+                    "if-modified-since" => if_modified_since = Some(value.trim().to_string()),
+                    "cookie" => cookies = value.trim().to_string(),
+                    _ => {}
+                }
+            }
+            header.clear();
+        }
+        thread::sleep(self.latency);
+
+        let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let path = match target.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+            None => target,
+        };
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        if let Some((_, location)) = self.redirects.iter().find(|(from, _)| from == path) {
+            write!(
+                stream,
+                "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                location
+            )?;
+            return Ok(302);
+        }
+        let relative = Path::new(path.trim_start_matches('/'));
+        let file = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+            .then(|| std::fs::read(self.dir.join(relative)).ok())
+            .flatten();
+        let etag = file.as_ref().map(|body| {
+            format!(
+                "\"{:016x}\"",
+                M3U8::content_hash(&String::from_utf8_lossy(body))
+            )
+        });
+        let last_modified = std::fs::metadata(self.dir.join(relative))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|modified| {
+                DateTime::<Utc>::from(modified)
+                    .format("%a, %d %b %Y %H:%M:%S GMT")
+                    .to_string()
+            });
+        let unchanged = match if_none_match {
+            Some(tag) => etag.as_ref() == Some(&tag),
+            None => if_modified_since.is_some() && if_modified_since == last_modified,
+        };
+        let (sets_cookie, forbidden) = match &self.session {
+            Some((session_path, cookie)) if session_path == path => (Some(cookie), false),
+            Some((_, cookie)) => (None, !cookies.split("; ").any(|sent| sent == cookie)),
+            None => (None, false),
+        };
+        let (status, body) = match file {
+            _ if fail => (
+                self.failure_status,
+                reason(self.failure_status).as_bytes().to_vec(),
+            ),
+            _ if forbidden => (403, reason(403).as_bytes().to_vec()),
+            Some(_) if unchanged => (304, vec![]),
+            Some(body) => (200, body),
+            None => (404, reason(404).as_bytes().to_vec()),
+        };
+        let content_type = if status == 200 && path.ends_with(".m3u8") {
+            "application/vnd.apple.mpegurl"
+        } else {
+            "text/plain"
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            status,
+            reason(status),
+            content_type,
+            body.len()
+        )?;
+        if let Some(cookie) = sets_cookie {
+            write!(stream, "Set-Cookie: {}; Path=/\r\n", cookie)?;
+        }
+        if let (Some(etag), Some(last_modified), 200 | 304) = (etag, last_modified, status) {
+            write!(
+                stream,
+                "ETag: {}\r\nLast-Modified: {}\r\n",
+                etag, last_modified
+            )?;
+        }
+        stream.write_all(b"\r\n")?;
+        stream.write_all(&body)?;
+        Ok(status)
+    }
+}
+
+/// Implementation for ServerGuard
+impl ServerGuard {
+    /// Base URL of the server, such as `http://127.0.0.1:41234`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Number of requests received so far
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// Number of conditional requests answered 304 Not Modified so far
+    pub fn not_modified(&self) -> usize {
+        self.not_modified.load(Ordering::SeqCst)
+    }
+}
+
+/// Stops the server thread, waking it with a last connection
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.url.trim_start_matches("http://"));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+
+    use crate::testing::{serve_fixture, FixtureDir, FixtureServer};
+    use crate::M3U8;
+
+    #[test]
+    /// Tests playlists are fetched from the fixture server, including injected failures
+    fn it_serves_fixtures() {
+        let dir = FixtureDir::new("fixture").unwrap();
+        std::fs::write(dir.join("media.m3u8"), "#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+
+        let (url, guard) = serve_fixture(&dir).unwrap();
+        let parsed = M3U8::from_uri(&format!("{}/media.m3u8", url)).unwrap();
+        assert_eq!(parsed.segments()[0].uri, "a.ts");
+        assert!(M3U8::from_uri(&format!("{}/../secret.m3u8", url)).is_err());
+        assert_eq!(guard.requests(), 2);
+        drop(guard);
+
+        let failing = FixtureServer::new(&dir).fail_first(1, 503).start().unwrap();
+        let uri = format!("{}/media.m3u8", failing.url());
+        assert!(M3U8::from_uri(&uri).is_err());
+        assert!(M3U8::from_uri(&uri).is_ok());
+    }
+}
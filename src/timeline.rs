@@ -0,0 +1,340 @@
+//! Cutting media playlists into clips and joining them into one timeline.
+use crate::{MediaPlaylist, ParseError, UnknownLine, M3U8};
+
+/// Implementation of timing aggregates for media playlists
+impl MediaPlaylist {
+    /// Returns the sum of the segment durations in seconds
+    pub fn total_duration(&self) -> f64 {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+
+    /// Returns the number of segments listed
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns the offset in seconds at which each segment starts, from 0 for the
+    /// first listed segment
+    pub fn cumulative_start_times(&self) -> Vec<f64> {
+        self.segments
+            .iter()
+            .scan(0.0, |offset, segment| {
+                let start = *offset;
+                *offset += segment.duration;
+                Some(start)
+            })
+            .collect()
+    }
+}
+
+/// Implementation of timeline editing for media playlists
+impl MediaPlaylist {
+    /// Returns a VOD playlist of the segments overlapping `start..end` seconds
+    ///
+    /// Segments keep their media sequence numbers, keys and byte ranges, so the
+    /// clip plays the same resources. EXT-X-KEY and EXT-X-MAP tags in effect are
+    /// written again before the first segment of the clip. Header tags are always
+    /// kept; other comments and unknown tags are kept with the segments they precede.
+    pub fn clip(&self, start: f64, end: f64) -> Result<MediaPlaylist, ParseError> {
+        if start >= end || start < 0.0 || start.is_nan() || end.is_nan() {
+            return Err(ParseError::InvalidM3U8(format!(
+                "Invalid clip range {} to {}",
+                start, end
+            )));
+        }
+        let kept: Vec<usize> = self
+            .cumulative_start_times()
+            .into_iter()
+            .zip(&self.segments)
+            .enumerate()
+            .filter(|(_, (offset, segment))| offset + segment.duration > start && *offset < end)
+            .map(|(index, _)| index)
+            .collect();
+        let (Some(&first), Some(&last)) = (kept.first(), kept.last()) else {
+            return Err(ParseError::InvalidM3U8(format!(
+                "No segments between {} and {}",
+                start, end
+            )));
+        };
+
+        let mut clip = M3U8 {
+            segments: self.segments[first..=last].to_vec(),
+            discontinuity_sequence: self.discontinuity_sequence
+                + self.segments[..first]
+                    .iter()
+                    .filter(|segment| segment.discontinuity)
+                    .count() as u64,
+            skip: None,
+            unknown_lines: self
+                .unknown_lines
+                .iter()
+                .filter(|unknown| {
+                    let header = unknown.segment_index == 0 && unknown.line.starts_with("#EXT");
+                    header || (first..=last).contains(&unknown.segment_index)
+                })
+                .map(|unknown| UnknownLine {
+                    segment_index: unknown.segment_index.saturating_sub(first),
+                    ..unknown.clone()
+                })
+                .collect(),
+            ..self.clone()
+        };
+        clip.media_sequence = clip.segments[0].sequence;
+        clip.finish_vod();
+        Ok(clip)
+    }
+
+    /// Joins VOD playlists into one, in order, with a discontinuity between sources
+    ///
+    /// Segments are renumbered from the media sequence of the first playlist. Each
+    /// segment keeps the keys of its source, so EXT-X-KEY tags are rewritten, or
+    /// cleared with METHOD=NONE, where sources change. EXT-X-TARGETDURATION becomes
+    /// the largest of the sources, and each source's EXT-X-MAP is written after the
+    /// discontinuity that starts it. Other header tags come from the first playlist;
+    /// comments and segment-level unknown tags of every source are kept.
+    pub fn concat(playlists: &[MediaPlaylist]) -> Result<MediaPlaylist, ParseError> {
+        let Some(first) = playlists.first() else {
+            return Err(ParseError::InvalidM3U8(
+                "No playlists to concatenate".to_string(),
+            ));
+        };
+        if playlists
+            .iter()
+            .any(|playlist| !playlist.variant_streams.is_empty())
+        {
+            return Err(ParseError::InvalidM3U8(
+                "Cannot concatenate master playlists".to_string(),
+            ));
+        }
+
+        let mut joined = M3U8 {
+            segments: vec![],
+            date_ranges: vec![],
+            unknown_lines: vec![],
+            skip: None,
+            ..first.clone()
+        };
+        for (source, playlist) in playlists.iter().enumerate() {
+            let offset = joined.segments.len();
+            joined.unknown_lines.extend(
+                playlist
+                    .unknown_lines
+                    .iter()
+                    .filter(|unknown| {
+                        source == 0
+                            || unknown.segment_index > 0
+                            || !unknown.line.starts_with("#EXT")
+                    })
+                    .map(|unknown| UnknownLine {
+                        segment_index: unknown.segment_index + offset,
+                        ..unknown.clone()
+                    }),
+            );
+            for (index, segment) in playlist.segments.iter().enumerate() {
+                let mut segment = segment.clone();
+                segment.sequence = joined.media_sequence + joined.segments.len() as u64;
+                if source > 0 && index == 0 {
+                    segment.discontinuity = true;
+                }
+                joined.segments.push(segment);
+            }
+            joined
+                .date_ranges
+                .extend(playlist.date_ranges.iter().cloned());
+            joined.version = joined.version.max(playlist.version);
+        }
+
+        joined.target_duration = joined
+            .segments
+            .iter()
+            .map(|segment| segment.duration.round() as u64)
+            .chain(playlists.iter().filter_map(MediaPlaylist::target_duration))
+            .max();
+        joined.finish_vod();
+        Ok(joined)
+    }
+
+    /// Drops live and low-latency state and ends the playlist
+    fn finish_vod(&mut self) {
+        self.skip = None;
+        self.server_control = None;
+        self.part_target = None;
+        self.pending_parts.clear();
+        self.pending_ad_cue = None;
+        self.preload_hints.clear();
+        self.rendition_reports.clear();
+        for segment in &mut self.segments {
+            segment.parts.clear();
+        }
+        self.end_list = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{MediaPlaylist, M3U8};
+
+    #[test]
+    /// Tests a clip keeps the covering segments, their key and their sequence numbers
+    fn it_clips_a_time_range() {
+        let body = "#EXTM3U\n#EXT-X-TARGETDURATION:4\n#EXT-X-MEDIA-SEQUENCE:10\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"k1\"\n\
+            #EXTINF:4,\na.ts\n#EXTINF:4,\nb.ts\n\
+            #EXT-X-DISCONTINUITY\n#EXTINF:4,\nc.ts\n\
+            # chapter two\n#EXTINF:4,\nd.ts\n#EXTINF:4,\ne.ts\n#EXT-X-ENDLIST\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let clip = parsed.clip(9.0, 13.0).unwrap();
+
+        let uris: Vec<&str> = clip
+            .segments()
+            .iter()
+            .map(|segment| segment.uri.as_str())
+            .collect();
+        assert_eq!(uris, vec!["c.ts", "d.ts"]);
+        assert_eq!(clip.media_sequence(), 12);
+        let written = clip.to_string();
+        assert!(written.contains("#EXT-X-TARGETDURATION:4\n#EXT-X-MEDIA-SEQUENCE:12\n"));
+        assert!(written.contains("#EXT-X-KEY:METHOD=AES-128,URI=\"k1\"\n"));
+        assert!(written.contains("# chapter two\n#EXTINF:4,\nd.ts\n"));
+        assert!(written.ends_with("#EXT-X-ENDLIST\n"));
+        assert_eq!(M3U8::from_body(&written).unwrap().segments().len(), 2);
+
+        assert!(parsed.clip(20.0, 30.0).is_err());
+        assert!(parsed.clip(5.0, 5.0).is_err());
+    }
+
+    #[test]
+    /// Tests a clip from the middle keeps the header tags and the init section
+    fn it_clips_fmp4_from_the_middle() {
+        let mut body = "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:4\n\
+            #EXT-X-PLAYLIST-TYPE:VOD\n#EXT-X-VENDOR-HEADER:1\n\
+            #EXT-X-MAP:URI=\"init.mp4\"\n"
+            .to_string();
+        for index in 0..4 {
+            body.push_str(&format!("#EXTINF:4,\n{}.m4s\n", index));
+        }
+        let parsed = M3U8::from_body(&body).unwrap();
+
+        let written = parsed.clip(5.0, 9.0).unwrap().to_string();
+
+        assert!(written.contains("#EXT-X-TARGETDURATION:4\n#EXT-X-PLAYLIST-TYPE:VOD\n"));
+        assert!(written
+            .contains("#EXT-X-VENDOR-HEADER:1\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:4,\n1.m4s\n"));
+        let reparsed = M3U8::from_body(&written).unwrap();
+        assert_eq!(reparsed.segments().len(), 2);
+        assert!(reparsed
+            .segments()
+            .iter()
+            .all(|segment| segment.map.is_some()));
+    }
+
+    #[test]
+    /// Tests sources are joined with discontinuities, renumbered and keep their keys
+    fn it_concatenates_playlists() {
+        let intro = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-TARGETDURATION:4\n#EXT-X-MEDIA-SEQUENCE:5\n\
+             #EXTINF:4,\nintro.ts\n#EXT-X-ENDLIST\n",
+        )
+        .unwrap();
+        let feature = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n\
+             #EXT-X-KEY:METHOD=AES-128,URI=\"k\"\n\
+             #EXTINF:10,\nf1.ts\n#EXTINF:10,\nf2.ts\n#EXT-X-ENDLIST\n",
+        )
+        .unwrap();
+
+        let joined = MediaPlaylist::concat(&[intro.clone(), feature, intro]).unwrap();
+
+        let sequences: Vec<u64> = joined
+            .segments()
+            .iter()
+            .map(|segment| segment.sequence)
+            .collect();
+        assert_eq!(sequences, vec![5, 6, 7, 8]);
+        let written = joined.to_string();
+        assert_eq!(written.matches("#EXT-X-TARGETDURATION:10\n").count(), 1);
+        assert_eq!(joined.target_duration(), Some(10));
+        assert!(written.contains("#EXT-X-VERSION:3\n"));
+        assert!(written.contains("#EXT-X-DISCONTINUITY\n#EXTINF:4,\nintro.ts\n#EXT-X-ENDLIST\n"));
+        assert!(written.contains("#EXT-X-KEY:METHOD=NONE\n"));
+        assert_eq!(written.matches("#EXT-X-DISCONTINUITY\n").count(), 2);
+
+        assert!(MediaPlaylist::concat(&[]).is_err());
+    }
+
+    #[test]
+    /// Tests header-level vendor tags come from the first source only
+    fn it_concatenates_vendor_headers() {
+        let source = |vendor: &str, uri: &str| {
+            M3U8::from_body(&format!(
+                "#EXTM3U\n#EXT-X-VENDOR:{}\n#EXTINF:4,\n{}\n#EXT-X-CUE:{}\n\
+                 #EXTINF:4,\n{}-2.ts\n#EXT-X-ENDLIST\n",
+                vendor, uri, vendor, vendor
+            ))
+            .unwrap()
+        };
+
+        let joined = MediaPlaylist::concat(&[source("a", "a.ts"), source("b", "b.ts")]).unwrap();
+
+        let written = joined.to_string();
+        assert!(written.contains("#EXT-X-VENDOR:a\n"));
+        assert!(!written.contains("#EXT-X-VENDOR:b"));
+        assert!(written.contains("#EXT-X-CUE:a\n"));
+        assert!(written.contains("#EXT-X-CUE:b\n#EXTINF:4,\nb-2.ts\n"));
+    }
+
+    #[test]
+    /// Tests each joined source keeps its own init section
+    fn it_concatenates_fmp4_playlists() {
+        let source = |init: &str, uri: &str| {
+            M3U8::from_body(&format!(
+                "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:4\n\
+                 #EXT-X-PLAYLIST-TYPE:VOD\n#EXT-X-MAP:URI=\"{}\"\n\
+                 #EXTINF:4,\n{}\n#EXT-X-ENDLIST\n",
+                init, uri
+            ))
+            .unwrap()
+        };
+
+        let joined = MediaPlaylist::concat(&[
+            source("init.mp4", "a.m4s"),
+            source("init2.mp4", "b.m4s"),
+            source("init2.mp4", "c.m4s"),
+        ])
+        .unwrap();
+
+        let written = joined.to_string();
+        assert_eq!(written.matches("#EXT-X-TARGETDURATION:4\n").count(), 1);
+        assert!(written.contains("#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:4,\na.m4s\n"));
+        assert!(written
+            .contains("#EXT-X-DISCONTINUITY\n#EXT-X-MAP:URI=\"init2.mp4\"\n#EXTINF:4,\nb.m4s\n"));
+        assert!(written
+            .contains("#EXT-X-DISCONTINUITY\n#EXT-X-MAP:URI=\"init2.mp4\"\n#EXTINF:4,\nc.m4s\n"));
+        let maps: Vec<String> = M3U8::from_body(&written)
+            .unwrap()
+            .segments()
+            .iter()
+            .map(|segment| segment.map.as_ref().unwrap().uri.clone())
+            .collect();
+        assert_eq!(maps, vec!["init.mp4", "init2.mp4", "init2.mp4"]);
+    }
+
+    #[test]
+    /// Tests durations and start offsets are summed over the segments
+    fn it_aggregates_segment_timing() {
+        let parsed =
+            M3U8::from_body("#EXTM3U\n#EXTINF:4,\na.ts\n#EXTINF:4.5,\nb.ts\n#EXTINF:2,\nc.ts\n")
+                .unwrap();
+
+        assert_eq!(parsed.segment_count(), 3);
+        assert_eq!(parsed.target_duration(), None);
+        assert_eq!(parsed.total_duration(), 10.5);
+        assert_eq!(parsed.cumulative_start_times(), vec![0.0, 4.0, 8.5]);
+
+        let empty = M3U8::from_body("#EXTM3U\n").unwrap();
+        assert_eq!(empty.total_duration(), 0.0);
+        assert!(empty.cumulative_start_times().is_empty());
+    }
+}
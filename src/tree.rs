@@ -0,0 +1,149 @@
+//! Master playlists fetched together with all of their media playlists.
+use std::collections::BTreeMap;
+
+use crate::{
+    FetchOptions, MasterPlaylist, MediaPlaylist, ParseError, PlaylistKind, Rendition,
+    VariantStream, M3U8,
+};
+
+/// A master playlist and every media playlist it references
+///
+/// Media playlists are keyed by their absolute URI, resolved against the URI of the
+/// master playlist, and are fetched once however many variants or renditions share
+/// them.
+#[derive(Debug, Clone)]
+pub struct PlaylistTree {
+    /// URI the master playlist was fetched from
+    pub uri: String,
+    pub master: MasterPlaylist,
+    pub media: BTreeMap<String, MediaPlaylist>,
+}
+
+/// Implementation for PlaylistTree
+impl PlaylistTree {
+    /// Returns the media playlist of a variant stream
+    pub fn variant(&self, variant: &VariantStream) -> Option<&MediaPlaylist> {
+        self.media.get(&variant.absolute_uri(&self.master))
+    }
+
+    /// Returns the media playlist of a rendition, if it has one
+    pub fn rendition(&self, rendition: &Rendition) -> Option<&MediaPlaylist> {
+        self.media.get(&rendition.absolute_uri(&self.master)?)
+    }
+
+    /// Returns the absolute URIs of the media playlists a master playlist references
+    fn media_uris(master: &MasterPlaylist) -> Result<Vec<String>, ParseError> {
+        if master.kind() != PlaylistKind::Master {
+            return Err(ParseError::InvalidM3U8(format!(
+                "Invalid master playlist: {}",
+                master.base_uri().unwrap_or_default()
+            )));
+        }
+        let variants = master
+            .variant_streams
+            .iter()
+            .filter_map(|variant| variant.get("uri"));
+        let renditions = master
+            .media_tags
+            .iter()
+            .filter_map(|rendition| rendition.get("URI"));
+        let mut uris: Vec<String> = variants
+            .chain(renditions)
+            .map(|child| master.absolute_uri(child))
+            .collect();
+        uris.sort();
+        uris.dedup();
+        Ok(uris)
+    }
+}
+
+/// Implementation of recursive fetching for M3U8
+impl M3U8 {
+    /// Fetches a master playlist and each variant and rendition media playlist
+    pub fn resolve_tree(uri: &str) -> Result<PlaylistTree, ParseError> {
+        M3U8::resolve_tree_with_options(uri, &FetchOptions::default())
+    }
+
+    /// Like resolve_tree, but fetches every playlist with the given options
+    pub fn resolve_tree_with_options(
+        uri: &str,
+        options: &FetchOptions,
+    ) -> Result<PlaylistTree, ParseError> {
+        let master = M3U8::from_uri_with_options(uri, options)?;
+        let media = PlaylistTree::media_uris(&master)?
+            .into_iter()
+            .map(|child| {
+                let playlist = M3U8::from_uri_with_options(&child, options)?;
+                Ok((child, playlist))
+            })
+            .collect::<Result<_, ParseError>>()?;
+        Ok(PlaylistTree {
+            uri: uri.to_string(),
+            master,
+            media,
+        })
+    }
+
+    /// Like resolve_tree, but requests the playlists without blocking
+    #[cfg(feature = "async")]
+    pub async fn resolve_tree_async(uri: &str) -> Result<PlaylistTree, ParseError> {
+        let options = FetchOptions::default();
+        let master = M3U8::from_uri_with_options_async(uri, &options).await?;
+        let mut media = BTreeMap::new();
+        for child in PlaylistTree::media_uris(&master)? {
+            let playlist = M3U8::from_uri_with_options_async(&child, &options).await?;
+            media.insert(child, playlist);
+        }
+        Ok(PlaylistTree {
+            uri: uri.to_string(),
+            master,
+            media,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+
+    use crate::testing::{serve_fixture, FixtureDir};
+    use crate::M3U8;
+
+    #[test]
+    /// Tests every variant and rendition playlist is fetched once
+    fn it_resolves_a_playlist_tree() {
+        let dir = FixtureDir::new("tree").unwrap();
+        std::fs::create_dir_all(dir.join("video")).unwrap();
+        std::fs::create_dir_all(dir.join("audio")).unwrap();
+        std::fs::write(
+            dir.join("video/master.m3u8"),
+            "#EXTM3U\n\
+             #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aud\",NAME=\"en\",URI=\"../audio/en.m3u8\"\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=800000,AUDIO=\"aud\"\nlow.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=2000000,AUDIO=\"aud\"\nhigh.m3u8\n",
+        )
+        .unwrap();
+        for path in ["video/low.m3u8", "video/high.m3u8", "audio/en.m3u8"] {
+            std::fs::write(
+                dir.join(path),
+                format!("#EXTM3U\n#EXTINF:4,\n{}.ts\n", path),
+            )
+            .unwrap();
+        }
+        let (url, guard) = serve_fixture(&dir).unwrap();
+
+        let tree = M3U8::resolve_tree(&format!("{}/video/master.m3u8", url)).unwrap();
+
+        assert_eq!(tree.media.len(), 3);
+        assert_eq!(guard.requests(), 4);
+        let variants = tree.master.variants().unwrap();
+        assert_eq!(
+            tree.variant(&variants[1]).unwrap().segments()[0].uri,
+            "video/high.m3u8.ts"
+        );
+        let renditions = tree.master.renditions().unwrap();
+        assert!(tree.rendition(&renditions[0]).is_some());
+        assert!(tree.media.contains_key(&format!("{}/audio/en.m3u8", url)));
+
+        assert!(M3U8::resolve_tree(&format!("{}/audio/en.m3u8", url)).is_err());
+    }
+}
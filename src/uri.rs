@@ -0,0 +1,303 @@
+//! Resolving relative references against a base URI, as in RFC 3986 section 5.
+use crate::{EncryptionKey, Rendition, Segment, VariantStream, M3U8};
+
+/// A URI split into its five components
+struct Components<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+/// Splits a URI reference into its components, as in RFC 3986 appendix B
+fn split(uri: &str) -> Components<'_> {
+    let (rest, fragment) = match uri.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (uri, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (scheme, rest) = match rest.split_once(':') {
+        Some((scheme, rest)) if !scheme.is_empty() && !scheme.contains('/') => (Some(scheme), rest),
+        _ => (None, rest),
+    };
+    let (authority, path) = match rest.strip_prefix("//") {
+        Some(rest) => {
+            let end = rest.find('/').unwrap_or(rest.len());
+            (Some(&rest[..end]), &rest[end..])
+        }
+        None => (None, rest),
+    };
+    Components {
+        scheme,
+        authority,
+        path,
+        query,
+        fragment,
+    }
+}
+
+/// Removes `.` and `..` segments from a path
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output: Vec<&str> = vec![];
+    while !input.is_empty() {
+        if let Some(rest) = input
+            .strip_prefix("../")
+            .or_else(|| input.strip_prefix("./"))
+        {
+            input = rest;
+        } else if input.starts_with("/./") || input == "/." {
+            input = &input[2..];
+            if input.is_empty() {
+                input = "/";
+            }
+        } else if input.starts_with("/../") || input == "/.." {
+            input = &input[3..];
+            if input.is_empty() {
+                input = "/";
+            }
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..]
+                .find('/')
+                .map_or(input.len(), |end| end + start);
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output.concat()
+}
+
+/// Resolves a URI reference against a base URI
+pub(crate) fn resolve(base: &str, reference: &str) -> String {
+    let base = split(base);
+    let reference = split(reference);
+    let (scheme, authority, path, query) = if reference.scheme.is_some() {
+        (
+            reference.scheme,
+            reference.authority,
+            remove_dot_segments(reference.path),
+            reference.query,
+        )
+    } else if reference.authority.is_some() {
+        (
+            base.scheme,
+            reference.authority,
+            remove_dot_segments(reference.path),
+            reference.query,
+        )
+    } else if reference.path.is_empty() {
+        (
+            base.scheme,
+            base.authority,
+            base.path.to_string(),
+            reference.query.or(base.query),
+        )
+    } else if reference.path.starts_with('/') {
+        (
+            base.scheme,
+            base.authority,
+            remove_dot_segments(reference.path),
+            reference.query,
+        )
+    } else {
+        let merged = if base.authority.is_some() && base.path.is_empty() {
+            format!("/{}", reference.path)
+        } else {
+            let directory = base.path.rfind('/').map_or("", |end| &base.path[..=end]);
+            format!("{}{}", directory, reference.path)
+        };
+        (
+            base.scheme,
+            base.authority,
+            remove_dot_segments(&merged),
+            reference.query,
+        )
+    };
+
+    let mut resolved = String::new();
+    if let Some(scheme) = scheme {
+        resolved.push_str(scheme);
+        resolved.push(':');
+    }
+    if let Some(authority) = authority {
+        resolved.push_str("//");
+        resolved.push_str(authority);
+    }
+    resolved.push_str(&path);
+    if let Some(query) = query {
+        resolved.push('?');
+        resolved.push_str(query);
+    }
+    if let Some(fragment) = reference.fragment {
+        resolved.push('#');
+        resolved.push_str(fragment);
+    }
+    resolved
+}
+
+/// Implementation of relative URI resolution for M3U8
+impl M3U8 {
+    /// Returns the URI the playlist was loaded from, if known
+    pub fn base_uri(&self) -> Option<&str> {
+        self.base_uri.as_deref()
+    }
+
+    /// Sets the URI relative URIs are resolved against, for playlists parsed from a body
+    pub fn set_base_uri(&mut self, uri: &str) {
+        self.base_uri = Some(uri.to_string());
+    }
+
+    /// Resolves a URI from the playlist against its base URI
+    ///
+    /// Without a base URI the reference is returned unchanged.
+    pub fn absolute_uri(&self, relative: &str) -> String {
+        match &self.base_uri {
+            Some(base) => resolve(base, relative),
+            None => relative.to_string(),
+        }
+    }
+}
+
+/// Implementation of relative URI resolution for VariantStream
+impl VariantStream {
+    /// Returns the URI of the variant resolved against the playlist listing it
+    pub fn absolute_uri(&self, playlist: &M3U8) -> String {
+        playlist.absolute_uri(&self.uri)
+    }
+}
+
+/// Implementation of relative URI resolution for Rendition
+impl Rendition {
+    /// Returns the URI of the rendition resolved against the playlist listing it
+    pub fn absolute_uri(&self, playlist: &M3U8) -> Option<String> {
+        self.uri.as_ref().map(|uri| playlist.absolute_uri(uri))
+    }
+}
+
+/// Implementation of relative URI resolution for EncryptionKey
+impl EncryptionKey {
+    /// Returns the key URI resolved against the playlist listing it
+    pub fn absolute_uri(&self, playlist: &M3U8) -> Option<String> {
+        self.uri.as_ref().map(|uri| playlist.absolute_uri(uri))
+    }
+}
+
+/// Implementation of relative URI resolution for Segment
+impl Segment {
+    /// Returns the URI of the segment resolved against the playlist listing it
+    pub fn absolute_uri(&self, playlist: &M3U8) -> String {
+        playlist.absolute_uri(&self.uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::uri::resolve;
+    use crate::M3U8;
+
+    #[test]
+    /// Tests the reference resolution examples of RFC 3986 section 5.4
+    fn it_resolves_references() {
+        let base = "http://a/b/c/d;p?q";
+        let examples = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+        ];
+        for (reference, expected) in examples {
+            assert_eq!(resolve(base, reference), expected, "{}", reference);
+        }
+        assert_eq!(
+            resolve("https://cdn/live/master.m3u8?token=1", "720p/index.m3u8"),
+            "https://cdn/live/720p/index.m3u8"
+        );
+    }
+
+    #[test]
+    /// Tests playlist URIs resolve against the base URI of their playlist
+    fn it_resolves_against_the_base_uri() {
+        let mut master = M3U8::from_body(
+            "#EXTM3U\n\
+             #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aud\",NAME=\"en\",URI=\"/audio/en.m3u8\"\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=800000\nlow/index.m3u8\n",
+        )
+        .unwrap();
+        assert_eq!(master.base_uri(), None);
+        assert_eq!(master.absolute_uri("a.ts"), "a.ts");
+
+        master.set_base_uri("https://cdn.example/live/master.m3u8");
+        let variant = &master.variants().unwrap()[0];
+        assert_eq!(
+            variant.absolute_uri(&master),
+            "https://cdn.example/live/low/index.m3u8"
+        );
+        let rendition = &master.renditions().unwrap()[0];
+        assert_eq!(
+            rendition.absolute_uri(&master).as_deref(),
+            Some("https://cdn.example/audio/en.m3u8")
+        );
+
+        let dir = crate::testing::FixtureDir::new("base").unwrap();
+        let path = dir.join("media.m3u8");
+        std::fs::write(&path, "#EXTM3U\n#EXTINF:4,\nseg/a.ts\n").unwrap();
+        let media = M3U8::from_file(&path).unwrap();
+        assert_eq!(
+            media.segments()[0].absolute_uri(&media),
+            dir.join("seg/a.ts").to_string_lossy()
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    /// Tests redirected playlists resolve URIs against the URL that served them
+    fn it_resolves_against_the_redirected_url() {
+        let dir = crate::testing::FixtureDir::new("redirect").unwrap();
+        std::fs::create_dir_all(dir.join("cdn")).unwrap();
+        std::fs::write(dir.join("cdn/live.m3u8"), "#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+        let server = crate::testing::FixtureServer::new(&dir)
+            .redirect("/live.m3u8", "/cdn/live.m3u8?token=1")
+            .start()
+            .unwrap();
+
+        let parsed = M3U8::from_uri(&format!("{}/live.m3u8", server.url())).unwrap();
+
+        let served = format!("{}/cdn/live.m3u8?token=1", server.url());
+        assert_eq!(parsed.base_uri(), Some(served.as_str()));
+        assert_eq!(
+            parsed.segments()[0].absolute_uri(&parsed),
+            format!("{}/cdn/a.ts", server.url())
+        );
+    }
+}
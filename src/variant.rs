@@ -0,0 +1,644 @@
+//! Typed EXT-X-STREAM-INF variant streams.
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{ParseError, M3U8};
+
+/// Attribute keys mapped onto typed VariantStream fields
+const TYPED_KEYS: &[&str] = &[
+    "uri",
+    "BANDWIDTH",
+    "AVERAGE-BANDWIDTH",
+    "CODECS",
+    "RESOLUTION",
+    "FRAME-RATE",
+    "AUDIO",
+    "VIDEO",
+    "SUBTITLES",
+    "CLOSED-CAPTIONS",
+    "VIDEO-RANGE",
+    "HDCP-LEVEL",
+    "SCORE",
+    "PATHWAY-ID",
+];
+
+/// A missing or malformed attribute found while converting an attribute map
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AttributeError {
+    Missing(String),
+    Invalid { key: String, value: String },
+}
+
+/// AttributeError as a Display type, naming the attribute
+impl fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AttributeError::Missing(key) => write!(f, "missing {} attribute", key),
+            AttributeError::Invalid { key, value } => {
+                write!(f, "invalid {} attribute: {}", key, value)
+            }
+        }
+    }
+}
+
+/// Implementation of the standard Error trait for AttributeError
+impl std::error::Error for AttributeError {}
+
+/// Map an AttributeError to our Error Wrapper
+impl From<AttributeError> for ParseError {
+    fn from(err: AttributeError) -> ParseError {
+        match err {
+            AttributeError::Missing(key) => {
+                ParseError::InvalidM3U8(format!("Missing {} attribute", key))
+            }
+            AttributeError::Invalid { key, value } => {
+                ParseError::InvalidM3U8(format!("Invalid {} attribute: {}", key, value))
+            }
+        }
+    }
+}
+
+/// Video resolution from a RESOLUTION attribute, ordered by pixel count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Implementation for Resolution
+impl Resolution {
+    /// Returns the number of pixels in a frame
+    pub fn pixels(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+}
+
+/// Orders resolutions by pixel count, then by width
+impl Ord for Resolution {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.pixels()
+            .cmp(&other.pixels())
+            .then(self.width.cmp(&other.width))
+    }
+}
+
+/// Implementation of PartialOrd for Resolution, consistent with Ord
+impl PartialOrd for Resolution {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Implementation for Resolution
+impl FromStr for Resolution {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Resolution, Self::Err> {
+        let (width, height) = input.split_once(['x', 'X']).ok_or(())?;
+        Ok(Resolution {
+            width: width.trim().parse().map_err(|_| ())?,
+            height: height.trim().parse().map_err(|_| ())?,
+        })
+    }
+}
+
+/// Resolution as a Display type, writing `<width>x<height>`
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Dynamic range of a variant from its VIDEO-RANGE attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VideoRange {
+    Sdr,
+    /// Hybrid Log-Gamma HDR
+    Hlg,
+    /// Perceptual Quantizer HDR, as used by HDR10 and Dolby Vision
+    Pq,
+}
+
+/// Implementation for VideoRange
+impl VideoRange {
+    /// Whether the range is HDR
+    pub fn is_hdr(&self) -> bool {
+        *self != VideoRange::Sdr
+    }
+}
+
+/// Implementation for VideoRange
+impl FromStr for VideoRange {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<VideoRange, Self::Err> {
+        match input {
+            "SDR" => Ok(VideoRange::Sdr),
+            "HLG" => Ok(VideoRange::Hlg),
+            "PQ" => Ok(VideoRange::Pq),
+            _ => Err(()),
+        }
+    }
+}
+
+/// VideoRange as a Display type, writing the attribute value
+impl fmt::Display for VideoRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VideoRange::Sdr => write!(f, "SDR"),
+            VideoRange::Hlg => write!(f, "HLG"),
+            VideoRange::Pq => write!(f, "PQ"),
+        }
+    }
+}
+
+/// Output protection a variant requires from its HDCP-LEVEL attribute, ordered
+/// from least to most demanding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HdcpLevel {
+    None,
+    Type0,
+    /// HDCP 2.2 or later, usually required for UHD
+    Type1,
+}
+
+/// Implementation for HdcpLevel
+impl FromStr for HdcpLevel {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<HdcpLevel, Self::Err> {
+        match input {
+            "NONE" => Ok(HdcpLevel::None),
+            "TYPE-0" => Ok(HdcpLevel::Type0),
+            "TYPE-1" => Ok(HdcpLevel::Type1),
+            _ => Err(()),
+        }
+    }
+}
+
+/// HdcpLevel as a Display type, writing the attribute value
+impl fmt::Display for HdcpLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HdcpLevel::None => write!(f, "NONE"),
+            HdcpLevel::Type0 => write!(f, "TYPE-0"),
+            HdcpLevel::Type1 => write!(f, "TYPE-1"),
+        }
+    }
+}
+
+/// Closed captions of a variant from its CLOSED-CAPTIONS attribute
+///
+/// Attribute values are stored unquoted, so a group quoted as `"NONE"` reads as
+/// `ClosedCaptions::None`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClosedCaptions {
+    /// The enumerated value NONE, declaring the variant has no captions
+    None,
+    /// GROUP-ID of the closed caption renditions
+    Group(String),
+}
+
+/// Implementation for ClosedCaptions
+impl ClosedCaptions {
+    /// Converts an attribute value
+    pub fn from_value(value: &str) -> ClosedCaptions {
+        match value {
+            "NONE" => ClosedCaptions::None,
+            group_id => ClosedCaptions::Group(group_id.to_string()),
+        }
+    }
+
+    /// Returns the GROUP-ID, if a group is named
+    pub fn group_id(&self) -> Option<&str> {
+        match self {
+            ClosedCaptions::None => None,
+            ClosedCaptions::Group(group_id) => Some(group_id),
+        }
+    }
+}
+
+/// ClosedCaptions as a Display type, writing the unquoted attribute value
+impl fmt::Display for ClosedCaptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClosedCaptions::None => write!(f, "NONE"),
+            ClosedCaptions::Group(group_id) => write!(f, "{}", group_id),
+        }
+    }
+}
+
+/// A variant stream from EXT-X-STREAM-INF and the URI line after it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VariantStream {
+    pub uri: String,
+    /// Peak bits per second
+    pub bandwidth: u64,
+    pub average_bandwidth: Option<u64>,
+    pub codecs: Vec<String>,
+    pub resolution: Option<Resolution>,
+    pub frame_rate: Option<f64>,
+    /// GROUP-ID of the audio renditions
+    pub audio: Option<String>,
+    /// GROUP-ID of the video renditions
+    pub video: Option<String>,
+    /// GROUP-ID of the subtitle renditions
+    pub subtitles: Option<String>,
+    pub closed_captions: Option<ClosedCaptions>,
+    /// Dynamic range, where absent means SDR
+    pub video_range: Option<VideoRange>,
+    pub hdcp_level: Option<HdcpLevel>,
+    /// Author preference among variants, higher is better
+    pub score: Option<f64>,
+    /// Content Steering pathway serving the variant
+    pub pathway_id: Option<String>,
+    /// Attributes without a typed field, keyed by attribute name
+    pub extras: HashMap<String, String>,
+}
+
+/// Implementation for VariantStream
+impl VariantStream {
+    /// Converts an attribute map, as returned by `get_variant_streams`, with the URI
+    /// under the `uri` key
+    pub fn from_map(map: &HashMap<String, String>) -> Result<VariantStream, AttributeError> {
+        fn parse<T: FromStr>(
+            map: &HashMap<String, String>,
+            key: &str,
+        ) -> Result<Option<T>, AttributeError> {
+            map.get(key)
+                .map(|value| {
+                    value.trim().parse().map_err(|_| AttributeError::Invalid {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    })
+                })
+                .transpose()
+        }
+        let uri = map
+            .get("uri")
+            .ok_or_else(|| AttributeError::Missing("uri".to_string()))?;
+        Ok(VariantStream {
+            uri: uri.to_string(),
+            bandwidth: parse(map, "BANDWIDTH")?
+                .ok_or_else(|| AttributeError::Missing("BANDWIDTH".to_string()))?,
+            average_bandwidth: parse(map, "AVERAGE-BANDWIDTH")?,
+            codecs: map
+                .get("CODECS")
+                .map(|codecs| {
+                    codecs
+                        .split(',')
+                        .map(|codec| codec.trim().to_string())
+                        .filter(|codec| !codec.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            resolution: parse(map, "RESOLUTION")?,
+            frame_rate: parse(map, "FRAME-RATE")?,
+            audio: map.get("AUDIO").cloned(),
+            video: map.get("VIDEO").cloned(),
+            subtitles: map.get("SUBTITLES").cloned(),
+            closed_captions: map
+                .get("CLOSED-CAPTIONS")
+                .map(|value| ClosedCaptions::from_value(value)),
+            video_range: parse(map, "VIDEO-RANGE")?,
+            hdcp_level: parse(map, "HDCP-LEVEL")?,
+            score: parse(map, "SCORE")?,
+            pathway_id: map.get("PATHWAY-ID").cloned(),
+            extras: map
+                .iter()
+                .filter(|(key, _)| !TYPED_KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        })
+    }
+
+    /// Whether the variant's VIDEO-RANGE is HDR
+    pub fn is_hdr(&self) -> bool {
+        self.video_range.is_some_and(|range| range.is_hdr())
+    }
+
+    /// Converts back to an attribute map, including extras and the `uri` key
+    pub fn to_map(&self) -> HashMap<String, String> {
+        let mut map = self.extras.clone();
+        map.insert("uri".to_string(), self.uri.to_string());
+        map.insert("BANDWIDTH".to_string(), self.bandwidth.to_string());
+        let optional = [
+            (
+                "AVERAGE-BANDWIDTH",
+                self.average_bandwidth.map(|value| value.to_string()),
+            ),
+            (
+                "CODECS",
+                Some(self.codecs.join(",")).filter(|codecs| !codecs.is_empty()),
+            ),
+            ("RESOLUTION", self.resolution.map(|value| value.to_string())),
+            (
+                "FRAME-RATE",
+                self.frame_rate.map(|value| format!("{:.3}", value)),
+            ),
+            ("AUDIO", self.audio.clone()),
+            ("VIDEO", self.video.clone()),
+            ("SUBTITLES", self.subtitles.clone()),
+            (
+                "CLOSED-CAPTIONS",
+                self.closed_captions.as_ref().map(|value| value.to_string()),
+            ),
+            (
+                "VIDEO-RANGE",
+                self.video_range.map(|value| value.to_string()),
+            ),
+            ("HDCP-LEVEL", self.hdcp_level.map(|value| value.to_string())),
+            ("SCORE", self.score.map(|value| value.to_string())),
+            ("PATHWAY-ID", self.pathway_id.clone()),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                map.insert(key.to_string(), value);
+            }
+        }
+        map
+    }
+}
+
+/// Implementation of typed variant access for M3U8
+impl M3U8 {
+    /// Returns the EXT-X-STREAM-INF variant streams as typed values, in playlist order
+    pub fn variants(&self) -> Result<Vec<VariantStream>, AttributeError> {
+        self.iter_variants().collect()
+    }
+
+    /// Converts variant streams one at a time, in playlist order
+    pub fn iter_variants(
+        &self,
+    ) -> impl Iterator<Item = Result<VariantStream, AttributeError>> + '_ {
+        self.variant_streams.iter().map(VariantStream::from_map)
+    }
+
+    /// Returns the variants with an HDR VIDEO-RANGE, in playlist order
+    ///
+    /// Variants whose attributes cannot be parsed are skipped.
+    pub fn hdr_variants(&self) -> Vec<VariantStream> {
+        self.iter_variants()
+            .filter_map(Result::ok)
+            .filter(|variant| variant.is_hdr())
+            .collect()
+    }
+
+    /// Returns the SDR variants, including those without VIDEO-RANGE, in playlist order
+    ///
+    /// Variants whose attributes cannot be parsed are skipped.
+    pub fn sdr_variants(&self) -> Vec<VariantStream> {
+        self.iter_variants()
+            .filter_map(Result::ok)
+            .filter(|variant| !variant.is_hdr())
+            .collect()
+    }
+
+    /// Removes variants duplicating an earlier one, returning how many were removed
+    ///
+    /// A variant is a duplicate when its attributes only differ by PATHWAY-ID, or when
+    /// it has the same URI, BANDWIDTH and CODECS. The first listed copy is kept.
+    pub fn dedup_variants(&mut self) -> usize {
+        let before = self.variant_streams.len();
+        let mut kept: Vec<HashMap<String, String>> = vec![];
+        self.variant_streams_mut().retain(|variant| {
+            let duplicate = kept.iter().any(|earlier| {
+                let same = |key: &str| variant.get(key) == earlier.get(key);
+                let all_but_pathway = variant
+                    .keys()
+                    .chain(earlier.keys())
+                    .all(|key| key == "PATHWAY-ID" || same(key));
+                all_but_pathway || (same("uri") && same("BANDWIDTH") && same("CODECS"))
+            });
+            if !duplicate {
+                kept.push(variant.clone());
+            }
+            !duplicate
+        });
+        before - self.variant_streams.len()
+    }
+
+    /// Whether the master playlist declares it carries no closed captions
+    ///
+    /// True when there are variant streams and every one has CLOSED-CAPTIONS=NONE,
+    /// which the specification requires once any of them does.
+    pub fn declares_no_closed_captions(&self) -> bool {
+        !self.variant_streams.is_empty()
+            && self
+                .variant_streams
+                .iter()
+                .all(|variant| variant.get("CLOSED-CAPTIONS").map(String::as_str) == Some("NONE"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        AttributeError, ClosedCaptions, HdcpLevel, Resolution, VariantStream, VideoRange, M3U8,
+    };
+
+    #[test]
+    /// Tests attribute maps convert to typed variants and back, keeping unknown keys
+    fn it_converts_variant_maps() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.64001f,mp4a.40.2\",\
+            RESOLUTION=1280x720,FRAME-RATE=29.970,AUDIO=\"aac\",HDCP-LEVEL=NONE\nhigh.m3u8\n";
+        let mut parsed = M3U8::from_body(body).unwrap();
+        let map = &parsed.get_variant_streams("BANDWIDTH")[0];
+
+        let variant = VariantStream::from_map(map).unwrap();
+
+        assert_eq!(variant.uri, "high.m3u8");
+        assert_eq!(variant.bandwidth, 2_000_000);
+        assert_eq!(variant.codecs, vec!["avc1.64001f", "mp4a.40.2"]);
+        assert_eq!(
+            variant.resolution,
+            Some(Resolution {
+                width: 1280,
+                height: 720
+            })
+        );
+        assert_eq!(variant.frame_rate, Some(29.97));
+        assert_eq!(variant.audio.as_deref(), Some("aac"));
+        assert_eq!(variant.hdcp_level, Some(HdcpLevel::None));
+        assert_eq!(&variant.to_map(), map);
+
+        let mut invalid = map.clone();
+        invalid.insert("BANDWIDTH".to_string(), "fast".to_string());
+        assert_eq!(
+            VariantStream::from_map(&invalid),
+            Err(AttributeError::Invalid {
+                key: "BANDWIDTH".to_string(),
+                value: "fast".to_string()
+            })
+        );
+        invalid.remove("uri");
+        assert_eq!(
+            VariantStream::from_map(&invalid),
+            Err(AttributeError::Missing("uri".to_string()))
+        );
+    }
+
+    #[test]
+    /// Tests typed variants compare bandwidth numerically and report malformed attributes
+    fn it_returns_typed_variants() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=900000,AUDIO=\"aac\",SUBTITLES=\"subs\"\nlow.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=10000000,RESOLUTION=1920x1080\nhigh.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let mut variants = parsed.variants().unwrap();
+        variants.sort_by_key(|variant| variant.bandwidth);
+
+        assert_eq!(variants[0].uri, "low.m3u8");
+        assert_eq!(variants[0].audio.as_deref(), Some("aac"));
+        assert_eq!(variants[0].subtitles.as_deref(), Some("subs"));
+        assert_eq!(variants[1].bandwidth, 10_000_000);
+
+        let malformed =
+            M3U8::from_body("#EXTM3U\n#EXT-X-STREAM-INF:RESOLUTION=640x360\nlow.m3u8\n").unwrap();
+        assert_eq!(
+            malformed.variants(),
+            Err(AttributeError::Missing("BANDWIDTH".to_string()))
+        );
+    }
+
+    #[test]
+    /// Tests resolutions parse from attributes and order by pixel count
+    fn it_orders_resolutions_by_pixels() {
+        let portrait: Resolution = "720x1280".parse().unwrap();
+        let small: Resolution = "1000x100".parse().unwrap();
+        let hd: Resolution = "1280X720".parse().unwrap();
+
+        assert!(small < portrait);
+        assert!(portrait < hd);
+        assert_eq!(hd.pixels(), 921_600);
+        assert_eq!(hd.to_string(), "1280x720");
+        assert!("1280".parse::<Resolution>().is_err());
+        assert!("wide x tall".parse::<Resolution>().is_err());
+    }
+
+    #[test]
+    /// Tests variants and segments compose with iterator adapters
+    fn it_iterates_playlist_contents() {
+        let master = M3U8::from_body(
+            "#EXTM3U\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=800000\nlow.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=5000000\nhigh.m3u8\n\
+             #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\"\n",
+        )
+        .unwrap();
+        let high: Vec<String> = master
+            .iter_variants()
+            .filter_map(Result::ok)
+            .filter(|variant| variant.bandwidth > 1_000_000)
+            .map(|variant| variant.uri)
+            .collect();
+        assert_eq!(high, vec!["high.m3u8"]);
+        assert_eq!(master.iter_renditions().count(), 1);
+
+        let media = M3U8::from_body("#EXTM3U\n#EXTINF:4,\na.ts\n#EXTINF:6,\nb.ts\n").unwrap();
+        let total: f64 = (&media).into_iter().map(|segment| segment.duration).sum();
+        assert_eq!(total, 10.0);
+        let uris: Vec<String> = media.into_iter().map(|segment| segment.uri).collect();
+        assert_eq!(uris, vec!["a.ts", "b.ts"]);
+    }
+
+    #[test]
+    /// Tests CLOSED-CAPTIONS distinguishes NONE from a group id
+    fn it_types_closed_captions() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1,CLOSED-CAPTIONS=NONE\na.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2,CLOSED-CAPTIONS=\"cc\"\nb.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+        let variants = parsed.variants().unwrap();
+
+        assert_eq!(variants[0].closed_captions, Some(ClosedCaptions::None));
+        assert_eq!(
+            variants[1].closed_captions,
+            Some(ClosedCaptions::Group("cc".to_string()))
+        );
+        assert_eq!(
+            variants[1].closed_captions.as_ref().unwrap().group_id(),
+            Some("cc")
+        );
+        assert_eq!(variants[0].to_map()["CLOSED-CAPTIONS"], "NONE");
+        assert!(!parsed.declares_no_closed_captions());
+
+        let none = M3U8::from_body(
+            "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1,CLOSED-CAPTIONS=NONE\na.m3u8\n",
+        )
+        .unwrap();
+        assert!(none.declares_no_closed_captions());
+        assert!(none.to_string().contains("CLOSED-CAPTIONS=NONE\n"));
+        assert!(!M3U8::from_body("#EXTM3U\n")
+            .unwrap()
+            .declares_no_closed_captions());
+    }
+
+    #[test]
+    /// Tests VIDEO-RANGE and HDCP-LEVEL are typed and HDR variants can be filtered
+    fn it_filters_by_video_range() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1,VIDEO-RANGE=SDR,HDCP-LEVEL=NONE\nsdr.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2\nplain.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=3,VIDEO-RANGE=PQ,HDCP-LEVEL=TYPE-1\npq.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=4,VIDEO-RANGE=HLG,HDCP-LEVEL=TYPE-0\nhlg.m3u8\n";
+        let parsed = M3U8::from_body(body).unwrap();
+
+        let uris = |variants: Vec<VariantStream>| -> Vec<String> {
+            variants.into_iter().map(|variant| variant.uri).collect()
+        };
+
+        assert_eq!(uris(parsed.hdr_variants()), vec!["pq.m3u8", "hlg.m3u8"]);
+        assert_eq!(uris(parsed.sdr_variants()), vec!["sdr.m3u8", "plain.m3u8"]);
+        let variants = parsed.variants().unwrap();
+        assert_eq!(variants[2].video_range, Some(VideoRange::Pq));
+        assert!(variants[3].hdcp_level < variants[2].hdcp_level);
+        assert_eq!(variants[2].to_map()["HDCP-LEVEL"], "TYPE-1");
+
+        let invalid =
+            M3U8::from_body("#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1,VIDEO-RANGE=HDR\na.m3u8\n")
+                .unwrap();
+        assert_eq!(
+            invalid.variants(),
+            Err(AttributeError::Invalid {
+                key: "VIDEO-RANGE".to_string(),
+                value: "HDR".to_string()
+            })
+        );
+    }
+
+    #[test]
+    /// Tests variants repeated per pathway or listed twice are removed
+    fn it_dedups_variants() {
+        let body = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1,CODECS=\"avc1\",PATHWAY-ID=\"a\"\nlow.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1,CODECS=\"avc1\",PATHWAY-ID=\"b\"\nlow.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2,CODECS=\"avc1\",RESOLUTION=640x360\nhigh.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2,CODECS=\"avc1\",RESOLUTION=1280x720\nhigh.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2,CODECS=\"avc1\"\nhigh-b.m3u8\n";
+        let mut parsed = M3U8::from_body(body).unwrap();
+
+        assert_eq!(parsed.dedup_variants(), 2);
+
+        let kept: Vec<(String, Option<String>)> = parsed
+            .variants()
+            .unwrap()
+            .into_iter()
+            .map(|variant| (variant.uri, variant.pathway_id))
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                ("low.m3u8".to_string(), Some("a".to_string())),
+                ("high.m3u8".to_string(), None),
+                ("high-b.m3u8".to_string(), None),
+            ]
+        );
+        assert_eq!(parsed.dedup_variants(), 0);
+    }
+}
@@ -0,0 +1,132 @@
+//! Protocol version requirements of playlist features.
+use crate::{ParseError, M3U8};
+
+/// Implementation of EXT-X-VERSION requirements for M3U8
+impl M3U8 {
+    /// Returns the lowest EXT-X-VERSION covering the features this playlist uses
+    ///
+    /// Follows the compatibility rules of RFC 8216 and its revision: an IV needs 2,
+    /// floating point EXTINF durations 3, byte ranges and I-frame playlists 4,
+    /// KEYFORMAT 5, EXT-X-MAP 6 (5 in I-frame playlists), INSTREAM-ID SERVICE
+    /// channels 7, EXT-X-DEFINE 8 and EXT-X-SKIP 9, or 10 with removed date ranges.
+    pub fn required_version(&self) -> u8 {
+        let keys = || self.segments.iter().flat_map(|segment| &segment.keys);
+        let has_tag = |name: &str| {
+            self.unknown_lines
+                .iter()
+                .any(|unknown| M3U8::by_value(&unknown.line).0 == name)
+        };
+        let requirements = [
+            (keys().any(|key| key.iv.is_some()), 2),
+            (
+                self.segments
+                    .iter()
+                    .any(|segment| segment.duration.fract() != 0.0),
+                3,
+            ),
+            (
+                self.i_frames_only
+                    || self
+                        .segments
+                        .iter()
+                        .any(|segment| segment.byte_range.is_some()),
+                4,
+            ),
+            (
+                keys().any(|key| key.keyformat.is_some() || key.keyformat_versions.is_some()),
+                5,
+            ),
+            (
+                self.segments.iter().any(|segment| segment.map.is_some()),
+                if self.i_frames_only { 5 } else { 6 },
+            ),
+            (
+                self.media_tags.iter().any(|media| {
+                    media
+                        .get("INSTREAM-ID")
+                        .is_some_and(|id| id.starts_with("SERVICE"))
+                }),
+                7,
+            ),
+            (has_tag("#EXT-X-DEFINE"), 8),
+            (self.skip.is_some(), 9),
+            (
+                self.skip
+                    .as_ref()
+                    .is_some_and(|skip| !skip.recently_removed_dateranges.is_empty()),
+                10,
+            ),
+        ];
+        requirements
+            .iter()
+            .filter(|(used, _)| *used)
+            .map(|(_, version)| *version)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Fails when the declared EXT-X-VERSION is lower than `required_version`
+    pub fn check_version(&self) -> Result<(), ParseError> {
+        let required = self.required_version();
+        if self.version < required {
+            return Err(ParseError::InvalidM3U8(format!(
+                "EXT-X-VERSION {} is below the required version {}",
+                self.version, required
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::M3U8;
+
+    #[test]
+    /// Tests the required version follows the features in use
+    fn it_computes_required_versions() {
+        let version = |body: &str| M3U8::from_body(body).unwrap().required_version();
+
+        assert_eq!(version("#EXTM3U\n#EXTINF:4,\na.ts\n"), 1);
+        assert_eq!(version("#EXTM3U\n#EXTINF:4.5,\na.ts\n"), 3);
+        assert_eq!(
+            version("#EXTM3U\n#EXT-X-BYTERANGE:100@0\n#EXTINF:4,\na.ts\n"),
+            4
+        );
+        assert_eq!(
+            version(
+                "#EXTM3U\n#EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"skd://k\",KEYFORMAT=\"com.apple\"\n\
+                 #EXTINF:4,\na.ts\n"
+            ),
+            5
+        );
+        assert_eq!(
+            version("#EXTM3U\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:4,\na.m4s\n"),
+            6
+        );
+        assert_eq!(
+            version("#EXTM3U\n#EXT-X-SKIP:SKIPPED-SEGMENTS=3\n#EXTINF:4,\na.ts\n"),
+            9
+        );
+
+        let fmp4 = "#EXT-X-MAP:URI=\"i.mp4\"\n#EXTINF:4,\na.m4s\n";
+        let low = M3U8::from_body(&format!("#EXTM3U\n#EXT-X-VERSION:3\n{}", fmp4)).unwrap();
+        assert!(low.check_version().is_err());
+        let ok = M3U8::from_body(&format!("#EXTM3U\n#EXT-X-VERSION:7\n{}", fmp4)).unwrap();
+        assert!(ok.check_version().is_ok());
+    }
+
+    #[test]
+    /// Tests EXT-X-VERSION parses as a number, defaulting to 1
+    fn it_parses_numeric_versions() {
+        let parsed = M3U8::from_body("#EXTM3U\n#EXT-X-VERSION:7\n").unwrap();
+        assert_eq!(parsed.version(), 7);
+        assert!(parsed.to_string().contains("#EXT-X-VERSION:7\n"));
+        let unversioned = M3U8::from_body("#EXTM3U\n#EXTINF:4,\na.ts\n").unwrap();
+        assert_eq!(unversioned.version(), 1);
+        assert!(unversioned.to_string().contains("#EXT-X-VERSION:1\n"));
+        assert!(unversioned.check_version().is_ok());
+        assert!(M3U8::from_body("#EXTM3U\n#EXT-X-VERSION:3.0\n").is_err());
+    }
+}
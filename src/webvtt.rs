@@ -0,0 +1,200 @@
+//! WebVTT subtitle segments and cue timing validation.
+use crate::{FetchOptions, ParseError, M3U8};
+
+/// Seconds a cue may start outside its segment window before it is flagged
+const CUE_TOLERANCE: f64 = 0.5;
+
+/// MPEG-TS presentation timestamps run at 90kHz
+const MPEGTS_CLOCK: f64 = 90_000.0;
+
+/// MPEG-TS presentation timestamps are 33 bits and wrap around
+const MPEGTS_ROLLOVER: u64 = 1 << 33;
+
+/// Mapping between MPEG-TS and WebVTT local time from the X-TIMESTAMP-MAP header
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimestampMap {
+    /// MPEG-TS timestamp, in 90kHz ticks, corresponding to `local`
+    pub mpegts: u64,
+    /// WebVTT cue time in seconds
+    pub local: f64,
+}
+
+/// Implementation for TimestampMap
+impl TimestampMap {
+    /// Finds the X-TIMESTAMP-MAP header of a WebVTT document
+    pub fn from_webvtt(text: &str) -> Option<TimestampMap> {
+        text.lines()
+            .take_while(|line| !line.contains("-->"))
+            .find_map(TimestampMap::parse)
+    }
+
+    /// Fetches a WebVTT segment and reads its X-TIMESTAMP-MAP header
+    pub fn fetch(uri: &str) -> Result<Option<TimestampMap>, ParseError> {
+        let body = FetchOptions::default().fetch(uri)?.body;
+        Ok(TimestampMap::from_webvtt(&body))
+    }
+
+    /// Converts a WebVTT cue time in seconds to an MPEG-TS timestamp
+    pub fn local_to_mpegts(&self, local: f64) -> u64 {
+        let ticks = ((local - self.local) * MPEGTS_CLOCK).round() as i64 + self.mpegts as i64;
+        ticks.rem_euclid(MPEGTS_ROLLOVER as i64) as u64
+    }
+
+    /// Converts an MPEG-TS timestamp to a WebVTT cue time in seconds
+    pub fn mpegts_to_local(&self, mpegts: u64) -> f64 {
+        let ticks = (mpegts as i64 - self.mpegts as i64) as f64;
+        self.local + ticks / MPEGTS_CLOCK
+    }
+
+    /// Parses `X-TIMESTAMP-MAP=MPEGTS:<n>,LOCAL:<time>`
+    fn parse(line: &str) -> Option<TimestampMap> {
+        let data = line.trim().strip_prefix("X-TIMESTAMP-MAP=")?;
+        let mut mpegts = None;
+        let mut local = None;
+        for item in data.split(',') {
+            match item.trim().split_once(':')? {
+                ("MPEGTS", value) => mpegts = value.parse().ok(),
+                ("LOCAL", value) => local = parse_timestamp(value),
+                _ => (),
+            }
+        }
+        Some(TimestampMap {
+            mpegts: mpegts?,
+            local: local?,
+        })
+    }
+
+    /// Converts a cue time to seconds on the MPEG-TS timeline
+    fn to_mpegts_seconds(self, local: f64) -> f64 {
+        local - self.local + self.mpegts as f64 / MPEGTS_CLOCK
+    }
+}
+
+/// Parses a WebVTT timestamp, `[hh:]mm:ss.ttt`, into seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let mut seconds = 0.0;
+    for part in value.trim().split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+/// Parses the `start --> end` timing line of every cue in a WebVTT document
+fn parse_cues(text: &str) -> Vec<(f64, f64)> {
+    text.lines()
+        .filter_map(|line| {
+            let (start, rest) = line.split_once("-->")?;
+            let end = rest.split_whitespace().next()?;
+            Some((parse_timestamp(start)?, parse_timestamp(end)?))
+        })
+        .collect()
+}
+
+/// A WebVTT cue that falls outside the window of the segment carrying it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueTimingIssue {
+    pub sequence: u64,
+    pub uri: String,
+    /// Cue start and end on the playlist timeline, in seconds
+    pub cue_start: f64,
+    pub cue_end: f64,
+    /// Segment window on the playlist timeline, in seconds
+    pub window_start: f64,
+    pub window_end: f64,
+}
+
+/// Implementation of subtitle validation for M3U8
+impl M3U8 {
+    /// Checks that the cues of fetched WebVTT segments start within their segment windows
+    ///
+    /// `bodies` holds the WebVTT text of each segment, in playlist order. Segments with an
+    /// X-TIMESTAMP-MAP are placed on the MPEG-TS timeline, where `mpegts_origin` is the
+    /// timestamp of the start of the playlist; other cues are taken as playlist times.
+    pub fn check_subtitle_cues(&self, bodies: &[&str], mpegts_origin: u64) -> Vec<CueTimingIssue> {
+        let origin = mpegts_origin as f64 / MPEGTS_CLOCK;
+        let mut issues = Vec::new();
+        let mut window_start = 0.0;
+        for (segment, body) in self.segments.iter().zip(bodies) {
+            let window_end = window_start + segment.duration;
+            let map = TimestampMap::from_webvtt(body);
+            for (start, end) in parse_cues(body) {
+                let (cue_start, cue_end) = match map {
+                    Some(map) => (
+                        map.to_mpegts_seconds(start) - origin,
+                        map.to_mpegts_seconds(end) - origin,
+                    ),
+                    None => (start, end),
+                };
+                if cue_start < window_start - CUE_TOLERANCE
+                    || cue_start > window_end + CUE_TOLERANCE
+                {
+                    issues.push(CueTimingIssue {
+                        sequence: segment.sequence,
+                        uri: segment.uri.to_string(),
+                        cue_start,
+                        cue_end,
+                        window_start,
+                        window_end,
+                    });
+                }
+            }
+            window_start = window_end;
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{TimestampMap, M3U8};
+
+    const SUBTITLES: &str = "#EXTM3U\n\
+        #EXTINF:10,\nsub0.vtt\n\
+        #EXTINF:10,\nsub1.vtt\n";
+
+    #[test]
+    /// Tests cues are placed with X-TIMESTAMP-MAP and drifting ones are flagged
+    fn it_flags_drifting_cues() {
+        let parsed = M3U8::from_body(SUBTITLES).unwrap();
+        let first = "WEBVTT\n\
+            X-TIMESTAMP-MAP=MPEGTS:900000,LOCAL:00:00:00.000\n\n\
+            1\n00:00:01.000 --> 00:00:04.000 align:start\nHello\n\n\
+            00:00:09.800 --> 00:00:11.000\nacross the boundary\n";
+        let second = "WEBVTT\n\
+            X-TIMESTAMP-MAP=LOCAL:00:00:00.000,MPEGTS:900000\n\n\
+            00:00:12.000 --> 00:00:14.000\nIn sync\n\n\
+            00:31.000 --> 00:33.000\nDrifted\n";
+
+        let issues = parsed.check_subtitle_cues(&[first, second], 900_000);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].uri, "sub1.vtt");
+        assert_eq!(issues[0].cue_start, 31.0);
+        assert_eq!(issues[0].window_start, 10.0);
+        assert_eq!(issues[0].window_end, 20.0);
+
+        let offset = parsed.check_subtitle_cues(&[first, second], 0);
+        assert_eq!(offset.len(), 4);
+    }
+
+    #[test]
+    /// Tests the timestamp map header is exposed and converts both ways
+    fn it_maps_mpegts_to_local_time() {
+        let text = "WEBVTT\nX-TIMESTAMP-MAP=LOCAL:00:00:10.000,MPEGTS:1800000\n\n\
+            00:00:10.000 --> 00:00:12.000\nX-TIMESTAMP-MAP=MPEGTS:0,LOCAL:00:00:00.000\n";
+
+        let map = TimestampMap::from_webvtt(text).unwrap();
+
+        assert_eq!(map.mpegts, 1_800_000);
+        assert_eq!(map.local, 10.0);
+        assert_eq!(map.local_to_mpegts(12.5), 2_025_000);
+        assert_eq!(map.mpegts_to_local(900_000), 0.0);
+        let early = TimestampMap {
+            mpegts: 0,
+            local: 1.0,
+        };
+        assert_eq!(early.local_to_mpegts(0.0), (1 << 33) - 90_000);
+        assert!(TimestampMap::from_webvtt("WEBVTT\n\n00:01.000 --> 00:02.000\n").is_none());
+    }
+}